@@ -1,5 +1,6 @@
 //! Functionality related to running `wasm-bindgen`.
 
+use build;
 use child;
 use command::build::{BuildProfile, Target};
 use failure::{self, ResultExt};
@@ -9,6 +10,8 @@ use semver;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
+const DENO_TARGET_UNSUPPORTED: &str = "Your current version of wasm-bindgen does not support the 'deno' target. Please update your project to wasm-bindgen version >= 0.2.64.";
+
 /// Run the `wasm-bindgen` CLI to generate bindings for the current crate's
 /// `.wasm`.
 pub fn wasm_bindgen_build(
@@ -18,48 +21,60 @@ pub fn wasm_bindgen_build(
     out_name: &Option<String>,
     disable_dts: bool,
     target: Target,
-    profile: BuildProfile,
+    profile: &BuildProfile,
 ) -> Result<(), failure::Error> {
-    let release_or_debug = match profile {
-        BuildProfile::Release | BuildProfile::Profiling => "release",
-        BuildProfile::Dev => "debug",
-    };
+    let bindgen_path = install::get_tool_path(install_status, Tool::WasmBindgen)?
+        .binary(&Tool::WasmBindgen.to_string())?;
+    let target_args = target_args(target, &bindgen_path)?;
+    let cmd = wasm_bindgen_command(
+        data,
+        &bindgen_path,
+        out_dir,
+        out_name,
+        disable_dts,
+        target,
+        &target_args,
+        profile,
+    );
+    child::run(cmd, "wasm-bindgen").context("Running the wasm-bindgen CLI")?;
+    Ok(())
+}
 
+/// The command that runs the wasm-bindgen CLI at `bindgen_path` over the
+/// crate's `.wasm` for `target`. `target_args` select the target, see
+/// `target_args`.
+pub fn wasm_bindgen_command(
+    data: &CrateData,
+    bindgen_path: &Path,
+    out_dir: &Path,
+    out_name: &Option<String>,
+    disable_dts: bool,
+    target: Target,
+    target_args: &[String],
+    profile: &BuildProfile,
+) -> Command {
     let out_dir = out_dir.to_str().unwrap();
 
-    let wasm_path = data
-        .target_directory()
-        .join("wasm32-unknown-unknown")
-        .join(release_or_debug)
-        .join(data.crate_name())
-        .with_extension("wasm");
+    let wasm_path = wasm_artifact_path(data, profile);
 
     let dts_arg = if disable_dts {
         "--no-typescript"
     } else {
         "--typescript"
     };
-    let bindgen_path = install::get_tool_path(install_status, Tool::WasmBindgen)?
-        .binary(&Tool::WasmBindgen.to_string())?;
 
-    let mut cmd = Command::new(&bindgen_path);
+    let mut cmd = Command::new(bindgen_path);
     cmd.arg(&wasm_path)
         .arg("--out-dir")
         .arg(out_dir)
-        .arg(dts_arg);
-
-    let target_arg = build_target_arg(target, &bindgen_path)?;
-    if supports_dash_dash_target(bindgen_path.to_path_buf())? {
-        cmd.arg("--target").arg(target_arg);
-    } else {
-        cmd.arg(target_arg);
-    }
+        .arg(dts_arg)
+        .args(target_args);
 
     if let Some(value) = out_name {
         cmd.arg("--out-name").arg(value);
     }
 
-    let profile = data.configured_profile(profile);
+    let profile = data.configured_target_profile(profile, target);
     if profile.wasm_bindgen_debug_js_glue() {
         cmd.arg("--debug");
     }
@@ -69,9 +84,26 @@ pub fn wasm_bindgen_build(
     if profile.wasm_bindgen_dwarf_debug_info() {
         cmd.arg("--keep-debug");
     }
+    cmd
+}
 
-    child::run(cmd, "wasm-bindgen").context("Running the wasm-bindgen CLI")?;
-    Ok(())
+/// The arguments that select `target` for the wasm-bindgen CLI at `cli_path`,
+/// which older versions of the CLI expect in a different form.
+pub fn target_args(target: Target, cli_path: &PathBuf) -> Result<Vec<String>, failure::Error> {
+    let target_arg = build_target_arg(target, cli_path)?;
+    if supports_dash_dash_target(cli_path.to_path_buf())? {
+        Ok(vec!["--target".to_string(), target_arg])
+    } else {
+        Ok(vec![target_arg])
+    }
+}
+
+/// The path of the `.wasm` file that `cargo build` produces for the crate with
+/// the given profile.
+pub fn wasm_artifact_path(data: &CrateData, profile: &BuildProfile) -> PathBuf {
+    build::artifact_dir(data, "wasm32-unknown-unknown", profile)
+        .join(data.crate_name())
+        .with_extension("wasm")
 }
 
 /// Check if the `wasm-bindgen` dependency is locally satisfied for the web target
@@ -84,6 +116,16 @@ fn supports_web_target(cli_path: &PathBuf) -> Result<bool, failure::Error> {
     Ok(cli_version >= expected_version)
 }
 
+/// Check if the `wasm-bindgen` dependency is locally satisfied for the deno target
+fn supports_deno_target(cli_path: &PathBuf) -> Result<bool, failure::Error> {
+    let cli_version = semver::Version::parse(&install::get_cli_version(
+        &install::Tool::WasmBindgen,
+        cli_path,
+    )?)?;
+    let expected_version = semver::Version::parse("0.2.64")?;
+    Ok(cli_version >= expected_version)
+}
+
 /// Check if the `wasm-bindgen` dependency is locally satisfied for the --target flag
 fn supports_dash_dash_target(cli_path: PathBuf) -> Result<bool, failure::Error> {
     let cli_version = semver::Version::parse(&install::get_cli_version(
@@ -97,6 +139,8 @@ fn supports_dash_dash_target(cli_path: PathBuf) -> Result<bool, failure::Error>
 fn build_target_arg(target: Target, cli_path: &PathBuf) -> Result<String, failure::Error> {
     if !supports_dash_dash_target(cli_path.to_path_buf())? {
         Ok(build_target_arg_legacy(target, cli_path)?)
+    } else if target == Target::Deno && !supports_deno_target(cli_path)? {
+        bail!(DENO_TARGET_UNSUPPORTED)
     } else {
         Ok(target.to_string())
     }
@@ -115,6 +159,8 @@ fn build_target_arg_legacy(target: Target, cli_path: &PathBuf) -> Result<String,
             }
         }
         Target::Bundler => "--browser",
+        Target::Deno => bail!(DENO_TARGET_UNSUPPORTED),
+        Target::Wasi => bail!("wasm-bindgen is not used for the 'wasi' target"),
     };
     Ok(target_arg.to_string())
 }
@@ -1,11 +1,11 @@
 //! Building a Rust crate into a `.wasm` binary.
 
 use child;
-use command::build::BuildProfile;
+use command::build::{BuildProfile, Target};
 use emoji;
 use failure::{Error, ResultExt};
-use manifest::Crate;
-use std::path::Path;
+use manifest::{Crate, CrateData};
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use std::str;
 use PBAR;
@@ -41,7 +41,8 @@ pub fn check_rustc_version() -> Result<String, Error> {
 }
 
 // from https://github.com/alexcrichton/proc-macro2/blob/79e40a113b51836f33214c6d00228934b41bd4ad/build.rs#L44-L61
-fn rustc_minor_version() -> Option<u32> {
+/// The minor version of the installed `rustc`, if it can be determined.
+pub fn rustc_minor_version() -> Option<u32> {
     macro_rules! otry {
         ($e:expr) => {
             match $e {
@@ -72,17 +73,36 @@ fn wasm_pack_local_version() -> Option<String> {
     Some(output.to_string())
 }
 
-/// Run `cargo build` targetting `wasm32-unknown-unknown`.
+/// Run `cargo build` targetting `wasm32-unknown-unknown`, or the WASI target
+/// for `Target::Wasi`.
 pub fn cargo_build_wasm(
     path: &Path,
-    profile: BuildProfile,
+    profile: &BuildProfile,
+    target: Target,
     extra_options: &[String],
 ) -> Result<(), Error> {
     let msg = format!("{}Compiling to Wasm...", emoji::CYCLONE);
     PBAR.info(&msg);
 
+    let cmd = cargo_build_wasm_command(path, profile, target, extra_options);
+    child::run(cmd, "cargo build").context("Compiling your crate to WebAssembly failed")?;
+    Ok(())
+}
+
+/// The `cargo build` command that `cargo_build_wasm` runs.
+pub fn cargo_build_wasm_command(
+    path: &Path,
+    profile: &BuildProfile,
+    target: Target,
+    extra_options: &[String],
+) -> Command {
     let mut cmd = Command::new("cargo");
-    cmd.current_dir(path).arg("build").arg("--lib");
+    cmd.current_dir(path).arg("build");
+    // WASI builds are command-line tools rather than libraries.
+    match target {
+        Target::Wasi => cmd.arg("--bins"),
+        _ => cmd.arg("--lib"),
+    };
 
     if PBAR.quiet() {
         cmd.arg("--quiet");
@@ -90,11 +110,13 @@ pub fn cargo_build_wasm(
 
     match profile {
         BuildProfile::Profiling => {
-            // Once there are DWARF debug info consumers, force enable debug
-            // info, because builds that use the release cargo profile disables
-            // debug info.
-            //
-            // cmd.env("RUSTFLAGS", "-g");
+            // Force enable debug info, because builds that use the release
+            // cargo profile disable it. Configuring the profile, rather than
+            // passing `-g` in `RUSTFLAGS`, leaves the user's rustflags alone,
+            // including those from `.cargo/config`, which `RUSTFLAGS` would
+            // override.
+            cmd.env("CARGO_PROFILE_RELEASE_DEBUG", "true")
+                .env("CARGO_PROFILE_RELEASE_STRIP", "none");
             cmd.arg("--release");
         }
         BuildProfile::Release => {
@@ -104,15 +126,34 @@ pub fn cargo_build_wasm(
             // Plain cargo builds use the dev cargo profile, which includes
             // debug info by default.
         }
+        BuildProfile::Custom(name) => {
+            cmd.arg("--profile").arg(name);
+        }
     }
 
-    cmd.arg("--target").arg("wasm32-unknown-unknown");
+    cmd.arg("--target").arg(target.rust_target());
     cmd.args(extra_options);
-    child::run(cmd, "cargo build").context("Compiling your crate to WebAssembly failed")?;
-    Ok(())
+    cmd
+}
+
+/// The directory that `cargo build` writes the artifacts of the crate to, for
+/// the given Rust target and profile.
+pub fn artifact_dir(data: &CrateData, rust_target: &str, profile: &BuildProfile) -> PathBuf {
+    // Cargo writes the artifacts of a custom profile to a directory named
+    // after it, except for its built-in `test` and `bench` profiles, which
+    // share the directories of `dev` and `release`.
+    let profile_dir = match profile {
+        BuildProfile::Release | BuildProfile::Profiling => "release",
+        BuildProfile::Dev => "debug",
+        BuildProfile::Custom(name) if name == "test" => "debug",
+        BuildProfile::Custom(name) if name == "bench" => "release",
+        BuildProfile::Custom(name) => name,
+    };
+    data.target_directory().join(rust_target).join(profile_dir)
 }
 
-/// Runs `cargo build --tests` targeting `wasm32-unknown-unknown`.
+/// Runs `cargo build --tests` targeting `rust_target`, e.g.
+/// `wasm32-unknown-unknown`.
 ///
 /// This generates the `Cargo.lock` file that we use in order to know which version of
 /// wasm-bindgen-cli to use when running tests.
@@ -124,10 +165,12 @@ pub fn cargo_build_wasm(
 ///
 /// * `path`: Path to the crate directory to build tests.
 /// * `debug`: Whether to build tests in `debug` mode.
+/// * `rust_target`: The target to build tests for.
 /// * `extra_options`: Additional parameters to pass to `cargo` when building tests.
 pub fn cargo_build_wasm_tests(
     path: &Path,
     debug: bool,
+    rust_target: &str,
     extra_options: &[String],
 ) -> Result<(), Error> {
     let mut cmd = Command::new("cargo");
@@ -142,7 +185,7 @@ pub fn cargo_build_wasm_tests(
         cmd.arg("--release");
     }
 
-    cmd.arg("--target").arg("wasm32-unknown-unknown");
+    cmd.arg("--target").arg(rust_target);
 
     cmd.args(extra_options);
 
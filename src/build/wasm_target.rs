@@ -1,15 +1,17 @@
-//! Checking for the wasm32 target
+//! Checking for the wasm32 targets
 
 use child;
 use emoji;
 use failure::{Error, ResultExt};
 use log::info;
+use offline;
 use std::fmt;
 use std::path::PathBuf;
 use std::process::Command;
 use PBAR;
 
 struct Wasm32Check {
+    target: &'static str,
     rustc_path: PathBuf,
     sysroot: PathBuf,
     found: bool,
@@ -18,7 +20,7 @@ struct Wasm32Check {
 
 impl fmt::Display for Wasm32Check {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let target = "wasm32-unknown-unknown";
+        let target = self.target;
 
         if !self.found {
             let rustup_string = if self.is_rustup {
@@ -50,14 +52,14 @@ impl fmt::Display for Wasm32Check {
     }
 }
 
-/// Ensure that `rustup` has the `wasm32-unknown-unknown` target installed for
-/// current toolchain
-pub fn check_for_wasm32_target() -> Result<(), Error> {
+/// Ensure that `rustup` has the given wasm32 target, e.g.
+/// `wasm32-unknown-unknown`, installed for current toolchain
+pub fn check_for_wasm32_target(target: &'static str) -> Result<(), Error> {
     let msg = format!("{}Checking for the Wasm target...", emoji::TARGET);
     PBAR.info(&msg);
 
     // Check if wasm32 target is present, otherwise bail.
-    match check_wasm32_target() {
+    match check_wasm32_target(target) {
         Ok(ref wasm32_check) if wasm32_check.found => Ok(()),
         Ok(wasm32_check) => bail!("{}", wasm32_check),
         Err(err) => Err(err),
@@ -80,10 +82,32 @@ fn get_rustc_sysroot() -> Result<PathBuf, Error> {
     }
 }
 
-/// Checks if the wasm32-unknown-unknown is present in rustc's sysroot.
-fn is_wasm32_target_in_sysroot(sysroot: &PathBuf) -> bool {
-    let wasm32_target = "wasm32-unknown-unknown";
+/// rustc's sysroot, where the standard libraries of the installed targets
+/// live.
+pub struct Sysroot {
+    /// The path of the sysroot.
+    pub path: PathBuf,
+    /// Whether the sysroot belongs to a toolchain that `rustup` manages.
+    pub is_rustup: bool,
+}
+
+impl Sysroot {
+    /// Ask `rustc` for its sysroot.
+    pub fn find() -> Result<Sysroot, Error> {
+        let path = get_rustc_sysroot()?;
+        let is_rustup = path.to_string_lossy().contains("rustup");
+        Ok(Sysroot { path, is_rustup })
+    }
 
+    /// Whether `target` is installed in the sysroot. Unlike
+    /// `check_for_wasm32_target`, this never installs it.
+    pub fn has_target(&self, target: &str) -> bool {
+        is_wasm32_target_in_sysroot(&self.path, target)
+    }
+}
+
+/// Checks if the wasm32 target is present in rustc's sysroot.
+fn is_wasm32_target_in_sysroot(sysroot: &PathBuf, wasm32_target: &str) -> bool {
     let rustlib_path = sysroot.join("lib/rustlib");
 
     info!("Looking for {} in {:?}", wasm32_target, rustlib_path);
@@ -97,13 +121,14 @@ fn is_wasm32_target_in_sysroot(sysroot: &PathBuf) -> bool {
     }
 }
 
-fn check_wasm32_target() -> Result<Wasm32Check, Error> {
+fn check_wasm32_target(target: &'static str) -> Result<Wasm32Check, Error> {
     let sysroot = get_rustc_sysroot()?;
     let rustc_path = which::which("rustc")?;
 
-    // If wasm32-unknown-unknown already exists we're ok.
-    if is_wasm32_target_in_sysroot(&sysroot) {
+    // If the target already exists we're ok.
+    if is_wasm32_target_in_sysroot(&sysroot, target) {
         Ok(Wasm32Check {
+            target,
             rustc_path,
             sysroot,
             found: true,
@@ -112,9 +137,10 @@ fn check_wasm32_target() -> Result<Wasm32Check, Error> {
     // If it doesn't exist, then we need to check if we're using rustup.
     } else {
         // If sysroot contains "rustup", then we can assume we're using rustup
-        // and use rustup to add the wasm32-unknown-unknown target.
+        // and use rustup to add the target.
         if sysroot.to_string_lossy().contains("rustup") {
-            rustup_add_wasm_target().map(|()| Wasm32Check {
+            rustup_add_wasm_target(target).map(|()| Wasm32Check {
+                target,
                 rustc_path,
                 sysroot,
                 found: true,
@@ -122,6 +148,7 @@ fn check_wasm32_target() -> Result<Wasm32Check, Error> {
             })
         } else {
             Ok(Wasm32Check {
+                target,
                 rustc_path,
                 sysroot,
                 found: false,
@@ -131,11 +158,28 @@ fn check_wasm32_target() -> Result<Wasm32Check, Error> {
     }
 }
 
-/// Add wasm32-unknown-unknown using `rustup`.
-fn rustup_add_wasm_target() -> Result<(), Error> {
+/// Add the wasm32 target using `rustup`.
+fn rustup_add_wasm_target(target: &str) -> Result<(), Error> {
+    offline::ensure_online(&format!(
+        "add the {} target with rustup. Install it with `rustup target add {}`",
+        target, target
+    ))?;
     let mut cmd = Command::new("rustup");
-    cmd.arg("target").arg("add").arg("wasm32-unknown-unknown");
-    child::run(cmd, "rustup").context("Adding the wasm32-unknown-unknown target with rustup")?;
+    cmd.arg("target").arg("add").arg(target);
+    child::run(cmd, "rustup")
+        .with_context(|_| format!("Adding the {} target with rustup", target))?;
 
     Ok(())
 }
+
+/// The target triple that `--target wasi` builds for. Rust 1.78 renamed
+/// `wasm32-wasi` to `wasm32-wasip1`, and Rust 1.84 removed the old name.
+pub fn wasi_target() -> &'static str {
+    lazy_static! {
+        static ref WASI_TARGET: &'static str = match super::rustc_minor_version() {
+            Some(minor) if minor < 78 => "wasm32-wasi",
+            _ => "wasm32-wasip1",
+        };
+    }
+    *WASI_TARGET
+}
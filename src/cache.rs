@@ -1,7 +1,10 @@
 //! Getting and configuring wasm-pack's binary cache.
 
 use binary_install::Cache;
+use failure::ResultExt;
+use lock::FileLock;
 use std::env;
+use std::fs;
 use std::path::Path;
 
 /// Get wasm-pack's binary cache.
@@ -12,3 +15,13 @@ pub fn get_wasm_pack_cache() -> Result<Cache, failure::Error> {
         Cache::new("wasm-pack")
     }
 }
+
+/// Lock the entry `name` of `cache`, e.g. the directory of a tool, so that
+/// other wasm-pack processes wait until we're done `what`. See
+/// `FileLock::acquire`.
+pub fn lock(cache: &Cache, name: &str, what: Option<&str>) -> Result<FileLock, failure::Error> {
+    let dir = cache.join(Path::new(""));
+    fs::create_dir_all(&dir)
+        .with_context(|_| format!("failed to create wasm-pack's cache at {}", dir.display()))?;
+    FileLock::acquire(&dir.join(format!(".{}.lock", name)), what)
+}
@@ -0,0 +1,140 @@
+//! SHA-256 digests of the archives that wasm-pack downloads tools from, which
+//! are checked before an archive is extracted into the cache.
+//!
+//! The digest that an archive must have is taken from the
+//! `WASM_PACK_<TOOL>_SHA256` environment variable, e.g.
+//! `WASM_PACK_WASM_BINDGEN_SHA256`, then from the `[checksums.<tool>]` table of
+//! wasm-pack's config file, and then from the digests wasm-pack knows of.
+//! An archive without a known digest is installed with a warning, unless
+//! digests are required by `WASM_PACK_REQUIRE_CHECKSUMS` or by
+//! `require-checksums = true` in the config file.
+
+use config;
+use failure::{Error, Fail};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fmt;
+
+/// The digests of the archives of the default versions of the tools, as
+/// `(tool, version, target, digest)`.
+///
+/// Add the digests of a tool's archives here whenever its default version
+/// changes, taken from the archives on the tool's release page. An archive
+/// without a digest is installed unchecked, with a warning that shows its
+/// digest.
+pub const KNOWN_DIGESTS: &[(&str, &str, &str, &str)] = &[];
+
+/// The environment variable that makes installing an archive without a known
+/// digest an error.
+pub const REQUIRE_ENV_VAR: &str = "WASM_PACK_REQUIRE_CHECKSUMS";
+
+/// The environment variable with the digest of the archive of `tool`.
+pub fn env_var(tool: &str) -> String {
+    format!("WASM_PACK_{}_SHA256", tool.to_uppercase().replace('-', "_"))
+}
+
+/// Whether an archive without a known digest must not be installed, i.e.
+/// `WASM_PACK_REQUIRE_CHECKSUMS` is set to anything but an empty string, `0`
+/// or `false`, or the config file sets `require-checksums = true`.
+pub fn digests_required() -> Result<bool, Error> {
+    if let Ok(value) = env::var(REQUIRE_ENV_VAR) {
+        return Ok(!matches!(value.trim(), "" | "0" | "false"));
+    }
+    Ok(config::read()?.require_checksums)
+}
+
+/// The SHA-256 digest, in lowercase hex, that the archive of `version` of
+/// `tool` for `target` must have, if it is known.
+pub fn expected(tool: &str, version: &str, target: &str) -> Result<Option<String>, Error> {
+    expected_in(KNOWN_DIGESTS, tool, version, target)
+}
+
+/// Like `expected`, with `known` in place of the digests wasm-pack knows of.
+pub fn expected_in(
+    known: &[(&str, &str, &str, &str)],
+    tool: &str,
+    version: &str,
+    target: &str,
+) -> Result<Option<String>, Error> {
+    let var = env_var(tool);
+    if let Ok(digest) = env::var(&var) {
+        return parse_digest(&digest)
+            .map(Some)
+            .map_err(|e| format_err!("{}: {}", var, e));
+    }
+    let key = format!("{}-{}", version, target);
+    if let Some(digest) = config::read()?
+        .checksums
+        .get(tool)
+        .and_then(|digests| digests.get(&key))
+    {
+        return parse_digest(digest).map(Some).map_err(|e| {
+            format_err!(
+                "`{}` in `[checksums.{}]` of wasm-pack's config: {}",
+                key,
+                tool,
+                e
+            )
+        });
+    }
+    Ok(known
+        .iter()
+        .find(|&&(t, v, tr, _)| t == tool && v == version && tr == target)
+        .map(|&(_, _, _, digest)| digest.to_string()))
+}
+
+fn parse_digest(digest: &str) -> Result<String, Error> {
+    let digest = digest.trim().to_lowercase();
+    if digest.len() != 64 || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+        bail!(
+            "`{}` is not a SHA-256 digest, which is 64 hex digits",
+            digest
+        );
+    }
+    Ok(digest)
+}
+
+/// The error of an archive whose digest is not the expected one.
+#[derive(Debug)]
+pub struct Mismatch {
+    /// The URL the archive was downloaded from.
+    pub url: String,
+    /// The digest of the archive.
+    pub actual: String,
+    /// The digest the archive should have.
+    pub expected: String,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "the SHA-256 digest of {} is {}, but it should be {}. Refusing to install it.",
+            self.url, self.actual, self.expected
+        )
+    }
+}
+
+impl Fail for Mismatch {}
+
+/// Check that `data`, downloaded from `url`, has the digest `expected`.
+pub fn verify(data: &[u8], expected: &str, url: &str) -> Result<(), Error> {
+    let actual = sha256_hex(data);
+    if actual != expected {
+        return Err(Mismatch {
+            url: url.to_string(),
+            actual,
+            expected: expected.to_string(),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// The SHA-256 digest of `data`, in lowercase hex.
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
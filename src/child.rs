@@ -6,7 +6,9 @@
 use failure::Error;
 use install::Tool;
 use log::info;
+use std::io;
 use std::process::{Command, Stdio};
+use PBAR;
 
 /// Return a new Command object
 pub fn new_command(program: &str) -> Command {
@@ -28,6 +30,10 @@ pub fn new_command(program: &str) -> Command {
 pub fn run(mut command: Command, command_name: &str) -> Result<(), Error> {
     info!("Running {:?}", command);
 
+    // Keep stdout reserved for events when they are requested.
+    if PBAR.json() {
+        command.stdout(Stdio::from(io::stderr()));
+    }
     let status = command.status()?;
 
     if status.success() {
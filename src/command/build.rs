@@ -1,23 +1,37 @@
 //! Implementation of the `wasm-pack build` command.
 
 use crate::wasm_opt;
+use crate::wasm_snip;
 use binary_install::Cache;
 use bindgen;
 use build;
 use cache;
-use command::utils::{create_pkg_dir, get_crate_path};
+use command::utils::{create_pkg_dir, get_crate_path, run_step};
+use debug_info;
+use deno;
 use emoji;
 use failure::Error;
+use fingerprint::{Fingerprint, Fingerprints};
+use hooks::{self, HookPoint};
 use install::{self, InstallMode, Tool};
 use license;
 use lockfile::Lockfile;
 use log::info;
 use manifest;
+use plan::{DescribeStep, Plan, Planner, StepPlan};
+use progressbar::{ArtifactKind, Event};
 use readme;
+use serde_json;
+use size::{self, SizeReport};
+use std::env;
+use std::ffi::OsString;
 use std::fmt;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::time::Instant;
+use wasi;
+use watch;
 use PBAR;
 
 /// Everything required to configure and run the `wasm-pack build` command.
@@ -27,7 +41,7 @@ pub struct Build {
     pub crate_data: manifest::CrateData,
     pub scope: Option<String>,
     pub disable_dts: bool,
-    pub target: Target,
+    pub targets: Vec<Target>,
     pub profile: BuildProfile,
     pub mode: InstallMode,
     pub out_dir: PathBuf,
@@ -36,11 +50,18 @@ pub struct Build {
     pub bindgen: Option<install::Status>,
     pub cache: Cache,
     pub extra_options: Vec<String>,
+    pub watch: bool,
+    pub fingerprints: Fingerprints,
+    pub bindings_fingerprint: Option<String>,
+    pub members: Vec<Member>,
+    pub dry_run: bool,
+    pub size_report: bool,
+    pub compare: Option<SizeReport>,
 }
 
 /// What sort of output we're going to be generating and flags we're invoking
 /// `wasm-bindgen` with.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Target {
     /// Default output mode or `--target bundler`, indicates output will be
     /// used with a bundle in a later step.
@@ -55,6 +76,13 @@ pub enum Target {
     /// in a browser but pollutes the global namespace and must be manually
     /// instantiated.
     NoModules,
+    /// Correspond to `--target deno` where the output is an ES module for
+    /// Deno, packaged with a `mod.ts` and a `deno.json` rather than a
+    /// `package.json`.
+    Deno,
+    /// Correspond to `--target wasi` where the crate's binaries are compiled
+    /// to WASI instead, and packaged with launchers that run them in Node.js.
+    Wasi,
 }
 
 impl Default for Target {
@@ -63,6 +91,26 @@ impl Default for Target {
     }
 }
 
+impl Target {
+    /// Every target.
+    pub const ALL: &'static [Target] = &[
+        Target::Bundler,
+        Target::Web,
+        Target::Nodejs,
+        Target::NoModules,
+        Target::Deno,
+        Target::Wasi,
+    ];
+
+    /// The Rust target that the crate is compiled to for this target.
+    pub fn rust_target(self) -> &'static str {
+        match self {
+            Target::Wasi => build::wasm_target::wasi_target(),
+            _ => "wasm32-unknown-unknown",
+        }
+    }
+}
+
 impl fmt::Display for Target {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let s = match self {
@@ -70,6 +118,8 @@ impl fmt::Display for Target {
             Target::Web => "web",
             Target::Nodejs => "nodejs",
             Target::NoModules => "no-modules",
+            Target::Deno => "deno",
+            Target::Wasi => "wasi",
         };
         write!(f, "{}", s)
     }
@@ -83,14 +133,41 @@ impl FromStr for Target {
             "web" => Ok(Target::Web),
             "nodejs" => Ok(Target::Nodejs),
             "no-modules" => Ok(Target::NoModules),
+            "deno" => Ok(Target::Deno),
+            "wasi" => Ok(Target::Wasi),
             _ => bail!("Unknown target: {}", s),
         }
     }
 }
 
+/// A member of a workspace build, along with the options that it may set
+/// its own defaults for in `[package.metadata.wasm-pack]`.
+#[derive(Clone)]
+pub struct Member {
+    /// The member's crate.
+    pub crate_data: manifest::CrateData,
+    /// The directory that the member's package is written to.
+    pub out_dir: PathBuf,
+    /// The npm scope of the member's package, if any.
+    pub scope: Option<String>,
+    /// The name of the member's generated files, if not the crate's name.
+    pub out_name: Option<String>,
+    /// Whether to skip generating the member's TypeScript declarations.
+    pub disable_dts: bool,
+}
+
+/// Steps that a workspace build runs once for all members, rather than once
+/// per member.
+const WORKSPACE_STEPS: &[&str] = &[
+    "step_run_pre_build_hooks",
+    "step_check_rustc_version",
+    "step_check_for_wasm_target",
+    "step_build_wasm",
+];
+
 /// The build profile controls whether optimizations, debug info, and assertions
 /// are enabled or disabled.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub enum BuildProfile {
     /// Enable assertions and debug info. Disable optimizations.
     Dev,
@@ -98,10 +175,35 @@ pub enum BuildProfile {
     Release,
     /// Enable optimizations and debug info. Disable assertions.
     Profiling,
+    /// A custom cargo profile, defined in a `[profile.<name>]` table of the
+    /// workspace's `Cargo.toml`.
+    Custom(String),
+}
+
+impl BuildProfile {
+    /// Get the profile that `--profile <name>` selects.
+    pub fn from_name(name: &str) -> BuildProfile {
+        match name {
+            "dev" => BuildProfile::Dev,
+            "release" => BuildProfile::Release,
+            "profiling" => BuildProfile::Profiling,
+            name => BuildProfile::Custom(name.to_string()),
+        }
+    }
+
+    /// The name of the profile, e.g. `release`.
+    pub fn name(&self) -> &str {
+        match self {
+            BuildProfile::Dev => "dev",
+            BuildProfile::Release => "release",
+            BuildProfile::Profiling => "profiling",
+            BuildProfile::Custom(name) => name,
+        }
+    }
 }
 
 /// Everything required to configure and run the `wasm-pack build` command.
-#[derive(Debug, StructOpt)]
+#[derive(Clone, Debug, Default, StructOpt)]
 pub struct BuildOptions {
     /// The path to the Rust crate. If not set, searches up the path from the current directory.
     #[structopt(parse(from_os_str))]
@@ -120,9 +222,17 @@ pub struct BuildOptions {
     /// this flag will disable generating this TypeScript file.
     pub disable_dts: bool,
 
-    #[structopt(long = "target", short = "t", default_value = "bundler")]
-    /// Sets the target environment. [possible values: bundler, nodejs, web, no-modules]
-    pub target: Target,
+    #[structopt(long = "typescript")]
+    /// Generate the *.d.ts file even if `no-typescript` is set in the
+    /// crate's `[package.metadata.wasm-pack]`.
+    pub typescript: bool,
+
+    #[structopt(long = "target", short = "t", use_delimiter = true)]
+    /// Sets the target environment. Several comma-separated targets may be
+    /// given, in which case each is written to its own subdirectory of
+    /// `out-dir`. [possible values: bundler, nodejs, web, no-modules, deno, wasi]
+    /// [default: bundler]
+    pub target: Vec<Target>,
 
     #[structopt(long = "debug")]
     /// Deprecated. Renamed to `--dev`.
@@ -141,9 +251,15 @@ pub struct BuildOptions {
     /// Create a profiling build. Enable optimizations and debug info.
     pub profiling: bool,
 
-    #[structopt(long = "out-dir", short = "d", default_value = "pkg")]
-    /// Sets the output directory with a relative path.
-    pub out_dir: String,
+    #[structopt(long = "profile")]
+    /// Build with the given cargo profile, e.g. a custom `[profile.wasm-size]`
+    /// of the workspace's `Cargo.toml`. wasm-pack reads its settings for the
+    /// build from `[package.metadata.wasm-pack.profile.<name>]`.
+    pub profile: Option<String>,
+
+    #[structopt(long = "out-dir", short = "d")]
+    /// Sets the output directory with a relative path. [default: pkg]
+    pub out_dir: Option<String>,
 
     #[structopt(long = "out-name")]
     /// Sets the output file names. Defaults to package name.
@@ -153,48 +269,208 @@ pub struct BuildOptions {
     /// Won't generate package metadata. Instead adds output to existing package given by `out-dir`.
     pub is_child: bool,
 
+    #[structopt(long = "watch", short = "w")]
+    /// Keep running and rebuild whenever the crate's sources, its path
+    /// dependencies, its Cargo.toml or the workspace's Cargo.toml or
+    /// Cargo.lock change.
+    pub watch: bool,
+
+    #[structopt(long = "workspace")]
+    /// Build every member of the workspace with crate-type cdylib, and write an
+    /// npm package for each of them. In this mode `--out-dir` is relative to
+    /// the workspace root and may contain the `{name}` and `{dir}`
+    /// placeholders, for the package's name and its directory within the
+    /// workspace. Without placeholders, each package is written to
+    /// `<out-dir>/{name}`.
+    pub workspace: bool,
+
+    #[structopt(long = "package", short = "p")]
+    /// Only build the given workspace member. May be repeated. Implies a
+    /// workspace build.
+    pub package: Vec<String>,
+
+    #[structopt(long = "exclude")]
+    /// Exclude the given member from a `--workspace` build. May be repeated.
+    pub exclude: Vec<String>,
+
+    #[structopt(long = "size-report")]
+    /// Also write the size report of the generated wasm, which is always
+    /// printed, to `size-report.json` in the out dir.
+    pub size_report: bool,
+
+    #[structopt(long = "compare", parse(from_os_str))]
+    /// Compare the size of the generated wasm with a previous build, given as
+    /// its package directory or a `size-report.json` written by
+    /// `--size-report`. Passing the out dir itself compares with the build
+    /// that is about to be replaced.
+    pub compare: Option<PathBuf>,
+
+    #[structopt(long = "dry-run")]
+    /// Print what the build would do, i.e. the tools it would install, the
+    /// commands it would run and the files it would write, without doing any
+    /// of it. The plan is printed as JSON with `--message-format json`.
+    pub dry_run: bool,
+
     #[structopt(last = true)]
     /// List of extra options to pass to `cargo build`
     pub extra_options: Vec<String>,
 }
 
-impl Default for BuildOptions {
-    fn default() -> Self {
-        Self {
-            path: None,
-            scope: None,
-            mode: InstallMode::default(),
-            disable_dts: false,
-            target: Target::default(),
-            debug: false,
-            dev: false,
-            release: false,
-            profiling: false,
-            out_dir: String::new(),
-            out_name: None,
-            is_child: false,
-            extra_options: Vec::new(),
+impl BuildOptions {
+    /// Fill in the options that weren't given on the command line with the
+    /// crate's defaults from `[package.metadata.wasm-pack]`.
+    pub fn apply_defaults(&mut self, defaults: manifest::BuildDefaults) {
+        if self.target.is_empty() {
+            self.target = defaults.target;
+        }
+        if self.scope.is_none() {
+            self.scope = defaults.scope;
+        }
+        if self.out_dir.is_none() {
+            self.out_dir = defaults.out_dir;
+        }
+        if self.out_name.is_none() {
+            self.out_name = defaults.out_name;
+        }
+        if !self.disable_dts && !self.typescript {
+            self.disable_dts = defaults.no_typescript;
         }
+        if self.extra_options.is_empty() {
+            self.extra_options = defaults.extra_options;
+        }
+    }
+}
+
+/// A step of a build: how it runs, and how `--dry-run` describes it.
+#[derive(Clone, Copy)]
+pub struct BuildStep {
+    run: fn(&mut Build) -> Result<(), Error>,
+    describe: DescribeStep,
+}
+
+impl BuildStep {
+    /// Describe what the step would do for `member` of `build` in `step`.
+    pub fn describe(
+        &self,
+        planner: &mut Planner,
+        build: &Build,
+        member: &Member,
+        step: &mut StepPlan,
+    ) -> Result<(), Error> {
+        (self.describe)(planner, build, member, step)
     }
 }
 
-type BuildStep = fn(&mut Build) -> Result<(), Error>;
+/// The build steps of the given `Build` methods, which `Planner` has
+/// namesakes of to describe them.
+macro_rules! steps {
+    ($($name:ident),+) => {
+        {
+            let steps: Vec<(&'static str, BuildStep)> = vec![$((
+                stringify!($name),
+                BuildStep {
+                    run: Build::$name,
+                    describe: Planner::$name,
+                },
+            )),*];
+            steps
+        }
+    };
+    ($($name:ident,)*) => (steps![$($name),*])
+}
 
 impl Build {
     /// Construct a build command from the given options.
-    pub fn try_from_opts(build_opts: BuildOptions) -> Result<Self, Error> {
-        let crate_path = get_crate_path(build_opts.path)?;
-        let crate_data = manifest::CrateData::new(&crate_path, build_opts.out_name.clone())?;
-        let out_dir = crate_path.join(PathBuf::from(build_opts.out_dir));
+    pub fn try_from_opts(mut build_opts: BuildOptions) -> Result<Self, Error> {
+        let crate_path = get_crate_path(build_opts.path.take())?;
+        if build_opts.disable_dts && build_opts.typescript {
+            bail!("Can only supply one of the --typescript or --no-typescript flags");
+        }
+        // A workspace build applies the defaults of each member on top of
+        // these, so keep the options that were given on the command line.
+        let cli_opts = build_opts.clone();
+        let defaults = manifest::CrateData::build_defaults(&crate_path)?;
+        build_opts.apply_defaults(defaults.clone());
+        let out_dir = build_opts
+            .out_dir
+            .take()
+            .unwrap_or_else(|| "pkg".to_string());
+        if build_opts.target.is_empty() {
+            build_opts.target.push(Target::default());
+        }
+
+        let workspace = build_opts.workspace || !build_opts.package.is_empty();
+        if !build_opts.exclude.is_empty() && !build_opts.workspace {
+            bail!("--exclude can only be used together with --workspace");
+        }
+        let (crate_data, out_dir, members) = if workspace {
+            if build_opts.watch || build_opts.is_child || build_opts.compare.is_some() {
+                bail!(
+                    "--watch, --is-child and --compare cannot be combined with a workspace build"
+                );
+            }
+            let members = manifest::CrateData::workspace_members(
+                &crate_path,
+                &build_opts.package,
+                &build_opts.exclude,
+                None,
+            )?
+            .into_iter()
+            .map(|crate_data| Member::new(crate_data, &cli_opts, &defaults, &build_opts))
+            .collect::<Result<Vec<_>, Error>>()?;
+            let (crate_data, out_dir) = match members.first() {
+                Some(first) => (first.crate_data.clone(), first.out_dir.clone()),
+                None => bail!("The workspace has no members with crate-type cdylib to build"),
+            };
+            (crate_data, out_dir, members)
+        } else {
+            let crate_data = manifest::CrateData::new(&crate_path, build_opts.out_name.clone())?;
+            let out_dir = crate_path.join(PathBuf::from(out_dir));
+            (crate_data, out_dir, vec![])
+        };
 
         let dev = build_opts.dev || build_opts.debug;
-        let profile = match (dev, build_opts.release, build_opts.profiling) {
-            (false, false, false) | (false, true, false) => BuildProfile::Release,
-            (true, false, false) => BuildProfile::Dev,
-            (false, false, true) => BuildProfile::Profiling,
+        let profile = match (
+            dev,
+            build_opts.release,
+            build_opts.profiling,
+            &build_opts.profile,
+        ) {
+            (false, false, false, None) | (false, true, false, None) => BuildProfile::Release,
+            (true, false, false, None) => BuildProfile::Dev,
+            (false, false, true, None) => BuildProfile::Profiling,
+            (false, false, false, Some(name)) => BuildProfile::from_name(name),
             // Unfortunately, `structopt` doesn't expose clap's `conflicts_with`
             // functionality yet, so we have to implement it ourselves.
-            _ => bail!("Can only supply one of the --dev, --release, or --profiling flags"),
+            _ => bail!(
+                "Can only supply one of the --dev, --release, --profiling, or --profile flags"
+            ),
+        };
+
+        let mut targets: Vec<Target> = Vec::new();
+        for target in build_opts.target {
+            if !targets.contains(&target) {
+                targets.push(target);
+            }
+        }
+        if targets.len() > 1 && build_opts.is_child {
+            bail!("--is-child cannot be combined with multiple targets");
+        }
+        if targets.contains(&Target::Wasi)
+            && (targets.len() > 1 || workspace || build_opts.is_child)
+        {
+            bail!("--target wasi cannot be combined with other targets, a workspace build or --is-child");
+        }
+        if targets.contains(&Target::Deno) && (targets.len() > 1 || build_opts.is_child) {
+            bail!("--target deno cannot be combined with other targets or --is-child");
+        }
+
+        let fingerprints = Fingerprints::load(&out_dir);
+        // Read this before building, in case it is the out dir we are about to
+        // write to.
+        let compare = match &build_opts.compare {
+            Some(path) if !build_opts.dry_run => Some(SizeReport::for_comparison(path)?),
+            _ => None,
         };
 
         Ok(Build {
@@ -202,7 +478,7 @@ impl Build {
             crate_data,
             scope: build_opts.scope,
             disable_dts: build_opts.disable_dts,
-            target: build_opts.target,
+            targets,
             profile,
             mode: build_opts.mode,
             out_dir,
@@ -211,6 +487,13 @@ impl Build {
             bindgen: None,
             cache: cache::get_wasm_pack_cache()?,
             extra_options: build_opts.extra_options,
+            watch: build_opts.watch,
+            fingerprints,
+            bindings_fingerprint: None,
+            members,
+            dry_run: build_opts.dry_run,
+            size_report: build_opts.size_report,
+            compare,
         })
     }
 
@@ -219,15 +502,282 @@ impl Build {
         self.cache = cache;
     }
 
+    /// The directory that wasm-bindgen writes `target`'s output to. Builds with
+    /// a single target use `out_dir` directly, otherwise each target gets its
+    /// own subdirectory.
+    pub fn target_out_dir(&self, target: Target) -> PathBuf {
+        self.target_dir_in(&self.out_dir, target)
+    }
+
+    /// Like `target_out_dir`, for the out dir `out_dir` of any workspace
+    /// member.
+    pub fn target_dir_in(&self, out_dir: &Path, target: Target) -> PathBuf {
+        if self.targets.len() > 1 {
+            out_dir.join(target.to_string())
+        } else {
+            out_dir.to_path_buf()
+        }
+    }
+
+    /// Whether this builds the crate's binaries for WASI, rather than
+    /// generating bindings for its library.
+    pub fn is_wasi(&self) -> bool {
+        self.targets == [Target::Wasi]
+    }
+
+    /// Whether this generates a Deno module, which is configured by a
+    /// `deno.json` instead of a `package.json`.
+    pub fn is_deno(&self) -> bool {
+        self.targets == [Target::Deno]
+    }
+
+    /// The `.wasm` files that the build writes into the out dir, i.e. the
+    /// binaries for WASI, or the bindings' `<name>_bg.wasm` for each target.
+    pub fn wasm_files(&self) -> Vec<PathBuf> {
+        self.package_files(&self.crate_data, &self.out_dir).0
+    }
+
+    /// The `.wasm` files that the build writes for `target`.
+    pub fn target_wasm_files(&self, target: Target) -> Vec<PathBuf> {
+        self.target_wasm_files_in(&self.crate_data, &self.out_dir, target)
+    }
+
+    /// Like `target_wasm_files`, for the crate `crate_data` of any workspace
+    /// member and its out dir `out_dir`.
+    pub fn target_wasm_files_in(
+        &self,
+        crate_data: &manifest::CrateData,
+        out_dir: &Path,
+        target: Target,
+    ) -> Vec<PathBuf> {
+        let target_dir = self.target_dir_in(out_dir, target);
+        self.package_files(crate_data, out_dir)
+            .0
+            .into_iter()
+            .filter(|path| path.starts_with(&target_dir))
+            .collect()
+    }
+
+    /// The configured profile as it applies to `target`.
+    pub fn target_profile(&self, target: Target) -> manifest::CargoWasmPackProfile {
+        self.crate_data
+            .configured_target_profile(&self.profile, target)
+    }
+
+    /// The `.wasm` and JavaScript entry point files of the package that the
+    /// crate `crate_data` gets in `out_dir`.
+    fn package_files(
+        &self,
+        crate_data: &manifest::CrateData,
+        out_dir: &Path,
+    ) -> (Vec<PathBuf>, Vec<PathBuf>) {
+        if self.is_wasi() {
+            let bins = crate_data.bin_names();
+            return (
+                bins.iter()
+                    .map(|name| out_dir.join(wasi::wasm_file(name)))
+                    .collect(),
+                bins.iter()
+                    .map(|name| out_dir.join(wasi::launcher_file(name)))
+                    .collect(),
+            );
+        }
+        let name_prefix = crate_data.name_prefix();
+        let dirs: Vec<_> = self
+            .targets
+            .iter()
+            .map(|&target| self.target_dir_in(out_dir, target))
+            .collect();
+        (
+            dirs.iter()
+                .map(|dir| dir.join(format!("{}_bg.wasm", name_prefix)))
+                .collect(),
+            dirs.iter()
+                .map(|dir| dir.join(format!("{}.js", name_prefix)))
+                .collect(),
+        )
+    }
+
+    /// The environment variables that describe the build of `crate_data` into
+    /// `out_dir` to its hooks.
+    fn hook_env(
+        &self,
+        crate_data: &manifest::CrateData,
+        out_dir: &Path,
+    ) -> Result<Vec<(&'static str, OsString)>, Error> {
+        let targets: Vec<_> = self.targets.iter().map(|t| t.to_string()).collect();
+        let manifest = if self.is_deno() {
+            deno::CONFIG_FILE
+        } else {
+            "package.json"
+        };
+        let (wasm_files, js_files) = self.package_files(crate_data, out_dir);
+        Ok(vec![
+            ("WASM_PACK_CRATE_DIR", crate_data.manifest_dir().into()),
+            ("WASM_PACK_OUT_DIR", out_dir.into()),
+            ("WASM_PACK_TARGET", targets.join(",").into()),
+            ("WASM_PACK_PROFILE", self.profile.name().into()),
+            ("WASM_PACK_WASM", env::join_paths(wasm_files)?),
+            ("WASM_PACK_JS", env::join_paths(js_files)?),
+            ("WASM_PACK_PACKAGE_JSON", out_dir.join(manifest).into()),
+        ])
+    }
+
+    /// Run the current crate's hooks of `point`.
+    fn run_hooks(&self, point: HookPoint) -> Result<(), Error> {
+        let env = self.hook_env(&self.crate_data, &self.out_dir)?;
+        hooks::run(
+            self.crate_data.hooks(),
+            point,
+            self.crate_data.manifest_dir(),
+            &env,
+        )
+    }
+
     /// Execute this `Build` command.
     pub fn run(&mut self) -> Result<(), Error> {
-        let process_steps = Build::get_process_steps(self.mode);
+        if self.dry_run {
+            return self.print_plan();
+        }
+        if self.watch {
+            return self.run_watch();
+        }
+        if !self.members.is_empty() {
+            return self.run_workspace();
+        }
+        let process_steps = Build::get_process_steps(self.mode, &self.targets);
+        self.run_steps(process_steps)
+    }
+
+    /// Print what `run` would do, without doing it.
+    fn print_plan(&self) -> Result<(), Error> {
+        let process_steps = Build::get_process_steps(self.mode, &self.targets);
+        let steps: Vec<_> = if self.members.is_empty() {
+            process_steps
+                .into_iter()
+                .map(|(name, step)| (name, step, None))
+                .collect()
+        } else {
+            let (workspace_steps, member_steps): (Vec<_>, Vec<_>) = process_steps
+                .into_iter()
+                .filter(|(name, _)| *name != "step_check_crate_config")
+                .partition(|(name, _)| WORKSPACE_STEPS.contains(name));
+            let mut steps: Vec<_> = workspace_steps
+                .into_iter()
+                .map(|(name, step)| (name, step, None))
+                .collect();
+            for member in &self.members {
+                steps.extend(
+                    member_steps
+                        .iter()
+                        .map(|&(name, step)| (name, step, Some(member))),
+                );
+            }
+            steps
+        };
+
+        let plan = Plan::new(self, &steps)?;
+        if PBAR.json() {
+            PBAR.event(&Event::Plan(serde_json::to_value(&plan)?));
+        } else {
+            print!("{}", plan);
+        }
+        Ok(())
+    }
+
+    /// Build once, then rebuild every time one of the watched paths changes.
+    /// Build errors are reported without stopping the watcher, and changes
+    /// made while a build is running trigger exactly one more build.
+    fn run_watch(&mut self) -> Result<(), Error> {
+        let mut full_build = true;
+        loop {
+            let paths = self.crate_data.watch_paths();
+            let snapshot = watch::Snapshot::take(&paths);
 
+            let process_steps = if full_build {
+                Build::get_process_steps(self.mode, &self.targets)
+            } else {
+                Build::get_rebuild_steps(self.is_wasi())
+            };
+            full_build = match self.run_steps(process_steps) {
+                Ok(()) => false,
+                Err(e) => {
+                    report_error(&e);
+                    true
+                }
+            };
+
+            PBAR.info(&format!("{}Watching for changes...", emoji::EYES));
+            let changed = watch::wait_for_changes(&paths, &snapshot);
+            let manifest_changed = snapshot
+                .changed_paths(&changed)
+                .iter()
+                .any(|path| watch::needs_full_build(path));
+
+            if manifest_changed {
+                info!("Cargo.toml or Cargo.lock changed, reloading crate metadata...");
+                match manifest::CrateData::new(&self.crate_path, self.out_name.clone()) {
+                    Ok(crate_data) => self.crate_data = crate_data,
+                    Err(e) => report_error(&e),
+                }
+                full_build = true;
+            }
+        }
+    }
+
+    /// Compile all selected workspace members with a single `cargo build`, then
+    /// generate a package for each of them.
+    fn run_workspace(&mut self) -> Result<(), Error> {
+        let started = Instant::now();
+
+        let (workspace_steps, member_steps): (Vec<_>, Vec<_>) =
+            Build::get_process_steps(self.mode, &self.targets)
+                .into_iter()
+                .filter(|(name, _)| *name != "step_check_crate_config")
+                .partition(|(name, _)| WORKSPACE_STEPS.contains(name));
+
+        for (name, process_step) in workspace_steps {
+            run_step(name, process_step.run, self)?;
+        }
+
+        let members: Vec<_> = self.members.drain(..).collect();
+        for member in members {
+            info!(
+                "Packaging workspace member {}...",
+                member.crate_data.package_name()
+            );
+            self.crate_path = member.crate_data.manifest_dir().to_path_buf();
+            self.crate_data = member.crate_data;
+            self.fingerprints = Fingerprints::load(&member.out_dir);
+            self.out_dir = member.out_dir;
+            self.scope = member.scope;
+            self.out_name = member.out_name;
+            self.disable_dts = member.disable_dts;
+            for (name, process_step) in &member_steps {
+                run_step(name, process_step.run, self)?;
+            }
+            self.report_artifacts();
+            PBAR.info(&format!(
+                "{} {} is ready to publish at {}.",
+                emoji::PACKAGE,
+                self.crate_data.package_name(),
+                self.out_dir.display()
+            ));
+        }
+
+        let duration = crate::command::utils::elapsed(started.elapsed());
+        info!("Done in {}.", &duration);
+        PBAR.info(&format!("{} Done in {}", emoji::SPARKLE, &duration));
+        Ok(())
+    }
+
+    fn run_steps(&mut self, process_steps: Vec<(&'static str, BuildStep)>) -> Result<(), Error> {
         let started = Instant::now();
 
-        for (_, process_step) in process_steps {
-            process_step(self)?;
+        for (name, process_step) in process_steps {
+            run_step(name, process_step.run, self)?;
         }
+        self.report_artifacts();
 
         let duration = crate::command::utils::elapsed(started.elapsed());
         info!("Done in {}.", &duration);
@@ -246,17 +796,44 @@ impl Build {
         Ok(())
     }
 
-    fn get_process_steps(mode: InstallMode) -> Vec<(&'static str, BuildStep)> {
-        macro_rules! steps {
-            ($($name:ident),+) => {
-                {
-                let mut steps: Vec<(&'static str, BuildStep)> = Vec::new();
-                    $(steps.push((stringify!($name), Build::$name));)*
-                        steps
-                    }
-                };
-            ($($name:ident,)*) => (steps![$($name),*])
+    /// Report every file of the generated package as an artifact event.
+    fn report_artifacts(&self) {
+        if !PBAR.json() {
+            return;
         }
+        let name_prefix = self.crate_data.name_prefix();
+        let mut artifacts = if self.is_deno() {
+            vec![
+                (ArtifactKind::DenoJson, self.out_dir.join(deno::CONFIG_FILE)),
+                (ArtifactKind::Ts, self.out_dir.join(deno::MODULE_FILE)),
+            ]
+        } else {
+            vec![(ArtifactKind::PackageJson, self.out_dir.join("package.json"))]
+        };
+        for wasm in self.wasm_files() {
+            artifacts.push((ArtifactKind::Wasm, debug_info::debug_file_path(&wasm)));
+            artifacts.push((ArtifactKind::Wasm, wasm));
+        }
+        if self.is_wasi() {
+            for name in self.crate_data.bin_names() {
+                let launcher = self.out_dir.join(wasi::launcher_file(&name));
+                artifacts.push((ArtifactKind::Js, launcher));
+            }
+        }
+        for target in &self.targets {
+            let dir = self.target_out_dir(*target);
+            artifacts.push((ArtifactKind::Js, dir.join(format!("{}.js", name_prefix))));
+            artifacts.push((ArtifactKind::Js, dir.join(format!("{}_bg.js", name_prefix))));
+            artifacts.push((ArtifactKind::Dts, dir.join(format!("{}.d.ts", name_prefix))));
+        }
+        for (kind, path) in &artifacts {
+            if path.is_file() {
+                PBAR.event(&Event::Artifact { kind: *kind, path });
+            }
+        }
+    }
+
+    fn get_process_steps(mode: InstallMode, targets: &[Target]) -> Vec<(&'static str, BuildStep)> {
         let mut steps = Vec::new();
         match &mode {
             InstallMode::Force => {}
@@ -269,14 +846,47 @@ impl Build {
             }
         }
         steps.extend(steps![
+            step_run_pre_build_hooks,
             step_build_wasm,
             step_create_dir,
             step_copy_readme,
             step_copy_license,
-            step_install_wasm_bindgen,
-            step_run_wasm_bindgen,
+        ]);
+        if targets == [Target::Wasi] {
+            steps.extend(steps![step_copy_wasi_binaries]);
+        } else {
+            steps.extend(steps![step_install_wasm_bindgen, step_run_wasm_bindgen]);
+        }
+        steps.extend(steps![
+            step_run_post_bindgen_hooks,
+            step_run_wasm_snip,
+            step_run_wasm_opt,
+            step_split_debug_info,
+            step_report_size,
+        ]);
+        if targets == [Target::Deno] {
+            steps.extend(steps![step_create_deno_json]);
+        } else {
+            steps.extend(steps![step_create_json]);
+        }
+        steps
+    }
+
+    /// The steps that have to be re-run after a source file of an already
+    /// built crate changes.
+    fn get_rebuild_steps(wasi: bool) -> Vec<(&'static str, BuildStep)> {
+        let mut steps = steps![step_run_pre_build_hooks, step_build_wasm];
+        if wasi {
+            steps.extend(steps![step_copy_wasi_binaries]);
+        } else {
+            steps.extend(steps![step_run_wasm_bindgen]);
+        }
+        steps.extend(steps![
+            step_run_post_bindgen_hooks,
+            step_run_wasm_snip,
             step_run_wasm_opt,
-            step_create_json,
+            step_split_debug_info,
+            step_report_size,
         ]);
         steps
     }
@@ -291,33 +901,69 @@ impl Build {
 
     fn step_check_crate_config(&mut self) -> Result<(), Error> {
         info!("Checking crate configuration...");
-        self.crate_data.check_crate_config()?;
+        if self.is_wasi() {
+            self.crate_data.check_wasi_crate_config()?;
+        } else {
+            self.crate_data.check_crate_config()?;
+        }
         info!("Crate is correctly configured.");
         Ok(())
     }
 
     fn step_check_for_wasm_target(&mut self) -> Result<(), Error> {
         info!("Checking for wasm-target...");
-        build::wasm_target::check_for_wasm32_target()?;
+        build::wasm_target::check_for_wasm32_target(self.targets[0].rust_target())?;
         info!("Checking for wasm-target was successful.");
         Ok(())
     }
 
+    fn step_run_pre_build_hooks(&mut self) -> Result<(), Error> {
+        if self.members.is_empty() {
+            return self.run_hooks(HookPoint::PreBuild);
+        }
+        // The members of a workspace are compiled together, so all of their
+        // hooks run before that.
+        for member in &self.members {
+            let env = self.hook_env(&member.crate_data, &member.out_dir)?;
+            hooks::run(
+                member.crate_data.hooks(),
+                HookPoint::PreBuild,
+                member.crate_data.manifest_dir(),
+                &env,
+            )?;
+        }
+        Ok(())
+    }
+
     fn step_build_wasm(&mut self) -> Result<(), Error> {
         info!("Building wasm...");
-        build::cargo_build_wasm(&self.crate_path, self.profile, &self.extra_options)?;
+        let target = self.targets[0];
+        build::cargo_build_wasm(
+            &self.crate_path,
+            &self.profile,
+            target,
+            &self.cargo_build_options(),
+        )?;
 
         info!(
             "wasm built at {:#?}.",
-            &self
-                .crate_path
-                .join("target")
-                .join("wasm32-unknown-unknown")
-                .join("release")
+            build::artifact_dir(&self.crate_data, target.rust_target(), &self.profile)
         );
         Ok(())
     }
 
+    /// The options passed to `cargo build` on top of the ones for the profile
+    /// and target.
+    pub fn cargo_build_options(&self) -> Vec<String> {
+        let mut options = vec![];
+        for member in &self.members {
+            options.push("--package".to_string());
+            options.push(member.crate_data.package_name().to_string());
+        }
+        options.extend(self.extra_options.iter().cloned());
+        options
+    }
+
     fn step_create_dir(&mut self) -> Result<(), Error> {
         info!("Creating a pkg directory...");
         create_pkg_dir(&self.out_dir)?;
@@ -326,17 +972,67 @@ impl Build {
     }
 
     fn step_create_json(&mut self) -> Result<(), Error> {
-        self.crate_data.write_package_json(
+        let fingerprint = self.package_json_fingerprint();
+        if !self.is_child
+            && self.fingerprints.is_fresh("package.json", &fingerprint)
+            && self.out_dir.join("package.json").is_file()
+        {
+            info!("package.json is up to date.");
+            return Ok(());
+        }
+        self.fingerprints.forget("package.json")?;
+
+        if self.targets.len() > 1 {
+            self.crate_data.write_multi_target_package_json(
+                &self.out_dir,
+                &self.scope,
+                self.disable_dts,
+                &self.targets,
+            )?;
+        } else {
+            self.crate_data.write_package_json(
+                &self.out_dir,
+                &self.scope,
+                self.disable_dts,
+                self.targets[0],
+                self.is_child,
+            )?;
+        }
+        info!(
+            "Wrote a package.json at {:#?}.",
+            &self.out_dir.join("package.json")
+        );
+        self.run_hooks(HookPoint::PostPackageJson)?;
+        if !self.is_child {
+            self.fingerprints.record("package.json", fingerprint)?;
+        }
+        Ok(())
+    }
+
+    fn step_create_deno_json(&mut self) -> Result<(), Error> {
+        let fingerprint = self.package_json_fingerprint();
+        if self.fingerprints.is_fresh(deno::CONFIG_FILE, &fingerprint)
+            && self.out_dir.join(deno::CONFIG_FILE).is_file()
+            && self.out_dir.join(deno::MODULE_FILE).is_file()
+        {
+            info!("deno.json is up to date.");
+            return Ok(());
+        }
+        self.fingerprints.forget(deno::CONFIG_FILE)?;
+
+        deno::write_module(
             &self.out_dir,
-            &self.scope,
+            &self.crate_data.name_prefix(),
             self.disable_dts,
-            self.target,
-            self.is_child,
         )?;
+        self.crate_data
+            .write_deno_json(&self.out_dir, &self.scope)?;
         info!(
-            "Wrote a package.json at {:#?}.",
-            &self.out_dir.join("package.json")
+            "Wrote a deno.json at {:#?}.",
+            &self.out_dir.join(deno::CONFIG_FILE)
         );
+        self.run_hooks(HookPoint::PostPackageJson)?;
+        self.fingerprints.record(deno::CONFIG_FILE, fingerprint)?;
         Ok(())
     }
 
@@ -344,9 +1040,21 @@ impl Build {
         if self.is_child {
             return Ok(());
         }
+        let fingerprint = Fingerprint::new()
+            .add_file(&self.crate_path.join("README.md"))
+            .finish();
+        if self.fingerprints.is_fresh("readme", &fingerprint)
+            && self.out_dir.join("README.md").is_file()
+        {
+            info!("Readme is up to date.");
+            return Ok(());
+        }
+        self.fingerprints.forget("readme")?;
+
         info!("Copying readme from crate...");
         readme::copy_from_crate(&self.crate_path, &self.out_dir)?;
         info!("Copied readme from crate to {:#?}.", &self.out_dir);
+        self.fingerprints.record("readme", fingerprint)?;
         Ok(())
     }
 
@@ -354,9 +1062,31 @@ impl Build {
         if self.is_child {
             return Ok(());
         }
+        let license_files = license::crate_license_files(&self.crate_data, &self.crate_path);
+        let mut fingerprint = Fingerprint::new();
+        fingerprint.add(format!(
+            "{:?} {:?}",
+            self.crate_data.crate_license(),
+            self.crate_data.crate_license_file()
+        ));
+        for file in &license_files {
+            fingerprint.add(file).add_file(&self.crate_path.join(file));
+        }
+        let fingerprint = fingerprint.finish();
+        if self.fingerprints.is_fresh("license", &fingerprint)
+            && license_files
+                .iter()
+                .all(|file| self.out_dir.join(file).is_file())
+        {
+            info!("License is up to date.");
+            return Ok(());
+        }
+        self.fingerprints.forget("license")?;
+
         info!("Copying license from crate...");
         license::copy_from_crate(&self.crate_data, &self.crate_path, &self.out_dir)?;
         info!("Copied license from crate to {:#?}.", &self.out_dir);
+        self.fingerprints.record("license", fingerprint)?;
         Ok(())
     }
 
@@ -377,39 +1107,374 @@ impl Build {
     }
 
     fn step_run_wasm_bindgen(&mut self) -> Result<(), Error> {
-        info!("Building the wasm bindings...");
-        bindgen::wasm_bindgen_build(
-            &self.crate_data,
-            &self.bindgen.as_ref().unwrap(),
-            &self.out_dir,
-            &self.out_name,
-            self.disable_dts,
-            self.target,
-            self.profile,
-        )?;
-        info!("wasm bindings were built at {:#?}.", &self.out_dir);
+        let fingerprint = self.bindings_fingerprint()?;
+        let wasm_file = format!("{}_bg.wasm", self.crate_data.name_prefix());
+        if self.fingerprints.is_fresh("wasm-bindgen", &fingerprint)
+            && self
+                .targets
+                .iter()
+                .all(|&target| self.target_out_dir(target).join(&wasm_file).is_file())
+        {
+            info!("wasm bindings are up to date, skipping wasm-bindgen, wasm-snip and wasm-opt.");
+            self.bindings_fingerprint = None;
+            return Ok(());
+        }
+        self.fingerprints.forget("wasm-bindgen")?;
+
+        for &target in &self.targets {
+            let out_dir = self.target_out_dir(target);
+            info!("Building the wasm bindings for {}...", target);
+            create_pkg_dir(&out_dir)?;
+            // Don't leave the debug info of a previous build behind, where
+            // wasm-snip and wasm-opt would pick it up.
+            let debug_file = debug_info::debug_file_path(&out_dir.join(&wasm_file));
+            if debug_file.is_file() {
+                fs::remove_file(&debug_file)?;
+            }
+            bindgen::wasm_bindgen_build(
+                &self.crate_data,
+                &self.bindgen.as_ref().unwrap(),
+                &out_dir,
+                &self.out_name,
+                self.disable_dts,
+                target,
+                &self.profile,
+            )?;
+            info!("wasm bindings were built at {:#?}.", &out_dir);
+        }
+        self.bindings_fingerprint = Some(fingerprint);
         Ok(())
     }
 
-    fn step_run_wasm_opt(&mut self) -> Result<(), Error> {
-        let args = match self
-            .crate_data
-            .configured_profile(self.profile)
-            .wasm_opt_args()
+    fn step_run_post_bindgen_hooks(&mut self) -> Result<(), Error> {
+        if self.bindings_fingerprint.is_none() {
+            info!("wasm bindings are up to date, skipping the post-bindgen hooks.");
+            return Ok(());
+        }
+        self.run_hooks(HookPoint::PostBindgen)
+    }
+
+    fn step_copy_wasi_binaries(&mut self) -> Result<(), Error> {
+        let fingerprint = self.wasi_fingerprint();
+        if self.fingerprints.is_fresh("wasm-bindgen", &fingerprint)
+            && self.wasm_files().iter().all(|path| path.is_file())
         {
-            Some(args) => args,
+            info!("WASI binaries are up to date, skipping wasm-snip and wasm-opt.");
+            self.bindings_fingerprint = None;
+            return Ok(());
+        }
+        self.fingerprints.forget("wasm-bindgen")?;
+
+        info!("Copying the WASI binaries...");
+        create_pkg_dir(&self.out_dir)?;
+        for wasm in self.wasm_files() {
+            let debug_file = debug_info::debug_file_path(&wasm);
+            if debug_file.is_file() {
+                fs::remove_file(&debug_file)?;
+            }
+        }
+        wasi::copy_binaries(&self.crate_data, &self.profile, &self.out_dir)?;
+        info!("WASI binaries were copied to {:#?}.", &self.out_dir);
+        self.bindings_fingerprint = Some(fingerprint);
+        Ok(())
+    }
+
+    fn step_run_wasm_snip(&mut self) -> Result<(), Error> {
+        // Bindings that are up to date have been snipped already.
+        if self.bindings_fingerprint.is_none() {
+            return Ok(());
+        }
+        for &target in &self.targets {
+            let args = match self.target_profile(target).wasm_snip_args() {
+                Some(args) => args,
+                None => continue,
+            };
+            let out_dir = self.target_out_dir(target);
+            info!("executing wasm-snip with {:?} in {:#?}", args, out_dir);
+            wasm_snip::run(&self.cache, &out_dir, &args, self.mode.install_permitted())?;
+        }
+        Ok(())
+    }
+
+    fn step_run_wasm_opt(&mut self) -> Result<(), Error> {
+        // Bindings that are up to date have been optimized already.
+        if self.bindings_fingerprint.is_none() {
+            return Ok(());
+        }
+        for &target in &self.targets {
+            if let Some(args) = self.target_profile(target).wasm_opt_args() {
+                self.run_wasm_opt(target, &args)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn step_split_debug_info(&mut self) -> Result<(), Error> {
+        // The fingerprint of freshly generated bindings is only recorded once
+        // they have been optimized and their debug info split off, too.
+        let fingerprint = match self.bindings_fingerprint.take() {
+            Some(fingerprint) => fingerprint,
             None => return Ok(()),
         };
-        info!("executing wasm-opt with {:?}", args);
+        for &target in &self.targets {
+            if !self.target_profile(target).split_debug_info() {
+                continue;
+            }
+            for wasm in self.target_wasm_files(target) {
+                if debug_info::split(&wasm)? {
+                    info!("split the debug info of {:#?} off", wasm);
+                } else {
+                    PBAR.warn(&format!(
+                        "{} has no DWARF debug info to split off",
+                        wasm.display()
+                    ));
+                }
+            }
+        }
+        self.fingerprints.record("wasm-bindgen", fingerprint)
+    }
+
+    fn step_report_size(&mut self) -> Result<(), Error> {
+        let paths: Vec<String> = self
+            .wasm_files()
+            .iter()
+            .map(|path| {
+                let path = path.strip_prefix(&self.out_dir).unwrap();
+                path.to_string_lossy().into_owned()
+            })
+            .collect();
+        let required = self.compare.is_some() || self.has_size_budget();
+        let report = match SizeReport::new(&self.out_dir, &paths, self.size_quality()) {
+            Ok(report) => report,
+            // The sizes are only informational unless they're compared or
+            // checked against a budget, so don't fail the build over them.
+            Err(e) if !required => {
+                PBAR.warn(&format!("Couldn't measure the size of the output: {}", e));
+                return Ok(());
+            }
+            Err(e) => return Err(e),
+        };
+        for file in &report.files {
+            PBAR.info(&format!("{}{}", emoji::RULER, file));
+        }
+        if let Some(old) = &self.compare {
+            PBAR.info(&format!(
+                "{}Size changes compared to the previous build:\n{}",
+                emoji::RULER,
+                report.compare(old)
+            ));
+        }
+        if self.size_report {
+            report.save(&self.out_dir)?;
+            info!(
+                "Wrote a size report at {:#?}.",
+                self.out_dir.join(size::SIZE_REPORT_FILE)
+            );
+        }
+        for &target in &self.targets {
+            if let Some(budget) = self.target_profile(target).size_budget() {
+                let target_dir = self.target_out_dir(target);
+                budget.check_files(
+                    report
+                        .files
+                        .iter()
+                        .filter(|file| self.out_dir.join(&file.path).starts_with(&target_dir)),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Compressing at the best quality is slow, so only do it where the
+    /// compressed sizes matter: for release builds, and when they are saved,
+    /// compared or checked against a budget.
+    fn size_quality(&self) -> size::Quality {
+        if matches!(self.profile, BuildProfile::Release)
+            || self.size_report
+            || self.compare.is_some()
+            || self.has_size_budget()
+        {
+            size::Quality::Best
+        } else {
+            size::Quality::Fast
+        }
+    }
+
+    /// Whether the profile of any target sets a size budget.
+    fn has_size_budget(&self) -> bool {
+        self.targets
+            .iter()
+            .any(|&target| self.target_profile(target).size_budget().is_some())
+    }
+
+    /// Fingerprint everything that the output of wasm-bindgen and wasm-opt
+    /// depends on.
+    fn bindings_fingerprint(&self) -> Result<String, Error> {
+        let bindgen_path =
+            install::get_tool_path(self.bindgen.as_ref().unwrap(), Tool::WasmBindgen)?
+                .binary(&Tool::WasmBindgen.to_string())?;
+        let mut fingerprint = Fingerprint::new();
+        fingerprint
+            .add_file(&bindgen::wasm_artifact_path(
+                &self.crate_data,
+                &self.profile,
+            ))
+            .add(install::get_cli_version(&Tool::WasmBindgen, &bindgen_path)?)
+            .add(format!("{}", self.disable_dts));
+        for &target in &self.targets {
+            let profile = self.target_profile(target);
+            fingerprint.add(format!(
+                "{} {} {} {}",
+                target,
+                profile.wasm_bindgen_debug_js_glue(),
+                profile.wasm_bindgen_demangle_name_section(),
+                profile.wasm_bindgen_dwarf_debug_info(),
+            ));
+        }
+        Ok(self.add_post_processing(fingerprint))
+    }
+
+    /// Fingerprint everything that the WASI binaries in the out dir depend
+    /// on.
+    fn wasi_fingerprint(&self) -> String {
+        let mut fingerprint = Fingerprint::new();
+        for artifact in wasi::artifact_paths(&self.crate_data, &self.profile) {
+            fingerprint.add_file(&artifact);
+        }
+        fingerprint.add(wasi::launcher("{name}"));
+        self.add_post_processing(fingerprint)
+    }
+
+    /// Add what wasm-snip, wasm-opt and splitting the debug info depend on to
+    /// `fingerprint`, and finish it.
+    fn add_post_processing(&self, mut fingerprint: Fingerprint) -> String {
+        fingerprint
+            .add(wasm_opt::WASM_OPT_VERSION)
+            .add(wasm_snip::WASM_SNIP_VERSION)
+            .add(format!("{:?}", self.targets))
+            .add(format!("{:?}", self.out_name))
+            .add(format!(
+                "{:?}",
+                self.crate_data.hooks().commands(HookPoint::PostBindgen)
+            ));
+        for &target in &self.targets {
+            let profile = self.target_profile(target);
+            fingerprint.add(format!(
+                "{:?} {:?} {}",
+                profile.wasm_snip_args(),
+                profile.wasm_opt_args(),
+                profile.split_debug_info(),
+            ));
+        }
+        fingerprint.finish()
+    }
+
+    /// Fingerprint everything that the generated package.json depends on,
+    /// including the hooks that edit it.
+    fn package_json_fingerprint(&self) -> String {
+        let mut fingerprint = Fingerprint::new();
+        fingerprint
+            .add_file(&self.crate_path.join("Cargo.toml"))
+            .add(format!("{:?}", self.scope))
+            .add(format!("{:?}", self.targets))
+            .add(format!("{:?}", self.out_name))
+            .add(format!("{}", self.disable_dts))
+            .add(format!(
+                "{:?}",
+                self.crate_data.hooks().commands(HookPoint::PostPackageJson)
+            ));
+        for file in license::crate_license_files(&self.crate_data, &self.crate_path) {
+            fingerprint.add(file);
+        }
+        fingerprint.finish()
+    }
+
+    fn run_wasm_opt(&self, target: Target, args: &[String]) -> Result<(), Error> {
+        let out_dir = self.target_out_dir(target);
+        info!("executing wasm-opt with {:?} in {:#?}", args, out_dir);
         wasm_opt::run(
             &self.cache,
-            &self.out_dir,
-            &args,
+            &out_dir,
+            args,
             self.mode.install_permitted(),
         ).map_err(|e| {
             format_err!(
                 "{}\nTo disable `wasm-opt`, add `wasm-opt = false` to your package metadata in your `Cargo.toml`.", e
             )
+        })?;
+        Ok(())
+    }
+}
+
+impl Member {
+    /// Work out the options of a workspace member. Options given on the command
+    /// line take precedence over the member's own defaults, which take
+    /// precedence over the defaults of the crate that `wasm-pack` runs for.
+    fn new(
+        mut crate_data: manifest::CrateData,
+        cli_opts: &BuildOptions,
+        defaults: &manifest::BuildDefaults,
+        build_opts: &BuildOptions,
+    ) -> Result<Member, Error> {
+        let own = manifest::CrateData::build_defaults(crate_data.manifest_dir())?;
+        // All members are compiled by one `cargo build` for the same targets.
+        if !own.target.is_empty() && own.target != build_opts.target {
+            PBAR.warn(&format!(
+                "{} sets a default `target`, which a workspace build ignores. All members \
+                 are built for the targets of the workspace build.",
+                crate_data.package_name()
+            ));
+        }
+        if !own.extra_options.is_empty() && own.extra_options != build_opts.extra_options {
+            PBAR.warn(&format!(
+                "{} sets default `extra-options`, which a workspace build ignores. All \
+                 members are built with the extra options of the workspace build.",
+                crate_data.package_name()
+            ));
+        }
+
+        let mut opts = cli_opts.clone();
+        // A member's own `out-dir` is relative to the member, like in a build
+        // of just that crate.
+        let own_out_dir = own.out_dir.clone();
+        opts.apply_defaults(own);
+        opts.apply_defaults(defaults.clone());
+        let out_dir = match (&cli_opts.out_dir, own_out_dir) {
+            (None, Some(dir)) => crate_data.manifest_dir().join(dir),
+            _ => member_out_dir(opts.out_dir.as_deref().unwrap_or("pkg"), &crate_data),
+        };
+        crate_data.set_out_name(opts.out_name.clone());
+        Ok(Member {
+            crate_data,
+            out_dir,
+            scope: opts.scope,
+            out_name: opts.out_name,
+            disable_dts: opts.disable_dts,
         })
     }
 }
+
+/// Resolve `--out-dir` for a member of a workspace build, relative to the
+/// workspace root.
+fn member_out_dir(layout: &str, member: &manifest::CrateData) -> PathBuf {
+    let root = member.workspace_root();
+    let dir = member
+        .manifest_dir()
+        .strip_prefix(root)
+        .unwrap_or_else(|_| member.manifest_dir());
+    if layout.contains("{name}") || layout.contains("{dir}") {
+        let layout = layout
+            .replace("{name}", member.package_name())
+            .replace("{dir}", &dir.to_string_lossy());
+        root.join(layout)
+    } else {
+        root.join(layout).join(member.package_name())
+    }
+}
+
+fn report_error(e: &Error) {
+    PBAR.error(&e.to_string());
+    for cause in e.iter_causes() {
+        PBAR.error(&format!("Caused by: {}", cause));
+    }
+}
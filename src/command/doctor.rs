@@ -0,0 +1,488 @@
+//! Implementation of the `wasm-pack doctor` command, which checks that the
+//! environment has everything wasm-pack needs and explains how to fix what
+//! it's missing.
+
+use binary_install::Cache;
+use build;
+use build::wasm_target::{self, Sysroot};
+use cache;
+use command::tools;
+use command::utils::get_crate_path;
+use failure::Error;
+use install::{self, Tool};
+use lockfile::Lockfile;
+use manifest::CrateData;
+use offline;
+use progressbar::Event;
+use serde_json;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+use which::which;
+use PBAR;
+
+const NON_RUSTUP_SETUPS: &str =
+    "https://rustwasm.github.io/wasm-pack/book/prerequisites/non-rustup-setups.html";
+
+/// The outcome of one of `wasm-pack doctor`'s checks.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    /// Nothing is wrong.
+    Pass,
+    /// Some commands or options won't work, or won't work offline.
+    Warn,
+    /// Builds will fail.
+    Fail,
+}
+
+impl Status {
+    fn name(self) -> &'static str {
+        match self {
+            Status::Pass => "PASS",
+            Status::Warn => "WARN",
+            Status::Fail => "FAIL",
+        }
+    }
+}
+
+/// One of `wasm-pack doctor`'s checks.
+#[derive(Debug, Serialize)]
+pub struct Check {
+    /// What was checked, e.g. `rustc`.
+    pub name: String,
+    /// How it went.
+    pub status: Status,
+    /// What was found.
+    pub message: String,
+    /// How to fix the problem, unless the check passed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &str, message: String) -> Check {
+        Check {
+            name: name.to_string(),
+            status: Status::Pass,
+            message,
+            fix: None,
+        }
+    }
+
+    fn warn(name: &str, message: String, fix: String) -> Check {
+        Check {
+            name: name.to_string(),
+            status: Status::Warn,
+            message,
+            fix: Some(fix),
+        }
+    }
+
+    fn fail(name: &str, message: String, fix: String) -> Check {
+        Check {
+            name: name.to_string(),
+            status: Status::Fail,
+            message,
+            fix: Some(fix),
+        }
+    }
+}
+
+/// Run every check and print the report. Fails if any of the checks did.
+pub fn doctor(path: Option<PathBuf>) -> Result<(), Error> {
+    let crate_path = get_crate_path(path)?;
+    let checks = run_checks(&crate_path);
+    if PBAR.json() {
+        PBAR.event(&Event::Doctor {
+            checks: serde_json::to_value(&checks)?,
+        });
+    } else {
+        for check in &checks {
+            println!(
+                "{}  {:<24} {}",
+                check.status.name(),
+                check.name,
+                check.message
+            );
+            if let Some(fix) = &check.fix {
+                println!("      {:<24} fix: {}", "", fix);
+            }
+        }
+    }
+    let count = |status| checks.iter().filter(|c| c.status == status).count();
+    let failed = count(Status::Fail);
+    PBAR.info(&format!(
+        "{} passed, {} warnings, {} failed",
+        count(Status::Pass),
+        count(Status::Warn),
+        failed
+    ));
+    if failed > 0 {
+        bail!("{} of wasm-pack doctor's checks failed", failed);
+    }
+    Ok(())
+}
+
+/// Run every check, including the ones about the crate at `crate_path` if
+/// there is one.
+pub fn run_checks(crate_path: &Path) -> Vec<Check> {
+    let mut checks = vec![check_rustc()];
+    match Sysroot::find() {
+        Ok(sysroot) => {
+            checks.push(check_rustup(&sysroot));
+            checks.push(check_target(
+                &sysroot,
+                "wasm32-unknown-unknown",
+                Status::Fail,
+            ));
+            checks.push(check_target(
+                &sysroot,
+                wasm_target::wasi_target(),
+                Status::Warn,
+            ));
+        }
+        Err(e) => checks.push(Check::fail(
+            "sysroot",
+            format!("Couldn't find rustc's sysroot: {}", e),
+            "Install Rust with rustup from https://rustup.rs/".to_string(),
+        )),
+    }
+
+    let cache = cache::get_wasm_pack_cache();
+    if crate_path.join("Cargo.toml").is_file() {
+        checks.push(check_wasm_bindgen(crate_path, cache.as_ref().ok()));
+    }
+
+    checks.push(check_node("node"));
+    checks.push(check_node("npm"));
+
+    let cache = match cache {
+        Ok(cache) => cache,
+        Err(e) => {
+            checks.push(Check::fail(
+                "cache",
+                format!("Couldn't find wasm-pack's cache: {}", e),
+                "Set WASM_PACK_CACHE to a writable directory".to_string(),
+            ));
+            return checks;
+        }
+    };
+    checks.push(check_browser(
+        "chrome",
+        "Chrome",
+        &[
+            "google-chrome",
+            "google-chrome-stable",
+            "chromium",
+            "chromium-browser",
+            "chrome",
+        ],
+        "/Applications/Google Chrome.app",
+        "chromedriver",
+        &cache,
+    ));
+    checks.push(check_browser(
+        "firefox",
+        "Firefox",
+        &["firefox"],
+        "/Applications/Firefox.app",
+        "geckodriver",
+        &cache,
+    ));
+    if cfg!(target_os = "macos") {
+        checks.push(check_safari());
+    }
+    checks.push(check_cache(&cache));
+    checks
+}
+
+fn check_rustc() -> Check {
+    match build::check_rustc_version() {
+        Ok(minor) => Check::pass("rustc", format!("rustc 1.{}", minor)),
+        Err(e) => Check::fail(
+            "rustc",
+            e.to_string(),
+            "Install Rust with rustup from https://rustup.rs/, or update it with `rustup \
+             update`"
+                .to_string(),
+        ),
+    }
+}
+
+fn check_rustup(sysroot: &Sysroot) -> Check {
+    if sysroot.is_rustup {
+        Check::pass(
+            "rustup",
+            format!("rustup manages the toolchain at {}", sysroot.path.display()),
+        )
+    } else {
+        Check::warn(
+            "rustup",
+            format!(
+                "The toolchain at {} isn't managed by rustup, so wasm-pack can't install \
+                 targets for it",
+                sysroot.path.display()
+            ),
+            format!(
+                "Install the wasm32 targets by hand, as described in {}",
+                NON_RUSTUP_SETUPS
+            ),
+        )
+    }
+}
+
+/// Check that `target` is installed. `missing` is what it means for it not
+/// to be.
+fn check_target(sysroot: &Sysroot, target: &str, missing: Status) -> Check {
+    if sysroot.has_target(target) {
+        return Check::pass(target, "installed".to_string());
+    }
+    let fix = if sysroot.is_rustup {
+        format!("Run `rustup target add {}`", target)
+    } else {
+        format!(
+            "Install {} by hand, as described in {}",
+            target, NON_RUSTUP_SETUPS
+        )
+    };
+    let message = format!("{} isn't installed in {}", target, sysroot.path.display());
+    // wasm-pack adds the target itself when rustup manages the toolchain.
+    if sysroot.is_rustup && !offline::is_offline() {
+        Check::warn(
+            target,
+            format!("{}, so wasm-pack will add it with rustup", message),
+            fix,
+        )
+    } else if missing == Status::Fail {
+        Check::fail(target, message, fix)
+    } else {
+        Check::warn(target, message, fix)
+    }
+}
+
+fn check_wasm_bindgen(crate_path: &Path, cache: Option<&Cache>) -> Check {
+    let name = "wasm-bindgen";
+    let lockfile = CrateData::new(crate_path, None).and_then(|data| Lockfile::new(&data));
+    let lockfile = match lockfile {
+        Ok(lockfile) => lockfile,
+        Err(e) => {
+            return Check::fail(
+                name,
+                format!("Couldn't read the crate's Cargo.lock: {}", e),
+                "Fix the crate's Cargo.toml until `cargo metadata` succeeds".to_string(),
+            )
+        }
+    };
+    let version = match lockfile.wasm_bindgen_version() {
+        Some(version) => version,
+        None => {
+            return Check::warn(
+                name,
+                "The crate doesn't depend on wasm-bindgen".to_string(),
+                "Add `wasm-bindgen` to the crate's [dependencies]".to_string(),
+            )
+        }
+    };
+    let on_path = which(name)
+        .ok()
+        .and_then(|path| install::get_cli_version(&Tool::WasmBindgen, &path).ok());
+    let plan = cache.map(|cache| {
+        install::plan_prebuilt_or_cargo_install(
+            Tool::WasmBindgen,
+            cache,
+            version,
+            !offline::is_offline(),
+        )
+    });
+    match plan {
+        Some(Ok(install::InstallPlan::Unavailable { reason })) => {
+            return Check::fail(
+                name,
+                format!(
+                    "Cargo.lock has {}, which is unavailable: {}",
+                    version, reason
+                ),
+                format!(
+                    "Run `wasm-pack prefetch` while online, or `cargo install -f \
+                     wasm-bindgen-cli --version {}`",
+                    version
+                ),
+            )
+        }
+        Some(Err(e)) => {
+            return Check::fail(
+                name,
+                format!(
+                    "Cargo.lock has {}, which can't be installed: {}",
+                    version, e
+                ),
+                "Fix wasm-pack's mirror configuration".to_string(),
+            )
+        }
+        _ => {}
+    }
+    match on_path {
+        Some(ref cli) if cli != version => Check::warn(
+            name,
+            format!(
+                "The wasm-bindgen CLI on the $PATH is {}, but Cargo.lock has {}, so wasm-pack \
+                 won't use it",
+                cli, version
+            ),
+            format!(
+                "Run `cargo install -f wasm-bindgen-cli --version {}`, or `cargo update -p \
+                 wasm-bindgen --precise {}`",
+                version, cli
+            ),
+        ),
+        _ => Check::pass(
+            name,
+            match plan {
+                Some(Ok(plan)) => format!("{}, {}", version, plan),
+                _ => version.to_string(),
+            },
+        ),
+    }
+}
+
+fn check_node(tool: &str) -> Check {
+    let (uses, fix) = if tool == "node" {
+        (
+            "`wasm-pack test --node`",
+            "Install Node.js from https://nodejs.org/",
+        )
+    } else {
+        (
+            "`wasm-pack pack`, `publish` and `login`",
+            "Install npm, which comes with Node.js, from https://nodejs.org/",
+        )
+    };
+    let path = match which(tool) {
+        Ok(path) => path,
+        Err(_) => {
+            return Check::warn(
+                tool,
+                format!("{} isn't on the $PATH, which {} need", tool, uses),
+                fix.to_string(),
+            )
+        }
+    };
+    match Command::new(&path).arg("--version").output() {
+        Ok(ref output) if output.status.success() => Check::pass(
+            tool,
+            format!(
+                "{} {} at {}",
+                tool,
+                String::from_utf8_lossy(&output.stdout).trim(),
+                path.display()
+            ),
+        ),
+        _ => Check::warn(
+            tool,
+            format!("`{} --version` failed", path.display()),
+            fix.to_string(),
+        ),
+    }
+}
+
+fn check_browser(
+    name: &str,
+    browser: &str,
+    binaries: &[&str],
+    mac_app: &str,
+    driver: &str,
+    cache: &Cache,
+) -> Check {
+    let installed = binaries.iter().any(|binary| which(binary).is_ok())
+        || (cfg!(target_os = "macos") && Path::new(mac_app).exists());
+    if !installed {
+        return Check::warn(
+            name,
+            format!("{} isn't installed", browser),
+            format!("Install {} to run `wasm-pack test --{}`", browser, name),
+        );
+    }
+    if let Ok(path) = which(driver) {
+        return Check::pass(
+            name,
+            format!(
+                "{} is installed, with {} at {}",
+                browser,
+                driver,
+                path.display()
+            ),
+        );
+    }
+    let cached = tools::cached_tools(cache)
+        .map(|tools| tools.into_iter().find(|tool| tool.tool == driver))
+        .unwrap_or(None);
+    match cached {
+        Some(tool) => Check::pass(
+            name,
+            format!(
+                "{} is installed, with {} at {}",
+                browser,
+                driver,
+                tool.path.display()
+            ),
+        ),
+        None if offline::is_offline() => Check::warn(
+            name,
+            format!(
+                "{} isn't on the $PATH or in wasm-pack's cache, and wasm-pack is offline",
+                driver
+            ),
+            format!(
+                "Run `wasm-pack prefetch --{}` while online, or pass `--{} <path>` to \
+                 `wasm-pack test`",
+                name, driver
+            ),
+        ),
+        None => Check::pass(
+            name,
+            format!(
+                "{} is installed, and `wasm-pack test --{}` will download {}",
+                browser, name, driver
+            ),
+        ),
+    }
+}
+
+fn check_safari() -> Check {
+    if which("safaridriver").is_ok() {
+        Check::pass("safari", "safaridriver is on the $PATH".to_string())
+    } else {
+        Check::warn(
+            "safari",
+            "safaridriver isn't on the $PATH".to_string(),
+            "Update macOS, which has shipped safaridriver since El Capitan".to_string(),
+        )
+    }
+}
+
+fn check_cache(cache: &Cache) -> Check {
+    let dir = cache.join(Path::new(""));
+    // The cache is created by the first install, so it's enough for its
+    // closest existing ancestor to be writable.
+    let existing = match dir.ancestors().find(|ancestor| ancestor.exists()) {
+        Some(existing) => existing,
+        None => Path::new("."),
+    };
+    let probe = existing.join(format!(".wasm-pack-doctor-{}", process::id()));
+    if let Err(e) = fs::write(&probe, "").and_then(|()| fs::remove_file(&probe)) {
+        return Check::fail(
+            "cache",
+            format!("Can't write to {}: {}", existing.display(), e),
+            format!(
+                "Make {} writable, or set WASM_PACK_CACHE to a writable directory",
+                existing.display()
+            ),
+        );
+    }
+    let count = tools::cached_tools(cache).map_or(0, |tools| tools.len());
+    Check::pass(
+        "cache",
+        format!("{} is writable, with {} tools in it", dir.display(), count),
+    )
+}
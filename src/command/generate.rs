@@ -3,6 +3,7 @@ use failure::Error;
 use generate;
 use install::{self, Tool};
 use log::info;
+use offline;
 use std::result;
 use PBAR;
 
@@ -13,6 +14,7 @@ pub fn generate(
     name: String,
     install_permitted: bool,
 ) -> result::Result<(), Error> {
+    offline::ensure_online("download the template")?;
     info!("Generating a new rustwasm project...");
     let download = install::download_prebuilt_or_cargo_install(
         Tool::CargoGenerate,
@@ -1,5 +1,6 @@
 use log::info;
 use npm;
+use offline;
 use std::result;
 use PBAR;
 
@@ -9,6 +10,7 @@ pub fn login(
     always_auth: bool,
     auth_type: &Option<String>,
 ) -> result::Result<(), failure::Error> {
+    offline::ensure_online("log in to npm")?;
     let registry = registry.unwrap_or_else(|| npm::DEFAULT_NPM_REGISTRY.to_string());
 
     info!("Logging in to npm...");
@@ -2,25 +2,34 @@
 #![allow(clippy::redundant_closure)]
 
 pub mod build;
+pub mod doctor;
 mod generate;
 mod login;
 mod pack;
+pub mod prefetch;
 /// Data structures and functions for publishing a package.
 pub mod publish;
 pub mod test;
+pub mod tools;
 pub mod utils;
 
 use self::build::{Build, BuildOptions};
+use self::doctor::doctor;
 use self::generate::generate;
 use self::login::login;
 use self::pack::pack;
+use self::prefetch::{prefetch, PrefetchOptions};
 use self::publish::{access::Access, publish};
 use self::test::{Test, TestOptions};
+use self::tools::{tools, ToolsCommand};
 use crate::install::InstallMode;
 use failure::Error;
 use log::info;
+use progressbar::{duration_ms, Event};
 use std::path::PathBuf;
 use std::result;
+use std::time::Instant;
+use PBAR;
 
 /// The various kinds of commands that `wasm-pack` can execute.
 #[derive(Debug, StructOpt)]
@@ -110,10 +119,43 @@ pub enum Command {
     #[structopt(name = "test")]
     /// 👩‍🔬  test your wasm!
     Test(TestOptions),
+
+    #[structopt(name = "prefetch")]
+    /// 📥  download every tool a crate needs into wasm-pack's cache!
+    Prefetch(PrefetchOptions),
+
+    #[structopt(name = "tools")]
+    /// 🧰  list, install and prune the tools in wasm-pack's cache!
+    Tools(ToolsCommand),
+
+    #[structopt(name = "doctor")]
+    /// 🩺  check that everything wasm-pack needs is installed!
+    Doctor {
+        /// The path to the Rust crate whose Cargo.lock to check. If not set,
+        /// searches up the path from the current directory.
+        #[structopt(parse(from_os_str))]
+        path: Option<PathBuf>,
+    },
 }
 
 /// Run a command with the given logger!
 pub fn run_wasm_pack(command: Command) -> result::Result<(), Error> {
+    let started = Instant::now();
+    let result = run_command(command);
+    PBAR.event(&Event::Finished {
+        success: result.is_ok(),
+        duration_ms: duration_ms(started.elapsed()),
+        error: result.as_ref().err().map(|e| {
+            e.iter_chain()
+                .map(|cause| cause.to_string())
+                .collect::<Vec<_>>()
+                .join(": ")
+        }),
+    });
+    result
+}
+
+fn run_command(command: Command) -> result::Result<(), Error> {
     // Run the correct command based off input and store the result of it so that we can clear
     // the progress bar then return it
     match command {
@@ -163,5 +205,18 @@ pub fn run_wasm_pack(command: Command) -> result::Result<(), Error> {
             info!("Running test command...");
             Test::try_from_opts(test_opts).and_then(|t| t.run())
         }
+        Command::Prefetch(prefetch_opts) => {
+            info!("Running prefetch command...");
+            prefetch(prefetch_opts)
+        }
+        Command::Tools(command) => {
+            info!("Running tools command...");
+            tools(command)
+        }
+        Command::Doctor { path } => {
+            info!("Running doctor command...");
+            info!("Path: {:?}", &path);
+            doctor(path)
+        }
     }
 }
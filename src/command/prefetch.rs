@@ -0,0 +1,128 @@
+//! Implementation of the `wasm-pack prefetch` command, which downloads the
+//! tools that a crate's builds and tests need into wasm-pack's cache.
+
+use cache;
+use command::tools;
+use command::utils::get_crate_path;
+use failure::Error;
+use install::{self, InstallMode, Tool};
+use lockfile::Lockfile;
+use manifest::CrateData;
+use offline;
+use std::path::PathBuf;
+use target;
+use test::webdriver;
+use wasm_opt;
+use wasm_snip;
+use PBAR;
+
+/// Everything required to configure the `wasm-pack prefetch` command.
+#[derive(Debug, Default, StructOpt)]
+pub struct PrefetchOptions {
+    /// The path to the Rust crate. If not set, searches up the path from the
+    /// current directory.
+    #[structopt(parse(from_os_str))]
+    pub path: Option<PathBuf>,
+
+    #[structopt(long = "workspace")]
+    /// Prefetch the tools of every member of the workspace.
+    pub workspace: bool,
+
+    #[structopt(long = "chrome")]
+    /// Also prefetch `chromedriver`, for `wasm-pack test --chrome`.
+    pub chrome: bool,
+
+    #[structopt(long = "firefox")]
+    /// Also prefetch `geckodriver`, for `wasm-pack test --firefox`.
+    pub firefox: bool,
+
+    #[structopt(long = "export", parse(from_os_str))]
+    /// Once everything is prefetched, write wasm-pack's cache to this
+    /// tarball, which `wasm-pack tools import` adds to the cache of another
+    /// machine.
+    pub export: Option<PathBuf>,
+}
+
+/// Download the tools that the crate described by `opts` needs.
+pub fn prefetch(opts: PrefetchOptions) -> Result<(), Error> {
+    offline::ensure_online("prefetch tools")?;
+    let crate_path = get_crate_path(opts.path)?;
+    let crates = if opts.workspace {
+        CrateData::workspace_members(&crate_path, &[], &[], None)?
+    } else {
+        vec![CrateData::new(&crate_path, None)?]
+    };
+    let first = match crates.first() {
+        Some(first) => first,
+        None => bail!("The workspace has no members with crate-type cdylib to prefetch tools for"),
+    };
+    let cache = cache::get_wasm_pack_cache()?;
+    let install_permitted = InstallMode::Normal.install_permitted();
+
+    let lockfile = Lockfile::new(first)?;
+    match lockfile.wasm_bindgen_version() {
+        Some(version) => {
+            let status = install::download_prebuilt_or_cargo_install(
+                Tool::WasmBindgen,
+                &cache,
+                version,
+                install_permitted,
+            )?;
+            install::get_tool_path(&status, Tool::WasmBindgen)?;
+            prefetched(Tool::WasmBindgen, version);
+        }
+        None => PBAR.info("Not prefetching wasm-bindgen, which the crate doesn't depend on"),
+    }
+
+    let profiles: Vec<_> = crates
+        .iter()
+        .flat_map(|crate_data| crate_data.all_configured_profiles())
+        .collect();
+    if profiles
+        .iter()
+        .any(|profile| profile.wasm_opt_args().is_some())
+    {
+        match wasm_opt::find_wasm_opt(&cache, install_permitted)? {
+            install::Status::PlatformNotSupported => PBAR.warn(&format!(
+                "Not prefetching wasm-opt, which has no prebuilt binaries for {}. Install \
+                 binaryen and put wasm-opt on the $PATH to optimize wasm binaries.",
+                target::platform()
+            )),
+            status => {
+                install::get_tool_path(&status, Tool::WasmOpt)?;
+                prefetched(Tool::WasmOpt, wasm_opt::WASM_OPT_VERSION);
+            }
+        }
+    }
+    if profiles
+        .iter()
+        .any(|profile| profile.wasm_snip_args().is_some())
+    {
+        let status = install::download_prebuilt_or_cargo_install(
+            Tool::WasmSnip,
+            &cache,
+            wasm_snip::WASM_SNIP_VERSION,
+            install_permitted,
+        )?;
+        install::get_tool_path(&status, Tool::WasmSnip)?;
+        prefetched(Tool::WasmSnip, wasm_snip::WASM_SNIP_VERSION);
+    }
+
+    if opts.chrome {
+        let path = webdriver::get_or_install_chromedriver(&cache, InstallMode::Normal)?;
+        PBAR.info(&format!("Prefetched chromedriver at {}", path.display()));
+    }
+    if opts.firefox {
+        let path = webdriver::get_or_install_geckodriver(&cache, InstallMode::Normal)?;
+        PBAR.info(&format!("Prefetched geckodriver at {}", path.display()));
+    }
+
+    if let Some(archive) = opts.export {
+        tools::export(&cache, &archive)?;
+    }
+    Ok(())
+}
+
+fn prefetched(tool: Tool, version: &str) {
+    PBAR.info(&format!("Prefetched {} {}", tool, version));
+}
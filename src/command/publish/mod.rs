@@ -8,6 +8,7 @@ use dialoguer::{Confirmation, Input, Select};
 use failure::Error;
 use log::info;
 use npm;
+use offline;
 use std::path::PathBuf;
 use std::result;
 use std::str::FromStr;
@@ -21,6 +22,7 @@ pub fn publish(
     access: Option<Access>,
     tag: Option<String>,
 ) -> result::Result<(), Error> {
+    offline::ensure_online("publish to npm")?;
     let crate_path = get_crate_path(path)?;
 
     info!("Publishing the npm package...");
@@ -50,8 +52,8 @@ pub fn publish(
                 let target = Target::from_str(&target)?;
                 let build_opts = BuildOptions {
                     path: Some(crate_path.clone()),
-                    target,
-                    out_dir: out_dir.clone(),
+                    target: vec![target],
+                    out_dir: Some(out_dir.clone()),
                     ..Default::default()
                 };
                 Build::try_from_opts(build_opts)
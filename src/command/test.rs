@@ -3,18 +3,22 @@
 use binary_install::Cache;
 use build;
 use cache;
-use command::utils::get_crate_path;
+use command::build::Target;
+use command::utils::{get_crate_path, run_step};
 use console::style;
+use deno;
 use failure::Error;
 use install::{self, InstallMode, Tool};
 use lockfile::Lockfile;
 use log::info;
 use manifest;
+use serde_json;
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::time::Instant;
 use structopt::clap::AppSettings;
 use test::{self, webdriver};
+use wasi;
 
 #[derive(Debug, Default, StructOpt)]
 #[structopt(
@@ -30,6 +34,15 @@ pub struct TestOptions {
     /// Run the tests in Node.js.
     pub node: bool,
 
+    #[structopt(long = "deno")]
+    /// Run the tests in Deno. The `deno` binary must be on the `$PATH`.
+    pub deno: bool,
+
+    #[structopt(long = "wasi")]
+    /// Compile the tests to WASI rather than with wasm-bindgen, and run them in
+    /// Node.js with its `node:wasi` module.
+    pub wasi: bool,
+
     #[structopt(long = "firefox")]
     /// Run the tests in Firefox. This machine must have a Firefox installation.
     /// If the `geckodriver` WebDriver client is not on the `$PATH`, and not
@@ -95,6 +108,8 @@ pub struct Test {
     crate_data: manifest::CrateData,
     cache: Cache,
     node: bool,
+    deno: bool,
+    wasi: bool,
     mode: InstallMode,
     firefox: bool,
     geckodriver: Option<PathBuf>,
@@ -104,6 +119,7 @@ pub struct Test {
     safaridriver: Option<PathBuf>,
     headless: bool,
     release: bool,
+    deno_path: Option<PathBuf>,
     test_runner_path: Option<PathBuf>,
     extra_options: Vec<String>,
 }
@@ -115,6 +131,8 @@ impl Test {
     pub fn try_from_opts(test_opts: TestOptions) -> Result<Self, Error> {
         let TestOptions {
             node,
+            deno,
+            wasi,
             mode,
             headless,
             release,
@@ -145,8 +163,15 @@ impl Test {
         let crate_data = manifest::CrateData::new(&crate_path, None)?;
         let any_browser = chrome || firefox || safari;
 
-        if !node && !any_browser {
-            bail!("Must specify at least one of `--node`, `--chrome`, `--firefox`, or `--safari`")
+        if !node && !deno && !any_browser && !wasi {
+            bail!(
+                "Must specify at least one of `--node`, `--deno`, `--chrome`, `--firefox`, \
+                 `--safari`, or `--wasi`"
+            )
+        }
+
+        if wasi && (node || deno || any_browser) {
+            bail!("`--wasi` tests cannot be combined with `--node`, `--deno` or browser tests")
         }
 
         if headless && !any_browser {
@@ -161,6 +186,8 @@ impl Test {
             crate_path,
             crate_data,
             node,
+            deno,
+            wasi,
             mode,
             chrome,
             chromedriver,
@@ -170,6 +197,7 @@ impl Test {
             safaridriver,
             headless,
             release,
+            deno_path: None,
             test_runner_path: None,
             extra_options,
         })
@@ -185,8 +213,8 @@ impl Test {
         let process_steps = self.get_process_steps();
 
         let started = Instant::now();
-        for (_, process_step) in process_steps {
-            process_step(&mut self)?;
+        for (name, process_step) in process_steps {
+            run_step(name, process_step, &mut self)?;
         }
         let duration = crate::command::utils::elapsed(started.elapsed());
         info!("Done in {}.", &duration);
@@ -214,8 +242,11 @@ impl Test {
                 step_check_rustc_version,
                 step_check_for_wasm_target,
                 step_build_tests,
-                step_install_wasm_bindgen,
+                step_install_wasm_bindgen if !self.wasi,
+                step_test_wasi if self.wasi,
                 step_test_node if self.node,
+                step_get_deno if self.deno,
+                step_test_deno if self.deno,
                 step_get_chromedriver if self.chrome && self.chromedriver.is_none(),
                 step_test_chrome if self.chrome,
                 step_get_geckodriver if self.firefox && self.geckodriver.is_none(),
@@ -226,8 +257,11 @@ impl Test {
             InstallMode::Force => steps![
                 step_check_for_wasm_target,
                 step_build_tests,
-                step_install_wasm_bindgen,
+                step_install_wasm_bindgen if !self.wasi,
+                step_test_wasi if self.wasi,
                 step_test_node if self.node,
+                step_get_deno if self.deno,
+                step_test_deno if self.deno,
                 step_get_chromedriver if self.chrome && self.chromedriver.is_none(),
                 step_test_chrome if self.chrome,
                 step_get_geckodriver if self.firefox && self.geckodriver.is_none(),
@@ -237,8 +271,11 @@ impl Test {
             ],
             InstallMode::Noinstall => steps![
                 step_build_tests,
-                step_install_wasm_bindgen,
+                step_install_wasm_bindgen if !self.wasi,
+                step_test_wasi if self.wasi,
                 step_test_node if self.node,
+                step_get_deno if self.deno,
+                step_test_deno if self.deno,
                 step_get_chromedriver if self.chrome && self.chromedriver.is_none(),
                 step_test_chrome if self.chrome,
                 step_get_geckodriver if self.firefox && self.geckodriver.is_none(),
@@ -258,7 +295,7 @@ impl Test {
 
     fn step_check_for_wasm_target(&mut self) -> Result<(), Error> {
         info!("Adding wasm-target...");
-        build::wasm_target::check_for_wasm32_target()?;
+        build::wasm_target::check_for_wasm32_target(self.rust_target())?;
         info!("Adding wasm-target was successful.");
         Ok(())
     }
@@ -274,7 +311,12 @@ impl Test {
             } else {
                 &self.extra_options
             };
-        build::cargo_build_wasm_tests(&self.crate_path, !self.release, extra_options)?;
+        build::cargo_build_wasm_tests(
+            &self.crate_path,
+            !self.release,
+            self.rust_target(),
+            extra_options,
+        )?;
 
         info!("Finished compiling tests to wasm.");
         Ok(())
@@ -284,6 +326,9 @@ impl Test {
         info!("Identifying wasm-bindgen dependency...");
         let lockfile = Lockfile::new(&self.crate_data)?;
         let bindgen_version = lockfile.require_wasm_bindgen()?;
+        if self.deno {
+            deno::check_test_runner(bindgen_version)?;
+        }
 
         // Unlike `wasm-bindgen` and `wasm-bindgen-cli`, `wasm-bindgen-test`
         // will work with any semver compatible `wasm-bindgen-cli`, so just make
@@ -321,6 +366,7 @@ impl Test {
         test::cargo_test_wasm(
             &self.crate_path,
             self.release,
+            self.rust_target(),
             vec![
                 (
                     "CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER",
@@ -334,6 +380,60 @@ impl Test {
         Ok(())
     }
 
+    fn step_get_deno(&mut self) -> Result<(), Error> {
+        assert!(self.deno);
+        self.deno_path = Some(deno::get_deno()?);
+        Ok(())
+    }
+
+    fn step_test_deno(&mut self) -> Result<(), Error> {
+        let deno_path = self.deno_path.as_ref().unwrap();
+        info!("Running tests in Deno at {}...", deno_path.display());
+        let path = deno::path_with(deno_path)?;
+        test::cargo_test_wasm(
+            &self.crate_path,
+            self.release,
+            self.rust_target(),
+            vec![
+                (
+                    "CARGO_TARGET_WASM32_UNKNOWN_UNKNOWN_RUNNER",
+                    self.test_runner_path.as_ref().unwrap().as_os_str(),
+                ),
+                ("WASM_BINDGEN_USE_DENO", "1".as_ref()),
+                ("PATH", &path),
+            ],
+            &self.extra_options,
+        )?;
+        info!("Finished running tests in Deno.");
+        Ok(())
+    }
+
+    fn step_test_wasi(&mut self) -> Result<(), Error> {
+        assert!(self.wasi);
+        info!("Running tests in node with WASI...");
+        let rust_target = self.rust_target();
+        let runner = wasi::write_test_runner(&self.crate_data)?;
+        // Cargo splits a runner given as a string on whitespace, so give it as
+        // an array to keep a path with spaces in one piece. JSON strings are
+        // valid TOML strings.
+        let runner = format!(
+            "target.{}.runner = [\"node\", {}]",
+            rust_target,
+            serde_json::to_string(&runner.to_string_lossy())?
+        );
+        let mut options = vec!["--config".to_string(), runner];
+        options.extend(self.extra_options.iter().cloned());
+        test::cargo_test_wasm(
+            &self.crate_path,
+            self.release,
+            rust_target,
+            Vec::<(&str, &str)>::new(),
+            &options,
+        )?;
+        info!("Finished running tests in node with WASI.");
+        Ok(())
+    }
+
     fn step_get_chromedriver(&mut self) -> Result<(), Error> {
         assert!(self.chrome && self.chromedriver.is_none());
 
@@ -355,7 +455,13 @@ impl Test {
         let mut envs = self.webdriver_env();
         envs.push(("CHROMEDRIVER", chromedriver));
 
-        test::cargo_test_wasm(&self.crate_path, self.release, envs, &self.extra_options)?;
+        test::cargo_test_wasm(
+            &self.crate_path,
+            self.release,
+            self.rust_target(),
+            envs,
+            &self.extra_options,
+        )?;
         Ok(())
     }
 
@@ -380,7 +486,13 @@ impl Test {
         let mut envs = self.webdriver_env();
         envs.push(("GECKODRIVER", geckodriver));
 
-        test::cargo_test_wasm(&self.crate_path, self.release, envs, &self.extra_options)?;
+        test::cargo_test_wasm(
+            &self.crate_path,
+            self.release,
+            self.rust_target(),
+            envs,
+            &self.extra_options,
+        )?;
         Ok(())
     }
 
@@ -402,10 +514,25 @@ impl Test {
         let mut envs = self.webdriver_env();
         envs.push(("SAFARIDRIVER", safaridriver));
 
-        test::cargo_test_wasm(&self.crate_path, self.release, envs, &self.extra_options)?;
+        test::cargo_test_wasm(
+            &self.crate_path,
+            self.release,
+            self.rust_target(),
+            envs,
+            &self.extra_options,
+        )?;
         Ok(())
     }
 
+    /// The Rust target that the tests are compiled to.
+    fn rust_target(&self) -> &'static str {
+        if self.wasi {
+            Target::Wasi.rust_target()
+        } else {
+            Target::Bundler.rust_target()
+        }
+    }
+
     fn webdriver_env(&self) -> Vec<(&'static str, &str)> {
         let test_runner = self.test_runner_path.as_ref().unwrap().to_str().unwrap();
         info!("Using wasm-bindgen test runner at {}", test_runner);
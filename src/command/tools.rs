@@ -0,0 +1,494 @@
+//! Implementation of the `wasm-pack tools` command, which shows and manages
+//! the tools in wasm-pack's cache.
+
+use binary_install::Cache;
+use cache;
+use chrono::{DateTime, Duration, Local};
+use command::utils::get_crate_path;
+use failure::{Error, ResultExt};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use install::index::{self, Source};
+use install::{self, InstallMode, InstallPlan, Tool};
+use lockfile::Lockfile;
+use manifest::CrateData;
+use progressbar::Event;
+use serde_json;
+use size::human_size;
+use stamps;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+use tar;
+use walkdir::WalkDir;
+use wasm_opt;
+use wasm_snip;
+use PBAR;
+
+/// The subcommands of `wasm-pack tools`.
+#[derive(Debug, StructOpt)]
+pub enum ToolsCommand {
+    #[structopt(name = "list")]
+    /// List the tools in wasm-pack's cache
+    List,
+
+    #[structopt(name = "install")]
+    /// Install a tool into wasm-pack's cache
+    Install {
+        /// The tool to install, as `<tool>@<version>`, e.g.
+        /// `wasm-bindgen@0.2.74`. Without a version, the version that a build
+        /// would use is installed.
+        tool: String,
+
+        #[structopt(long = "path", parse(from_os_str))]
+        /// The crate whose Cargo.lock has the version of wasm-bindgen to
+        /// install. If not set, searches up the path from the current
+        /// directory.
+        path: Option<PathBuf>,
+    },
+
+    #[structopt(name = "prune")]
+    /// Remove the tools that no build has used recently from wasm-pack's cache
+    Prune {
+        #[structopt(long = "days", default_value = "30")]
+        /// Remove the tools that no build has used for this many days.
+        days: u32,
+
+        #[structopt(long = "dry-run")]
+        /// Only print what would be removed.
+        dry_run: bool,
+    },
+
+    #[structopt(name = "export")]
+    /// Write the tools in wasm-pack's cache to a tarball
+    Export {
+        /// The tarball to write, e.g. `wasm-pack-cache.tar.gz`.
+        #[structopt(parse(from_os_str))]
+        archive: PathBuf,
+    },
+
+    #[structopt(name = "import")]
+    /// Add the tools in a tarball written by `export` to wasm-pack's cache
+    Import {
+        /// The tarball to read.
+        #[structopt(parse(from_os_str))]
+        archive: PathBuf,
+    },
+
+    #[structopt(name = "which")]
+    /// Print the path of the binary that a build would run for a tool
+    Which {
+        /// The tool, as `<tool>` or `<tool>@<version>`. Without a version,
+        /// the version that a build would use is looked up.
+        tool: String,
+
+        #[structopt(long = "path", parse(from_os_str))]
+        /// The crate whose Cargo.lock has the version of wasm-bindgen to look
+        /// up. If not set, searches up the path from the current directory.
+        path: Option<PathBuf>,
+    },
+}
+
+/// A tool in wasm-pack's cache.
+#[derive(Debug, Serialize)]
+pub struct CachedTool {
+    /// The name of the tool.
+    pub tool: String,
+    /// Its version, unless it was downloaded before wasm-pack kept track of
+    /// versions.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// How it got into the cache.
+    pub source: Source,
+    /// The directory it is in.
+    pub path: PathBuf,
+    /// The size of the directory, in bytes.
+    pub size: u64,
+    /// When a build last used it, or else when it was installed, in RFC 3339
+    /// format.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_used: Option<String>,
+}
+
+impl CachedTool {
+    fn last_used(&self) -> Option<DateTime<Local>> {
+        let last_used = DateTime::parse_from_rfc3339(self.last_used.as_ref()?).ok()?;
+        Some(last_used.with_timezone(&Local))
+    }
+
+    fn describe(&self) -> String {
+        match &self.version {
+            Some(version) => format!("{} {} ({})", self.tool, version, human_size(self.size)),
+            None => format!("{} ({})", self.tool, human_size(self.size)),
+        }
+    }
+}
+
+/// Run a `wasm-pack tools` subcommand.
+pub fn tools(command: ToolsCommand) -> Result<(), Error> {
+    match command {
+        ToolsCommand::List => list(),
+        ToolsCommand::Install { tool, path } => install(&tool, path),
+        ToolsCommand::Prune { days, dry_run } => prune(days, dry_run),
+        ToolsCommand::Export { archive } => export(&cache::get_wasm_pack_cache()?, &archive),
+        ToolsCommand::Import { archive } => import(&cache::get_wasm_pack_cache()?, &archive),
+        ToolsCommand::Which { tool, path } => which(&tool, path),
+    }
+}
+
+fn list() -> Result<(), Error> {
+    let cache = cache::get_wasm_pack_cache()?;
+    let tools = cached_tools(&cache)?;
+    if PBAR.json() {
+        PBAR.event(&Event::Tools {
+            tools: serde_json::to_value(&tools)?,
+        });
+        return Ok(());
+    }
+    if tools.is_empty() {
+        PBAR.info(&format!(
+            "There are no tools in wasm-pack's cache at {}.",
+            cache_dir(&cache).display()
+        ));
+        return Ok(());
+    }
+    println!(
+        "{:<16} {:<12} {:<14} {:>10}  {:<10}  PATH",
+        "TOOL", "VERSION", "SOURCE", "SIZE", "LAST USED"
+    );
+    for tool in &tools {
+        println!(
+            "{:<16} {:<12} {:<14} {:>10}  {:<10}  {}",
+            tool.tool,
+            tool.version.as_ref().map_or("-", String::as_str),
+            tool.source.name(),
+            human_size(tool.size),
+            tool.last_used()
+                .map_or_else(|| "-".to_string(), |t| t.format("%Y-%m-%d").to_string()),
+            tool.path.display()
+        );
+    }
+    Ok(())
+}
+
+fn install(spec: &str, path: Option<PathBuf>) -> Result<(), Error> {
+    let (tool, version) = parse_spec(spec, path)?;
+    let cache = cache::get_wasm_pack_cache()?;
+    let install_permitted = InstallMode::Normal.install_permitted();
+    let status = match tool {
+        Tool::WasmOpt => install::download_prebuilt(&tool, &cache, &version, install_permitted)?,
+        _ => {
+            install::download_prebuilt_or_cargo_install(tool, &cache, &version, install_permitted)?
+        }
+    };
+    let binary = install::get_tool_path(&status, tool)?.binary(&tool.to_string())?;
+    PBAR.info(&format!(
+        "{} {} is installed at {}",
+        tool,
+        version,
+        binary.display()
+    ));
+    Ok(())
+}
+
+fn prune(days: u32, dry_run: bool) -> Result<(), Error> {
+    let cache = cache::get_wasm_pack_cache()?;
+    let cutoff = Local::now() - Duration::days(i64::from(days));
+    let unused: Vec<_> = cached_tools(&cache)?
+        .into_iter()
+        .filter(|tool| match tool.last_used() {
+            Some(last_used) => last_used < cutoff,
+            None => true,
+        })
+        .collect();
+
+    let mut removed = Vec::new();
+    let mut freed = 0;
+    for tool in &unused {
+        if dry_run {
+            PBAR.info(&format!("Would remove {}", tool.describe()));
+            continue;
+        }
+        let dirname = match tool.path.file_name() {
+            Some(dirname) => dirname.to_string_lossy().into_owned(),
+            None => continue,
+        };
+        let _lock = cache::lock(&cache, &dirname, Some(&format!("installing {}", tool.tool)))?;
+        fs::remove_dir_all(&tool.path)
+            .with_context(|_| format!("failed to remove {}", tool.path.display()))?;
+        removed.push(dirname);
+        freed += tool.size;
+        PBAR.info(&format!("Removed {}", tool.describe()));
+    }
+    if !removed.is_empty() {
+        index::update(&cache, |index| {
+            for dirname in &removed {
+                index.remove(dirname);
+            }
+        })?;
+    }
+
+    if unused.is_empty() {
+        PBAR.info(&format!(
+            "Every tool in wasm-pack's cache was used in the last {} days.",
+            days
+        ));
+    } else if dry_run {
+        let size = unused.iter().map(|tool| tool.size).sum();
+        PBAR.info(&format!("Pruning would free {}.", human_size(size)));
+    } else {
+        PBAR.info(&format!("Freed {}.", human_size(freed)));
+    }
+    Ok(())
+}
+
+fn which(spec: &str, path: Option<PathBuf>) -> Result<(), Error> {
+    let (tool, version) = parse_spec(spec, path)?;
+    let cache = cache::get_wasm_pack_cache()?;
+    let install_permitted = InstallMode::Normal.install_permitted();
+    let plan = match tool {
+        Tool::WasmOpt if version == wasm_opt::WASM_OPT_VERSION => {
+            wasm_opt::plan(&cache, install_permitted)
+        }
+        Tool::WasmOpt => install::plan_prebuilt(&tool, &cache, &version, install_permitted),
+        _ => install::plan_prebuilt_or_cargo_install(tool, &cache, &version, install_permitted)?,
+    };
+    match plan {
+        InstallPlan::Global { path } | InstallPlan::Cached { path } => {
+            println!("{}", path.display());
+            Ok(())
+        }
+        plan => bail!("{} {} isn't installed ({})", tool, version, plan),
+    }
+}
+
+/// Write the tools in `cache` to the gzipped tarball `archive`, along with
+/// the record of them and the versions of the WebDriver clients that were
+/// looked up last. Those are stamps, whose values are always strings.
+pub fn export(cache: &Cache, archive: &Path) -> Result<(), Error> {
+    let tools = cached_tools(cache)?;
+    let file = fs::File::create(archive)
+        .with_context(|_| format!("failed to create {}", archive.display()))?;
+    let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+    for tool in &tools {
+        let dirname = tool.path.file_name().unwrap();
+        builder.append_dir_all(dirname, &tool.path)?;
+    }
+    let index_path = index::index_path(cache);
+    if index_path.is_file() {
+        builder.append_path_with_name(&index_path, index::INDEX_FILE)?;
+    }
+    if let Ok(json) = stamps::read_stamps_file_to_json() {
+        let contents = serde_json::to_vec_pretty(&json)?;
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, STAMPS_FILE, &contents[..])?;
+    }
+    builder
+        .into_inner()?
+        .finish()
+        .with_context(|_| format!("failed to write {}", archive.display()))?;
+
+    let size = tools.iter().map(|tool| tool.size).sum();
+    PBAR.info(&format!(
+        "Exported {} tools ({}) to {}",
+        tools.len(),
+        human_size(size),
+        archive.display()
+    ));
+    Ok(())
+}
+
+/// Add the tools in the tarball `archive`, written by `export`, to `cache`.
+/// Tools that are already in the cache are kept.
+pub fn import(cache: &Cache, archive: &Path) -> Result<(), Error> {
+    let dir = cache_dir(cache);
+    let temp = cache.join(format!(".import-{}", process::id()).as_ref());
+    drop(fs::remove_dir_all(&temp));
+    fs::create_dir_all(&temp)?;
+    let result = import_from(cache, archive, &temp);
+    drop(fs::remove_dir_all(&temp));
+    let (imported, existing) = result?;
+    PBAR.info(&format!(
+        "Imported {} tools into {}{}",
+        imported,
+        dir.display(),
+        match existing {
+            0 => String::new(),
+            n => format!(", and kept the {} that were already there", n),
+        }
+    ));
+    Ok(())
+}
+
+fn import_from(cache: &Cache, archive: &Path, temp: &Path) -> Result<(usize, usize), Error> {
+    let file = fs::File::open(archive)
+        .with_context(|_| format!("failed to open {}", archive.display()))?;
+    // `unpack` refuses to write outside of `temp`.
+    tar::Archive::new(GzDecoder::new(file))
+        .unpack(temp)
+        .with_context(|_| format!("failed to extract {}", archive.display()))?;
+
+    let imported_index = index::read(&Cache::at(temp))?;
+    let mut entries = Vec::new();
+    let (mut imported, mut existing) = (0, 0);
+    for entry in fs::read_dir(temp)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let dirname = entry.file_name().to_string_lossy().into_owned();
+        let _lock = cache::lock(cache, &dirname, Some(&format!("installing {}", dirname)))?;
+        let destination = cache.join(dirname.as_ref());
+        if destination.exists() {
+            existing += 1;
+            continue;
+        }
+        fs::rename(entry.path(), &destination)?;
+        if let Some(entry) = imported_index.get(&dirname) {
+            entries.push((dirname, entry.clone()));
+        }
+        imported += 1;
+    }
+    index::update(cache, |index| index.extend(entries))?;
+
+    let stamps_path = temp.join(STAMPS_FILE);
+    if stamps_path.is_file() {
+        let imported_stamps: BTreeMap<String, String> =
+            serde_json::from_str(&fs::read_to_string(&stamps_path)?)
+                .with_context(|_| format!("{} has invalid stamps", archive.display()))?;
+        // The archive may be older than the local state, so never let it
+        // replace a value that is already known here.
+        let local = stamps::read_stamps_file_to_json().unwrap_or(serde_json::Value::Null);
+        for (key, value) in imported_stamps {
+            if stamps::get_stamp_value(&key, &local).is_err() {
+                stamps::save_stamp_value(key, value)?;
+            }
+        }
+    }
+    Ok((imported, existing))
+}
+
+/// Split `<tool>@<version>` into the tool and its version. Without a version,
+/// it is the version that a build of the crate at `path` would use.
+fn parse_spec(spec: &str, path: Option<PathBuf>) -> Result<(Tool, String), Error> {
+    let (name, version) = match spec.find('@') {
+        Some(at) => (&spec[..at], Some(&spec[at + 1..])),
+        None => (spec, None),
+    };
+    let tool: Tool = name.parse()?;
+    let version = match version {
+        Some("") => bail!("`{}` has no version after the `@`", spec),
+        Some(version) => version.to_string(),
+        None => default_version(tool, path)?,
+    };
+    Ok((tool, version))
+}
+
+/// The version of `tool` that a build of the crate at `path` would use.
+fn default_version(tool: Tool, path: Option<PathBuf>) -> Result<String, Error> {
+    match tool {
+        Tool::CargoGenerate => Ok("latest".to_string()),
+        Tool::WasmOpt => Ok(wasm_opt::WASM_OPT_VERSION.to_string()),
+        Tool::WasmSnip => Ok(wasm_snip::WASM_SNIP_VERSION.to_string()),
+        Tool::WasmBindgen => {
+            let version = get_crate_path(path)
+                .and_then(|crate_path| CrateData::new(&crate_path, None))
+                .and_then(|crate_data| {
+                    let lockfile = Lockfile::new(&crate_data)?;
+                    Ok(lockfile.require_wasm_bindgen()?.to_string())
+                })
+                .context(
+                    "the version of wasm-bindgen is read from the crate's Cargo.lock; give one \
+                     as `wasm-bindgen@<version>` instead",
+                )?;
+            Ok(version)
+        }
+    }
+}
+
+/// The file in exported tarballs with wasm-pack's stamps.
+const STAMPS_FILE: &str = "wasm-pack.stamps";
+
+fn cache_dir(cache: &Cache) -> PathBuf {
+    cache.join(Path::new(""))
+}
+
+/// The tools in `cache`, sorted by name and version.
+pub fn cached_tools(cache: &Cache) -> Result<Vec<CachedTool>, Error> {
+    let dir = cache_dir(cache);
+    if !dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let index = index::read(cache)?;
+    let mut tools = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let entry = entry?;
+        let dirname = entry.file_name().to_string_lossy().into_owned();
+        // Directories that start with a `.` are installs in progress.
+        if !entry.file_type()?.is_dir() || dirname.starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        let (tool, version, source, last_used) = match index.get(&dirname) {
+            Some(entry) => (
+                entry.tool.clone(),
+                Some(entry.version.clone()),
+                entry.source,
+                Some(entry.last_used.clone()),
+            ),
+            None => {
+                let (tool, version, source) = match parse_dirname(&dirname) {
+                    Some(parsed) => parsed,
+                    None => continue,
+                };
+                let installed = entry
+                    .metadata()?
+                    .modified()
+                    .ok()
+                    .map(|time| DateTime::<Local>::from(time).to_rfc3339());
+                (tool, version, source, installed)
+            }
+        };
+        let size = WalkDir::new(&path)
+            .into_iter()
+            .filter_map(|entry| entry.ok()?.metadata().ok())
+            .filter(|metadata| metadata.is_file())
+            .map(|metadata| metadata.len())
+            .sum();
+        tools.push(CachedTool {
+            tool,
+            version,
+            source,
+            path,
+            size,
+            last_used,
+        });
+    }
+    tools.sort_by(|a, b| (&a.tool, &a.version).cmp(&(&b.tool, &b.version)));
+    Ok(tools)
+}
+
+/// Work out what is in a directory of the cache from its name, for tools that
+/// were installed before wasm-pack kept a record of them.
+fn parse_dirname(dirname: &str) -> Option<(String, Option<String>, Source)> {
+    if let Some(at) = dirname.find("-cargo-install-") {
+        let version = &dirname[at + "-cargo-install-".len()..];
+        return Some((
+            dirname[..at].to_string(),
+            Some(version.to_string()),
+            Source::CargoInstall,
+        ));
+    }
+    // Downloads are in `<tool>-<hash of the URL>`.
+    let dash = dirname.rfind('-')?;
+    let hash = &dirname[dash + 1..];
+    if dash == 0 || hash.len() != 16 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    Some((dirname[..dash].to_string(), None, Source::Download))
+}
@@ -2,10 +2,12 @@
 #![allow(clippy::redundant_closure)]
 
 use failure;
+use progressbar::{duration_ms, Event};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use walkdir::WalkDir;
+use PBAR;
 
 /// If an explicit path is given, then use it, otherwise assume the current
 /// directory is the crate path.
@@ -59,6 +61,24 @@ fn is_pkg_directory(path: &Path) -> bool {
     path.exists() && path.is_dir() && path.ends_with("pkg")
 }
 
+/// Run a single step of a command, reporting when it starts and finishes.
+pub fn run_step<T>(
+    name: &str,
+    step: fn(&mut T) -> Result<(), failure::Error>,
+    command: &mut T,
+) -> Result<(), failure::Error> {
+    let step_name = name.trim_start_matches("step_");
+    PBAR.event(&Event::StepStarted { step: step_name });
+    let started = Instant::now();
+    let result = step(command);
+    PBAR.event(&Event::StepFinished {
+        step: step_name,
+        success: result.is_ok(),
+        duration_ms: duration_ms(started.elapsed()),
+    });
+    result
+}
+
 /// Render a `Duration` to a form suitable for display on a console
 pub fn elapsed(duration: Duration) -> String {
     let secs = duration.as_secs();
@@ -0,0 +1,83 @@
+//! wasm-pack's own config file, with settings for the machine rather than for
+//! a crate: the mirrors that tools are downloaded from and the digests that
+//! the downloads are checked against.
+
+use cache;
+use failure::{Error, ResultExt};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use toml;
+
+/// The environment variable with the path of wasm-pack's config file, which
+/// defaults to `config.toml` in wasm-pack's cache.
+pub const CONFIG_ENV_VAR: &str = "WASM_PACK_CONFIG";
+
+/// The tools that wasm-pack downloads, and which can be configured in the
+/// config file.
+pub const DOWNLOADED_TOOLS: &[&str] = &[
+    "wasm-bindgen",
+    "cargo-generate",
+    "wasm-opt",
+    "chromedriver",
+    "geckodriver",
+];
+
+/// The contents of wasm-pack's config file.
+#[derive(Default, Deserialize)]
+pub struct Config {
+    /// The `[mirrors]` table: the URL template of each tool.
+    #[serde(default)]
+    pub mirrors: BTreeMap<String, String>,
+    /// The `[checksums.<tool>]` tables: the SHA-256 digests of the archives
+    /// of each tool, by `<version>-<target>`.
+    #[serde(default)]
+    pub checksums: BTreeMap<String, BTreeMap<String, String>>,
+    /// Whether to refuse archives that no digest is known for.
+    #[serde(default, rename = "require-checksums")]
+    pub require_checksums: bool,
+}
+
+/// The path of wasm-pack's config file.
+pub fn config_path() -> Result<PathBuf, Error> {
+    match env::var_os(CONFIG_ENV_VAR) {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => Ok(cache::get_wasm_pack_cache()?.join("config.toml".as_ref())),
+    }
+}
+
+/// Read wasm-pack's config file, which is empty if there is none.
+pub fn read() -> Result<Config, Error> {
+    let path = config_path()?;
+    if !path.is_file() {
+        return Ok(Config::default());
+    }
+    let contents =
+        fs::read_to_string(&path).with_context(|_| format!("failed to read {}", path.display()))?;
+    let config: Config = toml::from_str(&contents)
+        .with_context(|_| format!("failed to parse {}", path.display()))?;
+    let tables = [
+        ("mirrors", config.mirrors.keys().collect::<Vec<_>>()),
+        ("checksums", config.checksums.keys().collect()),
+    ];
+    for (table, tools) in &tables {
+        for tool in tools {
+            if !DOWNLOADED_TOOLS.contains(&tool.as_str()) {
+                bail!(
+                    "{} configures `{}` in `[{}]`, which wasm-pack doesn't download. \
+                     It downloads {}.",
+                    path.display(),
+                    tool,
+                    table,
+                    DOWNLOADED_TOOLS
+                        .iter()
+                        .map(|name| format!("`{}`", name))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+        }
+    }
+    Ok(config)
+}
@@ -0,0 +1,96 @@
+//! Splitting the DWARF debug info of a wasm binary into a separate file.
+//!
+//! The debug file is the complete binary, because DWARF refers to offsets in
+//! its code section. The binary itself loses its `.debug_*` custom sections
+//! and gains an `external_debug_info` section pointing at the debug file,
+//! which is where debuggers like Chrome's DevTools look for it.
+
+use failure::ResultExt;
+use size::Reader;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The name of the custom section that points at the split debug info.
+pub const EXTERNAL_DEBUG_INFO: &str = "external_debug_info";
+
+/// The path of the debug file that `split` writes for `wasm`, e.g.
+/// `foo_bg.debug.wasm` for `foo_bg.wasm`.
+pub fn debug_file_path(wasm: &Path) -> PathBuf {
+    wasm.with_extension("debug.wasm")
+}
+
+/// Move the DWARF debug info of the binary at `wasm` into the file at
+/// `debug_file_path(wasm)`. Returns whether there was any debug info to move.
+pub fn split(wasm: &Path) -> Result<bool, failure::Error> {
+    let data = fs::read(wasm).with_context(|_| format!("failed to read {}", wasm.display()))?;
+    let debug_path = debug_file_path(wasm);
+    let debug_name = debug_path.file_name().unwrap().to_string_lossy();
+
+    let stripped = match strip(&data, &debug_name)? {
+        Some(stripped) => stripped,
+        None => return Ok(false),
+    };
+    fs::write(&debug_path, &data)
+        .with_context(|_| format!("failed to write {}", debug_path.display()))?;
+    fs::write(wasm, &stripped).with_context(|_| format!("failed to write {}", wasm.display()))?;
+    Ok(true)
+}
+
+/// Remove the `.debug_*` custom sections from `wasm`, and point at the debug
+/// info in `url` instead. Returns `None` if there is no debug info to remove.
+pub fn strip(wasm: &[u8], url: &str) -> Result<Option<Vec<u8>>, failure::Error> {
+    if wasm.len() < 8 || &wasm[..4] != b"\0asm" {
+        bail!("not a wasm binary");
+    }
+    let mut stripped = wasm[..8].to_vec();
+    let mut found = false;
+
+    let mut reader = Reader::new(&wasm[8..]);
+    while !reader.is_empty() {
+        let start = reader.position();
+        let id = reader.byte()?;
+        let len = reader.u32()? as usize;
+        let payload = reader.bytes(len)?;
+        if id == 0 {
+            let name = Reader::new(payload).name()?;
+            if name.starts_with(".debug_") {
+                found = true;
+                continue;
+            }
+            // A previous link to debug info is replaced below.
+            if name == EXTERNAL_DEBUG_INFO {
+                continue;
+            }
+        }
+        stripped.extend_from_slice(&wasm[8 + start..8 + reader.position()]);
+    }
+    if !found {
+        return Ok(None);
+    }
+
+    let mut payload = Vec::new();
+    write_name(&mut payload, EXTERNAL_DEBUG_INFO);
+    write_name(&mut payload, url);
+    stripped.push(0);
+    write_u32(&mut stripped, payload.len() as u32);
+    stripped.extend(payload);
+    Ok(Some(stripped))
+}
+
+fn write_name(out: &mut Vec<u8>, name: &str) {
+    write_u32(out, name.len() as u32);
+    out.extend_from_slice(name.as_bytes());
+}
+
+/// Write an unsigned LEB128 encoded integer.
+fn write_u32(out: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
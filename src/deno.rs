@@ -0,0 +1,77 @@
+//! Packaging the output of `--target deno` as a Deno module, and finding the
+//! `deno` binary that runs tests.
+
+use failure::{Error, ResultExt};
+use semver::Version;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+use which::which;
+
+/// The configuration file of a Deno package, which takes the place of
+/// `package.json`.
+pub const CONFIG_FILE: &str = "deno.json";
+
+/// The entry point of a Deno package.
+pub const MODULE_FILE: &str = "mod.ts";
+
+/// The `mod.ts` that re-exports the bindings in `<name_prefix>.js`, along with
+/// their types unless `disable_dts` is set.
+pub fn module(name_prefix: &str, disable_dts: bool) -> String {
+    let mut module = String::new();
+    if !disable_dts {
+        module.push_str(&format!("// @deno-types=\"./{}.d.ts\"\n", name_prefix));
+    }
+    module.push_str(&format!("export * from \"./{}.js\";\n", name_prefix));
+    module
+}
+
+/// Write the `mod.ts` of the bindings in `<name_prefix>.js` into `out_dir`.
+pub fn write_module(out_dir: &Path, name_prefix: &str, disable_dts: bool) -> Result<(), Error> {
+    let path = out_dir.join(MODULE_FILE);
+    fs::write(&path, module(name_prefix, disable_dts))
+        .with_context(|_| format!("failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// Find the `deno` binary on the `$PATH`, which is where
+/// `wasm-bindgen-test-runner` looks for it.
+pub fn get_deno() -> Result<PathBuf, Error> {
+    match which("deno") {
+        Ok(p) => Ok(p),
+        Err(_) => bail!(
+            "could not find `deno` on the `$PATH`. Install it from https://deno.land to run \
+             `--deno` tests."
+        ),
+    }
+}
+
+/// The first version of `wasm-bindgen-test-runner` that runs tests in Deno
+/// when `WASM_BINDGEN_USE_DENO` is set. Older ones silently run them in
+/// Node.js instead.
+pub const MIN_TEST_RUNNER_VERSION: &str = "0.2.84";
+
+/// Check that the `wasm-bindgen-test-runner` of the given wasm-bindgen version
+/// can run tests in Deno.
+pub fn check_test_runner(bindgen_version: &str) -> Result<(), Error> {
+    let version = Version::parse(bindgen_version)?;
+    if version < Version::parse(MIN_TEST_RUNNER_VERSION)? {
+        bail!(
+            "the wasm-bindgen-test-runner of wasm-bindgen {} can't run tests in Deno. Update \
+             wasm-bindgen to {} or later to run `--deno` tests.",
+            bindgen_version,
+            MIN_TEST_RUNNER_VERSION
+        );
+    }
+    Ok(())
+}
+
+/// The `$PATH` with the directory of `deno` in front, so that
+/// `wasm-bindgen-test-runner` runs the `deno` that was found.
+pub fn path_with(deno: &Path) -> Result<OsString, Error> {
+    let mut paths: Vec<PathBuf> = deno.parent().map(Path::to_path_buf).into_iter().collect();
+    if let Some(path) = env::var_os("PATH") {
+        paths.extend(env::split_paths(&path));
+    }
+    Ok(env::join_paths(paths)?)
+}
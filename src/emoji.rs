@@ -28,3 +28,5 @@ pub static INFO: Emoji = Emoji("ℹ️  ", "");
 pub static WRENCH: Emoji = Emoji("🔧  ", "");
 pub static CRAB: Emoji = Emoji("🦀  ", "");
 pub static SHEEP: Emoji = Emoji("🐑 ", "");
+pub static EYES: Emoji = Emoji("👀  ", "");
+pub static RULER: Emoji = Emoji("📏  ", "");
@@ -0,0 +1,110 @@
+//! Fingerprints of the inputs of build steps.
+//!
+//! `wasm-pack build` stores a fingerprint for each of its output-producing
+//! steps in the out dir, so that a later build can skip steps whose inputs
+//! haven't changed since they last ran.
+
+use failure::{self, ResultExt};
+use lock;
+use serde_json;
+use siphasher::sip128::{Hasher128, SipHasher13};
+use std::collections::BTreeMap;
+use std::fs;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+
+const FINGERPRINT_FILE: &str = ".wasm-pack-fingerprint";
+
+/// A hash over all the inputs of a single build step.
+pub struct Fingerprint(SipHasher13);
+
+impl Fingerprint {
+    /// Start a new fingerprint. Every fingerprint covers the version of
+    /// wasm-pack itself, since that determines how each step behaves.
+    pub fn new() -> Fingerprint {
+        let mut fingerprint = Fingerprint(SipHasher13::new());
+        fingerprint.add(env!("CARGO_PKG_VERSION"));
+        fingerprint
+    }
+
+    /// Add an input to the fingerprint.
+    pub fn add(&mut self, input: impl AsRef<[u8]>) -> &mut Fingerprint {
+        let input = input.as_ref();
+        // Prefix each input with its length so that ["ab", "c"] and
+        // ["a", "bc"] hash differently.
+        self.0.write_usize(input.len());
+        self.0.write(input);
+        self
+    }
+
+    /// Add the contents of the file at `path` to the fingerprint. Missing
+    /// files are fingerprinted as such, so that creating them later on is
+    /// noticed.
+    pub fn add_file(&mut self, path: &Path) -> &mut Fingerprint {
+        match fs::read(path) {
+            Ok(contents) => self.add("file").add(contents),
+            Err(_) => self.add("missing"),
+        }
+    }
+
+    /// Render the fingerprint as a hex string.
+    pub fn finish(&self) -> String {
+        let hash = self.0.finish128();
+        format!("{:016x}{:016x}", hash.h1, hash.h2)
+    }
+}
+
+impl Default for Fingerprint {
+    fn default() -> Fingerprint {
+        Fingerprint::new()
+    }
+}
+
+/// The fingerprints recorded by the last build into an out dir, keyed by step.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Fingerprints {
+    #[serde(skip)]
+    path: PathBuf,
+    steps: BTreeMap<String, String>,
+}
+
+impl Fingerprints {
+    /// Load the fingerprints stored in `out_dir`. A missing or unreadable file
+    /// yields an empty set, which causes every step to run.
+    pub fn load(out_dir: &Path) -> Fingerprints {
+        let path = out_dir.join(FINGERPRINT_FILE);
+        let mut fingerprints = fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str::<Fingerprints>(&contents).ok())
+            .unwrap_or_default();
+        fingerprints.path = path;
+        fingerprints
+    }
+
+    /// Whether `step` last ran with exactly this `fingerprint`.
+    pub fn is_fresh(&self, step: &str, fingerprint: &str) -> bool {
+        self.steps.get(step).map(|f| f.as_str()) == Some(fingerprint)
+    }
+
+    /// Record that `step` completed with `fingerprint` and persist it.
+    pub fn record(&mut self, step: &str, fingerprint: String) -> Result<(), failure::Error> {
+        self.steps.insert(step.to_string(), fingerprint);
+        self.save()
+    }
+
+    /// Forget about `step`, e.g. because it is about to overwrite its outputs,
+    /// and persist that.
+    pub fn forget(&mut self, step: &str) -> Result<(), failure::Error> {
+        if self.steps.remove(step).is_some() {
+            self.save()?;
+        }
+        Ok(())
+    }
+
+    fn save(&self) -> Result<(), failure::Error> {
+        let json = serde_json::to_string_pretty(&self).context("JSON serialization failed")?;
+        lock::write_atomically(&self.path, json.as_bytes())
+            .with_context(|_| format!("failed to write: {}", self.path.display()))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+//! Commands that a crate declares in `[package.metadata.wasm-pack.hooks]` to
+//! be run at certain points of `wasm-pack build`.
+
+use child;
+use failure::{Error, ResultExt};
+use serde::{Deserialize, Deserializer};
+use std::ffi::OsString;
+use std::fmt;
+use std::path::Path;
+use std::process::Command;
+use PBAR;
+
+/// The points of a build at which hooks run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookPoint {
+    /// Before the crate is compiled with `cargo build`.
+    PreBuild,
+    /// After `wasm-bindgen` generated the bindings, or the WASI binaries were
+    /// copied, and before they are optimized.
+    PostBindgen,
+    /// After the `package.json` (or `deno.json`) was written.
+    PostPackageJson,
+}
+
+impl fmt::Display for HookPoint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            HookPoint::PreBuild => "pre-build",
+            HookPoint::PostBindgen => "post-bindgen",
+            HookPoint::PostPackageJson => "post-package-json",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// The `[package.metadata.wasm-pack.hooks]` table of a crate. Each hook is a
+/// command line, or a list of them, that is run with the system shell.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Hooks {
+    #[serde(default, rename = "pre-build", deserialize_with = "one_or_many")]
+    pre_build: Vec<String>,
+    #[serde(default, rename = "post-bindgen", deserialize_with = "one_or_many")]
+    post_bindgen: Vec<String>,
+    #[serde(
+        default,
+        rename = "post-package-json",
+        deserialize_with = "one_or_many"
+    )]
+    post_package_json: Vec<String>,
+}
+
+impl Hooks {
+    /// The command lines to run at `point`.
+    pub fn commands(&self, point: HookPoint) -> &[String] {
+        match point {
+            HookPoint::PreBuild => &self.pre_build,
+            HookPoint::PostBindgen => &self.post_bindgen,
+            HookPoint::PostPackageJson => &self.post_package_json,
+        }
+    }
+}
+
+fn one_or_many<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(command) => vec![command],
+        OneOrMany::Many(commands) => commands,
+    })
+}
+
+/// The command that runs the hook `line` with the system shell in `dir`.
+pub fn command(line: &str, dir: &Path) -> Command {
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C");
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c");
+        cmd
+    };
+    cmd.arg(line).current_dir(dir);
+    cmd
+}
+
+/// Run the hooks of `point` in `dir` one after the other, with the variables
+/// in `env` set. Stops at the first hook that fails.
+pub fn run(
+    hooks: &Hooks,
+    point: HookPoint,
+    dir: &Path,
+    env: &[(&str, OsString)],
+) -> Result<(), Error> {
+    for line in hooks.commands(point) {
+        PBAR.info(&format!("Running {} hook `{}`...", point, line));
+        let mut cmd = command(line, dir);
+        cmd.env("WASM_PACK_HOOK", point.to_string());
+        cmd.envs(env.iter().map(|(key, value)| (key, value)));
+        child::run(cmd, line).with_context(|_| format!("the {} hook failed", point))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,193 @@
+//! Downloading the archives of tools into the cache, after checking their
+//! digests.
+//!
+//! This keeps the layout of `binary_install::Cache::download`, so that tools
+//! can still be looked up with it.
+
+use binary_install::{Cache, Download};
+use cache;
+use checksum;
+use curl;
+use failure::{Error, ResultExt};
+use flate2;
+use siphasher::sip::SipHasher13;
+use std::collections::HashSet;
+use std::ffi::OsStr;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+use tar;
+use zip;
+use PBAR;
+
+/// Like `Cache::download`, but checks the archive against the SHA-256 digest
+/// `expected`, if there is one, before it is extracted. An archive with
+/// another digest is not cached.
+pub fn download(
+    cache: &Cache,
+    install_permitted: bool,
+    name: &str,
+    binaries: &[&str],
+    url: &str,
+    expected: Option<&str>,
+) -> Result<Option<Download>, Error> {
+    let dirname = hashed_dirname(url, name);
+    let destination = cache.join(dirname.as_ref());
+    if destination.exists() {
+        return Ok(Some(Download::at(&destination)));
+    }
+    if !install_permitted {
+        return Ok(None);
+    }
+    let _lock = cache::lock(cache, &dirname, Some(&format!("installing {}", name)))?;
+    // Another process may have installed it while we waited for the lock.
+    if destination.exists() {
+        return Ok(Some(Download::at(&destination)));
+    }
+
+    let data = get(url).with_context(|_| format!("failed to download from {}", url))?;
+    match expected {
+        Some(expected) => checksum::verify(&data, expected, url)?,
+        None if checksum::digests_required()? => bail!(
+            "No SHA-256 digest is known for {}, whose digest is {}, and digests are required \
+             by {} or `require-checksums` in wasm-pack's config. To install it, set {} or add \
+             its digest to `[checksums.{}]` in wasm-pack's config.",
+            url,
+            checksum::sha256_hex(&data),
+            checksum::REQUIRE_ENV_VAR,
+            checksum::env_var(name),
+            name
+        ),
+        None => PBAR.warn(&format!(
+            "No SHA-256 digest is known for {}, so it is installed unchecked. Its digest is \
+             {}. To check it, set {} or add it to `[checksums.{}]` in wasm-pack's config.",
+            url,
+            checksum::sha256_hex(&data),
+            checksum::env_var(name),
+            name
+        )),
+    }
+
+    // Extract everything in a temporary directory in case we're ctrl-c'd.
+    let temp = cache.join(format!(".{}", dirname).as_ref());
+    drop(fs::remove_dir_all(&temp));
+    fs::create_dir_all(&temp)?;
+    if url.ends_with(".tar.gz") {
+        extract_tarball(&data, &temp, binaries)
+            .with_context(|_| format!("failed to extract tarball from {}", url))?;
+    } else if url.ends_with(".zip") {
+        extract_zip(&data, &temp, binaries)
+            .with_context(|_| format!("failed to extract zip from {}", url))?;
+    } else {
+        bail!("don't know how to extract {}", url);
+    }
+    fs::rename(&temp, &destination)?;
+    Ok(Some(Download::at(&destination)))
+}
+
+/// The directory in the cache that the archive at `url` is extracted into.
+pub fn hashed_dirname(url: &str, name: &str) -> String {
+    let mut hasher = SipHasher13::new();
+    url.hash(&mut hasher);
+    let hash: String = hasher
+        .finish()
+        .to_le_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    format!("{}-{}", name, hash)
+}
+
+fn get(url: &str) -> Result<Vec<u8>, Error> {
+    let mut data = Vec::new();
+    let mut easy = curl::easy::Easy::new();
+    easy.follow_location(true)?;
+    easy.url(url)?;
+    easy.get(true)?;
+    {
+        let mut transfer = easy.transfer();
+        transfer.write_function(|part| {
+            data.extend_from_slice(part);
+            Ok(part.len())
+        })?;
+        transfer.perform()?;
+    }
+
+    // `file://` URLs have no status code.
+    let status_code = easy.response_code()?;
+    if status_code == 0 || (200..300).contains(&status_code) {
+        Ok(data)
+    } else {
+        bail!(
+            "received a bad HTTP status code ({}) when requesting {}",
+            status_code,
+            url
+        )
+    }
+}
+
+fn extract_tarball(tarball: &[u8], dst: &Path, binaries: &[&str]) -> Result<(), Error> {
+    let mut binaries: HashSet<_> = binaries.iter().map(OsStr::new).collect();
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(tarball));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let dest = match entry.path()?.file_stem() {
+            Some(f) if binaries.remove(f) => dst.join(entry.path()?.file_name().unwrap()),
+            _ => continue,
+        };
+        entry.unpack(dest)?;
+    }
+    check_missing(&binaries, "tarball")
+}
+
+fn extract_zip(data: &[u8], dst: &Path, binaries: &[&str]) -> Result<(), Error> {
+    let mut binaries: HashSet<_> = binaries.iter().map(OsStr::new).collect();
+    let mut archive = zip::ZipArchive::new(io::Cursor::new(data))?;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        let path = match entry.enclosed_name() {
+            Some(path) => path.to_owned(),
+            None => continue,
+        };
+        let dest: PathBuf = match path.file_stem() {
+            Some(f) if binaries.remove(f) => dst.join(path.file_name().unwrap()),
+            _ => continue,
+        };
+        let mut file = bin_open_options()
+            .write(true)
+            .create_new(true)
+            .open(&dest)?;
+        io::copy(&mut entry, &mut file)?;
+    }
+    check_missing(&binaries, "zip")
+}
+
+fn check_missing(binaries: &HashSet<&OsStr>, kind: &str) -> Result<(), Error> {
+    if !binaries.is_empty() {
+        bail!(
+            "the {} was missing expected executables: {}",
+            kind,
+            binaries
+                .iter()
+                .map(|s| s.to_string_lossy())
+                .collect::<Vec<_>>()
+                .join(", "),
+        )
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn bin_open_options() -> fs::OpenOptions {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let mut opts = fs::OpenOptions::new();
+    opts.mode(0o755);
+    opts
+}
+
+#[cfg(not(unix))]
+fn bin_open_options() -> fs::OpenOptions {
+    fs::OpenOptions::new()
+}
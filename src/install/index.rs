@@ -0,0 +1,126 @@
+//! A record of the tools in wasm-pack's cache: which tool and version each of
+//! its directories holds, where it came from, and when a build last used it.
+
+use binary_install::Cache;
+use cache;
+use chrono::{DateTime, FixedOffset};
+use failure::{Error, ResultExt};
+use lock;
+use log::warn;
+use serde_json;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// The file in the cache that the record is kept in.
+pub const INDEX_FILE: &str = "tools.json";
+
+/// How a tool got into the cache.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Source {
+    /// Its prebuilt binaries were downloaded.
+    Download,
+    /// It was compiled with `cargo install`.
+    CargoInstall,
+}
+
+impl Source {
+    /// The name of the source, as shown by `wasm-pack tools list`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Source::Download => "download",
+            Source::CargoInstall => "cargo install",
+        }
+    }
+}
+
+/// What is known about a directory of the cache.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Entry {
+    /// The tool in the directory.
+    pub tool: String,
+    /// Its version.
+    pub version: String,
+    /// How it got into the cache.
+    pub source: Source,
+    /// Where it was downloaded from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// When a build last used it, in RFC 3339 format.
+    pub last_used: String,
+}
+
+impl Entry {
+    /// When a build last used the tool, if that is known.
+    pub fn last_used(&self) -> Option<DateTime<FixedOffset>> {
+        DateTime::parse_from_rfc3339(&self.last_used).ok()
+    }
+}
+
+/// The path of the record in `cache`.
+pub fn index_path(cache: &Cache) -> PathBuf {
+    cache.join(INDEX_FILE.as_ref())
+}
+
+/// Read the record of `cache`, by the name of each directory.
+pub fn read(cache: &Cache) -> Result<BTreeMap<String, Entry>, Error> {
+    let path = index_path(cache);
+    if !path.is_file() {
+        return Ok(BTreeMap::new());
+    }
+    let contents =
+        fs::read_to_string(&path).with_context(|_| format!("failed to read {}", path.display()))?;
+    let index = serde_json::from_str(&contents)
+        .with_context(|_| format!("failed to parse {}", path.display()))?;
+    Ok(index)
+}
+
+/// Write the record of `cache`.
+pub fn write(cache: &Cache, index: &BTreeMap<String, Entry>) -> Result<(), Error> {
+    let path = index_path(cache);
+    let contents = serde_json::to_string_pretty(index)?;
+    lock::write_atomically(&path, contents.as_bytes())
+        .with_context(|_| format!("failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// Change the record of `cache` with `f`, while other wasm-pack processes
+/// wait to change it.
+pub fn update<F>(cache: &Cache, f: F) -> Result<(), Error>
+where
+    F: FnOnce(&mut BTreeMap<String, Entry>),
+{
+    let _lock = cache::lock(cache, INDEX_FILE, None)?;
+    let mut index = read(cache)?;
+    f(&mut index);
+    write(cache, &index)
+}
+
+/// Record that `version` of `tool`, which is in the directory `dirname` of
+/// `cache`, was just used.
+///
+/// The record only serves `wasm-pack tools`, so failing to update it is
+/// logged rather than failing the build.
+pub fn record_use(
+    cache: &Cache,
+    dirname: &str,
+    tool: &str,
+    version: &str,
+    source: Source,
+    url: Option<&str>,
+) {
+    let entry = Entry {
+        tool: tool.to_string(),
+        version: version.to_string(),
+        source,
+        url: url.map(ToOwned::to_owned),
+        last_used: chrono::offset::Local::now().to_rfc3339(),
+    };
+    let result = update(cache, |index| {
+        index.insert(dirname.to_string(), entry);
+    });
+    if let Err(e) = result {
+        warn!("failed to record the use of {} {}: {}", tool, version, e);
+    }
+}
@@ -1,4 +1,5 @@
 use install::Tool;
+use offline;
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
@@ -14,6 +15,7 @@ pub struct KrateResponse {
 
 impl Krate {
     pub fn new(name: &Tool) -> Result<Krate, failure::Error> {
+        offline::ensure_online(&format!("look up the latest version of {}", name))?;
         let krate_address = format!("https://crates.io/api/v1/crates/{}", name);
         let client = reqwest::Client::new();
         let mut res = client.get(&krate_address).send()?;
@@ -1,13 +1,18 @@
 //! Functionality related to installing prebuilt binaries and/or running cargo install.
 
+use self::index::Source;
 use self::krate::Krate;
 use binary_install::{Cache, Download};
+use cache;
+use checksum;
 use child;
 use emoji;
 use failure::{self, ResultExt};
 use install;
 use log::debug;
 use log::{info, warn};
+use mirrors;
+use offline;
 use std::env;
 use std::fs;
 use std::path::PathBuf;
@@ -16,10 +21,14 @@ use target;
 use which::which;
 use PBAR;
 
+pub mod download;
+pub mod index;
 mod krate;
 mod mode;
+mod plan;
 mod tool;
 pub use self::mode::InstallMode;
+pub use self::plan::{plan_prebuilt, plan_prebuilt_or_cargo_install, InstallPlan};
 pub use self::tool::Tool;
 
 /// Possible outcomes of attempting to find/install a tool
@@ -36,6 +45,11 @@ pub enum Status {
 pub fn get_tool_path(status: &Status, tool: Tool) -> Result<&Download, failure::Error> {
     match status {
         Status::Found(download) => Ok(download),
+        Status::CannotInstall if offline::is_offline() => bail!(
+            "Not able to find a local {} in the cache or on the $PATH, and wasm-pack is offline \
+             so it can't install one.",
+            tool
+        ),
         Status::CannotInstall => bail!("Not able to find or install a local {}.", tool),
         install::Status::PlatformNotSupported => {
             bail!("{} does not currently support your platform.", tool)
@@ -68,12 +82,35 @@ pub fn download_prebuilt_or_cargo_install(
         }
     }
 
+    // A broken mirror configuration is an error of its own, rather than a
+    // reason to fall back to `cargo install`.
+    match prebuilt_target(&tool) {
+        Ok(_) => {
+            prebuilt_url(&tool, version)?;
+        }
+        Err(e) => {
+            let installed = cache.join(cargo_install_dirname(&tool, version).as_ref());
+            let quiet = matches!(tool, Tool::WasmSnip) || installed.exists();
+            if !quiet && install_permitted {
+                PBAR.info(&format!(
+                    "{}, so it will be built with `cargo install`, which can take a few \
+                     minutes.",
+                    e
+                ));
+            }
+            return cargo_install(tool, cache, version, install_permitted);
+        }
+    }
+
     let msg = format!("{}Installing {}...", emoji::DOWN_ARROW, tool);
     PBAR.info(&msg);
 
     let dl = download_prebuilt(&tool, &cache, version, install_permitted);
     match dl {
         Ok(dl) => return Ok(dl),
+        // Building the tool instead would hide that something tampered with
+        // its download.
+        Err(e) if e.downcast_ref::<checksum::Mismatch>().is_some() => return Err(e),
         Err(e) => {
             warn!(
                 "could not download pre-built `{}`: {}. Falling back to `cargo install`.",
@@ -92,6 +129,10 @@ pub fn check_version(
     expected_version: &str,
 ) -> Result<bool, failure::Error> {
     let expected_version = if expected_version == "latest" {
+        // Whatever is installed is the latest version we can get offline.
+        if offline::is_offline() {
+            return Ok(true);
+        }
         let krate = Krate::new(tool)?;
         krate.max_version
     } else {
@@ -125,95 +166,157 @@ pub fn download_prebuilt(
     version: &str,
     install_permitted: bool,
 ) -> Result<Status, failure::Error> {
-    let url = match prebuilt_url(tool, version) {
-        Ok(url) => url,
-        Err(e) => bail!(
-            "no prebuilt {} binaries are available for this platform: {}",
-            tool,
-            e,
-        ),
+    prebuilt_target(tool)?;
+    let url = prebuilt_url(tool, version)?;
+    let digest = checksum::expected(
+        &tool.to_string(),
+        prebuilt_version(tool, version),
+        prebuilt_target(tool)?,
+    )?;
+    let download = |name: &str, binaries: &[&str]| {
+        let download = download::download(
+            cache,
+            install_permitted,
+            name,
+            binaries,
+            &url,
+            digest.as_deref(),
+        )?;
+        if download.is_some() {
+            index::record_use(
+                cache,
+                &download::hashed_dirname(&url, name),
+                name,
+                prebuilt_version(tool, version),
+                Source::Download,
+                Some(&url),
+            );
+        }
+        Ok::<_, failure::Error>(download)
     };
     match tool {
         Tool::WasmBindgen => {
             let binaries = &["wasm-bindgen", "wasm-bindgen-test-runner"];
-            match cache.download(install_permitted, "wasm-bindgen", binaries, &url)? {
+            match download("wasm-bindgen", binaries)? {
                 Some(download) => Ok(Status::Found(download)),
                 None => bail!("wasm-bindgen v{} is not installed!", version),
             }
         }
         Tool::CargoGenerate => {
             let binaries = &["cargo-generate"];
-            match cache.download(install_permitted, "cargo-generate", binaries, &url)? {
+            match download("cargo-generate", binaries)? {
                 Some(download) => Ok(Status::Found(download)),
                 None => bail!("cargo-generate v{} is not installed!", version),
             }
         }
         Tool::WasmOpt => {
             let binaries = &["wasm-opt"];
-            match cache.download(install_permitted, "wasm-opt", binaries, &url)? {
+            match download("wasm-opt", binaries)? {
                 Some(download) => Ok(Status::Found(download)),
                 // TODO(ag_dubs): why is this different? i forget...
                 None => Ok(Status::CannotInstall),
             }
         }
+        Tool::WasmSnip => bail!("wasm-snip has no prebuilt binaries"),
     }
 }
 
 /// Returns the URL of a precompiled version of wasm-bindgen, if we have one
-/// available for our host platform.
+/// available for our host platform. The URL can be pointed at a mirror, see
+/// the `mirrors` module.
 fn prebuilt_url(tool: &Tool, version: &str) -> Result<String, failure::Error> {
+    let target = prebuilt_target(tool)?;
+    mirrors::url(
+        &tool.to_string(),
+        prebuilt_version(tool, version),
+        target,
+        "tar.gz",
+    )
+}
+
+/// The version of the prebuilt binaries of `tool` that are downloaded for
+/// `version`.
+fn prebuilt_version<'a>(tool: &Tool, version: &'a str) -> &'a str {
+    match tool {
+        // Krate::new(&Tool::CargoGenerate)?.max_version,
+        Tool::CargoGenerate => "0.5.1", // latest released binary [#907](https://github.com/rustwasm/wasm-pack/issues/907)
+        _ => version,
+    }
+}
+
+/// The name of our host platform in the prebuilt binaries of `tool`.
+fn prebuilt_target(tool: &Tool) -> Result<&'static str, failure::Error> {
+    if let Tool::WasmSnip = tool {
+        bail!("wasm-snip is only distributed through crates.io")
+    }
+    // cargo-generate is pinned to 0.5.1, and `WASM_OPT_VERSION` of binaryen,
+    // neither of which has been released for aarch64.
     let target = if target::LINUX && target::x86_64 {
         match tool {
-            Tool::WasmOpt => "x86-linux",
-            _ => "x86_64-unknown-linux-musl",
+            Tool::WasmOpt => Some("x86-linux"),
+            _ => Some("x86_64-unknown-linux-musl"),
         }
     } else if target::LINUX && target::x86 {
         match tool {
-            Tool::WasmOpt => "x86-linux",
-            _ => bail!("Unrecognized target!"),
+            Tool::WasmOpt => Some("x86-linux"),
+            _ => None,
+        }
+    } else if target::LINUX && target::aarch64 {
+        match tool {
+            Tool::WasmBindgen => Some("aarch64-unknown-linux-gnu"),
+            _ => None,
         }
     } else if target::MACOS && target::x86_64 {
-        "x86_64-apple-darwin"
+        match tool {
+            Tool::WasmOpt => Some("x86_64-macos"),
+            _ => Some("x86_64-apple-darwin"),
+        }
+    } else if target::MACOS && target::aarch64 {
+        match tool {
+            Tool::WasmBindgen => Some("aarch64-apple-darwin"),
+            _ => None,
+        }
     } else if target::WINDOWS && target::x86_64 {
         match tool {
-            Tool::WasmOpt => "x86-windows",
-            _ => "x86_64-pc-windows-msvc",
+            Tool::WasmOpt => Some("x86-windows"),
+            _ => Some("x86_64-pc-windows-msvc"),
         }
     } else if target::WINDOWS && target::x86 {
         match tool {
-            Tool::WasmOpt => "x86-windows",
-            _ => bail!("Unrecognized target!"),
+            Tool::WasmOpt => Some("x86-windows"),
+            _ => None,
         }
     } else {
-        bail!("Unrecognized target!")
+        None
     };
-
-    match tool {
-        Tool::WasmBindgen => {
-            Ok(format!(
-                "https://github.com/rustwasm/wasm-bindgen/releases/download/{0}/wasm-bindgen-{0}-{1}.tar.gz",
-                version,
-                target
-            ))
-        },
-        Tool::CargoGenerate => {
-            Ok(format!(
-                "https://github.com/cargo-generate/cargo-generate/releases/download/v{0}/cargo-generate-v{0}-{1}.tar.gz",
-                // Krate::new(&Tool::CargoGenerate)?.max_version,
-                "0.5.1", // latest released binary [#907](https://github.com/rustwasm/wasm-pack/issues/907)
-                target
-            ))
-        },
-        Tool::WasmOpt => {
-            Ok(format!(
-        "https://github.com/WebAssembly/binaryen/releases/download/{vers}/binaryen-{vers}-{target}.tar.gz",
-        vers = "version_90",
-        target = target,
-            ))
-        }
+    match target {
+        Some(target) => Ok(target),
+        // wasm-opt can't be built with `cargo install`, so point at the only
+        // other way to get it.
+        None if matches!(tool, Tool::WasmOpt) => bail!(
+            "there are no prebuilt wasm-opt binaries for {}. Install binaryen and put \
+             wasm-opt on the $PATH, where wasm-pack will find it.",
+            target::platform()
+        ),
+        None => bail!(
+            "there are no prebuilt {} binaries for {}",
+            tool,
+            target::platform()
+        ),
     }
 }
 
+/// Whether there are prebuilt binaries of `tool` for our host platform.
+pub fn has_prebuilt(tool: &Tool) -> bool {
+    prebuilt_target(tool).is_ok()
+}
+
+/// The directory in the cache that `version` of `tool` is `cargo install`ed
+/// into.
+fn cargo_install_dirname(tool: &Tool, version: &str) -> String {
+    format!("{}-cargo-install-{}", tool, version)
+}
+
 /// Use `cargo install` to install the tool locally into the given
 /// crate.
 pub fn cargo_install(
@@ -227,7 +330,7 @@ pub fn cargo_install(
         tool, version,
     );
 
-    let dirname = format!("{}-cargo-install-{}", tool, version);
+    let dirname = cargo_install_dirname(&tool, version);
     let destination = cache.join(dirname.as_ref());
     if destination.exists() {
         debug!(
@@ -237,12 +340,20 @@ pub fn cargo_install(
             destination.display()
         );
         let download = Download::at(&destination);
+        record_cargo_install(&tool, cache, version, &dirname);
         return Ok(Status::Found(download));
     }
 
     if !install_permitted {
         return Ok(Status::CannotInstall);
     }
+    let _lock = cache::lock(cache, &dirname, Some(&format!("installing {}", tool)))?;
+    // Another process may have installed it while we waited for the lock.
+    if destination.exists() {
+        let download = Download::at(&destination);
+        record_cargo_install(&tool, cache, version, &dirname);
+        return Ok(Status::Found(download));
+    }
 
     // Run `cargo install` to a temporary location to handle ctrl-c gracefully
     // and ensure we don't accidentally use stale files in the future
@@ -280,6 +391,7 @@ pub fn cargo_install(
         Tool::WasmBindgen => Ok(vec!["wasm-bindgen", "wasm-bindgen-test-runner"]),
         Tool::CargoGenerate => Ok(vec!["cargo-generate"]),
         Tool::WasmOpt => bail!("Cannot install wasm-opt with cargo."),
+        Tool::WasmSnip => Ok(vec!["wasm-snip"]),
     };
 
     for b in binaries?.iter().cloned() {
@@ -302,5 +414,11 @@ pub fn cargo_install(
     fs::rename(&tmp, &destination)?;
 
     let download = Download::at(&destination);
+    record_cargo_install(&tool, cache, version, &dirname);
     Ok(Status::Found(download))
 }
+
+fn record_cargo_install(tool: &Tool, cache: &Cache, version: &str, dirname: &str) {
+    let tool = tool.to_string();
+    index::record_use(cache, dirname, &tool, version, Source::CargoInstall, None);
+}
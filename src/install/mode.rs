@@ -1,3 +1,4 @@
+use offline;
 use std::str::FromStr;
 
 /// The `InstallMode` determines which mode of initialization we are running, and
@@ -32,11 +33,12 @@ impl FromStr for InstallMode {
 }
 
 impl InstallMode {
-    /// Determines if installation is permitted during a function call based on --mode flag
+    /// Determines if installation is permitted during a function call based on --mode flag.
+    /// Nothing is installed when wasm-pack is offline.
     pub fn install_permitted(self) -> bool {
         match self {
-            InstallMode::Normal => true,
-            InstallMode::Force => true,
+            InstallMode::Normal => !offline::is_offline(),
+            InstallMode::Force => !offline::is_offline(),
             InstallMode::Noinstall => false,
         }
     }
@@ -0,0 +1,168 @@
+//! Working out how a tool would be installed, without installing it.
+
+use super::{cargo_install_dirname, check_version, prebuilt_target, prebuilt_url, Tool};
+use binary_install::{Cache, Download};
+use offline;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use which::which;
+
+/// How a tool would be obtained by a build, as reported by
+/// `wasm-pack build --dry-run`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "source", rename_all = "kebab-case")]
+pub enum InstallPlan {
+    /// A matching version is already installed globally.
+    Global {
+        /// The path of the installed binary.
+        path: PathBuf,
+    },
+    /// The tool is already in wasm-pack's cache.
+    Cached {
+        /// The path of the cached binary.
+        path: PathBuf,
+    },
+    /// A prebuilt binary would be downloaded.
+    Download {
+        /// Where the binary would be downloaded from.
+        url: String,
+    },
+    /// The tool would be compiled with `cargo install`.
+    CargoInstall {
+        /// The crate that would be installed.
+        krate: String,
+    },
+    /// The tool can't be obtained.
+    Unavailable {
+        /// Why the tool can't be obtained.
+        reason: String,
+    },
+    /// How the tool would be obtained is only known once the build is under
+    /// way.
+    Undetermined {
+        /// Why it isn't known yet.
+        reason: String,
+    },
+}
+
+impl fmt::Display for InstallPlan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InstallPlan::Global { path } => write!(f, "installed globally at {}", path.display()),
+            InstallPlan::Cached { path } => write!(f, "cached at {}", path.display()),
+            InstallPlan::Download { url } => write!(f, "download from {}", url),
+            InstallPlan::CargoInstall { krate } => write!(f, "cargo install {}", krate),
+            InstallPlan::Unavailable { reason } => write!(f, "unavailable: {}", reason),
+            InstallPlan::Undetermined { reason } => write!(f, "undetermined: {}", reason),
+        }
+    }
+}
+
+impl InstallPlan {
+    /// The path of the tool's binary, if it is available without installing
+    /// anything.
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            InstallPlan::Global { path } | InstallPlan::Cached { path } => Some(path),
+            _ => None,
+        }
+    }
+}
+
+/// Like `download_prebuilt_or_cargo_install`, but only reports what it would
+/// do.
+pub fn plan_prebuilt_or_cargo_install(
+    tool: Tool,
+    cache: &Cache,
+    version: &str,
+    install_permitted: bool,
+) -> Result<InstallPlan, failure::Error> {
+    if let Ok(path) = which(tool.to_string()) {
+        if check_version(&tool, &path, version)? {
+            return Ok(InstallPlan::Global { path });
+        }
+    }
+
+    if prebuilt_target(&tool).is_ok() {
+        prebuilt_url(&tool, version)?;
+    }
+
+    match plan_prebuilt(&tool, cache, version, install_permitted) {
+        InstallPlan::Unavailable { .. } => {}
+        plan => return Ok(plan),
+    }
+
+    let dirname = cargo_install_dirname(&tool, version);
+    let destination = cache.join(dirname.as_ref());
+    if let Ok(path) = Download::at(&destination).binary(&tool.to_string()) {
+        return Ok(InstallPlan::Cached { path });
+    }
+    if !install_permitted {
+        return Ok(InstallPlan::Unavailable {
+            reason: not_installed(&tool, version),
+        });
+    }
+    let krate = match tool {
+        Tool::WasmBindgen => "wasm-bindgen-cli".to_string(),
+        _ => tool.to_string(),
+    };
+    Ok(InstallPlan::CargoInstall {
+        krate: format!("{}@{}", krate, version),
+    })
+}
+
+/// Like `download_prebuilt`, but only reports what it would do.
+pub fn plan_prebuilt(
+    tool: &Tool,
+    cache: &Cache,
+    version: &str,
+    install_permitted: bool,
+) -> InstallPlan {
+    if let Err(e) = prebuilt_target(tool) {
+        return InstallPlan::Unavailable {
+            reason: e.to_string(),
+        };
+    }
+    let url = match prebuilt_url(tool, version) {
+        Ok(url) => url,
+        Err(e) => {
+            return InstallPlan::Unavailable {
+                reason: e
+                    .iter_chain()
+                    .map(|cause| cause.to_string())
+                    .collect::<Vec<_>>()
+                    .join(": "),
+            }
+        }
+    };
+    let binaries: &[&str] = match tool {
+        Tool::WasmBindgen => &["wasm-bindgen", "wasm-bindgen-test-runner"],
+        Tool::CargoGenerate => &["cargo-generate"],
+        Tool::WasmOpt => &["wasm-opt"],
+        Tool::WasmSnip => &["wasm-snip"],
+    };
+    // Downloads are forbidden here, so this only looks into the cache.
+    if let Ok(Some(download)) = cache.download(false, &tool.to_string(), binaries, &url) {
+        if let Ok(path) = download.binary(&tool.to_string()) {
+            return InstallPlan::Cached { path };
+        }
+    }
+    if install_permitted {
+        InstallPlan::Download { url }
+    } else {
+        InstallPlan::Unavailable {
+            reason: not_installed(tool, version),
+        }
+    }
+}
+
+fn not_installed(tool: &Tool, version: &str) -> String {
+    if offline::is_offline() {
+        format!(
+            "{} v{} is not installed, and wasm-pack is offline",
+            tool, version
+        )
+    } else {
+        format!("{} v{} is not installed", tool, version)
+    }
+}
@@ -1,6 +1,8 @@
 use std::fmt;
+use std::str::FromStr;
 
 /// Represents the set of CLI tools wasm-pack uses
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Tool {
     /// cargo-generate CLI tool
     CargoGenerate,
@@ -8,6 +10,8 @@ pub enum Tool {
     WasmBindgen,
     /// wasm-opt CLI tool
     WasmOpt,
+    /// wasm-snip CLI tool
+    WasmSnip,
 }
 
 impl fmt::Display for Tool {
@@ -16,7 +20,25 @@ impl fmt::Display for Tool {
             Tool::CargoGenerate => "cargo-generate",
             Tool::WasmBindgen => "wasm-bindgen",
             Tool::WasmOpt => "wasm-opt",
+            Tool::WasmSnip => "wasm-snip",
         };
         write!(f, "{}", s)
     }
 }
+
+impl FromStr for Tool {
+    type Err = failure::Error;
+    fn from_str(s: &str) -> Result<Self, failure::Error> {
+        match s {
+            "cargo-generate" => Ok(Tool::CargoGenerate),
+            "wasm-bindgen" => Ok(Tool::WasmBindgen),
+            "wasm-opt" => Ok(Tool::WasmOpt),
+            "wasm-snip" => Ok(Tool::WasmSnip),
+            _ => bail!(
+                "Unknown tool: {}. The tools are cargo-generate, wasm-bindgen, wasm-opt and \
+                 wasm-snip.",
+                s
+            ),
+        }
+    }
+}
@@ -2,12 +2,15 @@
 
 #![deny(missing_docs)]
 
+extern crate brotli;
 extern crate cargo_metadata;
 extern crate console;
 extern crate strsim;
 #[macro_use]
 extern crate failure;
 extern crate glob;
+#[macro_use]
+extern crate lazy_static;
 extern crate parking_lot;
 extern crate semver;
 extern crate serde;
@@ -16,36 +19,59 @@ extern crate which;
 extern crate serde_derive;
 extern crate serde_ignored;
 extern crate serde_json;
+extern crate sha2;
+extern crate siphasher;
 #[macro_use]
 extern crate structopt;
 extern crate binary_install;
 extern crate chrono;
 extern crate curl;
 extern crate dialoguer;
+extern crate flate2;
+#[cfg(unix)]
+extern crate libc;
 extern crate log;
+extern crate tar;
 extern crate toml;
 extern crate walkdir;
+#[cfg(windows)]
+extern crate winapi;
+extern crate zip;
 
 pub mod bindgen;
 pub mod build;
 pub mod cache;
+pub mod checksum;
 pub mod child;
 pub mod command;
+pub mod config;
+pub mod debug_info;
+pub mod deno;
 pub mod emoji;
+pub mod fingerprint;
 pub mod generate;
+pub mod hooks;
 pub mod install;
 pub mod license;
+pub mod lock;
 pub mod lockfile;
 pub mod manifest;
+pub mod mirrors;
 pub mod npm;
+pub mod offline;
+pub mod plan;
 pub mod progressbar;
 pub mod readme;
+pub mod size;
 pub mod stamps;
 pub mod target;
 pub mod test;
+pub mod wasi;
 pub mod wasm_opt;
+pub mod wasm_snip;
+pub mod watch;
 
-use progressbar::{LogLevel, ProgressOutput};
+use progressbar::{LogLevel, MessageFormat, ProgressOutput};
 
 /// The global progress bar and user-facing message output.
 pub static PBAR: ProgressOutput = ProgressOutput::new();
@@ -68,4 +94,12 @@ pub struct Cli {
     #[structopt(long = "log-level", default_value = "info")]
     /// The maximum level of messages that should be logged by wasm-pack. [possible values: info, warn, error]
     pub log_level: LogLevel,
+
+    #[structopt(long = "message-format", default_value = "human")]
+    /// How progress and results are reported. `json` prints one JSON event per line to stdout. [possible values: human, json]
+    pub message_format: MessageFormat,
+
+    #[structopt(long = "offline")]
+    /// Don't access the network. Tools are only taken from the cache or the `$PATH`. Can also be turned on by setting `WASM_PACK_OFFLINE`.
+    pub offline: bool,
 }
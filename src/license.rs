@@ -38,6 +38,16 @@ fn glob_license_files(path: &Path) -> Result<Vec<String>, failure::Error> {
     Ok(license_files)
 }
 
+/// List the license files, relative to the crate directory at `path`, that
+/// `copy_from_crate` copies into the `pkg` directory.
+pub fn crate_license_files(crate_data: &CrateData, path: &Path) -> Vec<String> {
+    match (crate_data.crate_license(), crate_data.crate_license_file()) {
+        (Some(_), _) => glob_license_files(path).unwrap_or_default(),
+        (None, Some(license_file)) => vec![license_file.clone()],
+        (None, None) => vec![],
+    }
+}
+
 /// Copy the crate's license into the `pkg` directory.
 pub fn copy_from_crate(
     crate_data: &CrateData,
@@ -0,0 +1,135 @@
+//! Locking files, so that wasm-pack processes which share a cache or a stamps
+//! file wait for each other instead of corrupting it, and replacing files
+//! atomically.
+
+use failure::{Error, ResultExt};
+use log::debug;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use std::process;
+use PBAR;
+
+/// An exclusive lock on a file. It is released when the `FileLock` is
+/// dropped, or when the process exits.
+#[derive(Debug)]
+pub struct FileLock {
+    _file: File,
+}
+
+impl FileLock {
+    /// Lock the file at `path`, creating it if needed, and block until
+    /// another process that holds the lock releases it. While blocked, tell
+    /// the user that we're waiting for the other process to finish `what`,
+    /// unless it is `None` because the lock is only ever held briefly.
+    pub fn acquire(path: &Path, what: Option<&str>) -> Result<FileLock, Error> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .with_context(|_| format!("failed to open the lock file {}", path.display()))?;
+        let locked =
+            sys::try_lock(&file).with_context(|_| format!("failed to lock {}", path.display()))?;
+        if !locked {
+            if let Some(what) = what {
+                PBAR.info(&format!(
+                    "Waiting for another wasm-pack process to finish {}...",
+                    what
+                ));
+            }
+            sys::lock(&file).with_context(|_| format!("failed to lock {}", path.display()))?;
+        }
+        debug!("locked {}", path.display());
+        Ok(FileLock { _file: file })
+    }
+}
+
+/// Replace the file at `path` with `contents`, such that a process reading
+/// it sees either the old or the new contents, never a partial write.
+pub fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let file_name = match path.file_name() {
+        Some(file_name) => file_name.to_string_lossy(),
+        None => return Err(io::Error::other("no file name")),
+    };
+    let temp = path.with_file_name(format!(".{}.{}.tmp", file_name, process::id()));
+    let result = File::create(&temp)
+        .and_then(|mut file| file.write_all(contents).and_then(|()| file.sync_all()))
+        .and_then(|()| fs::rename(&temp, path));
+    if result.is_err() {
+        drop(fs::remove_file(&temp));
+    }
+    result
+}
+
+#[cfg(unix)]
+mod sys {
+    use libc;
+    use std::fs::File;
+    use std::io;
+    use std::os::unix::io::AsRawFd;
+
+    pub fn try_lock(file: &File) -> io::Result<bool> {
+        match flock(file, libc::LOCK_EX | libc::LOCK_NB) {
+            Ok(()) => Ok(true),
+            Err(ref e) if e.raw_os_error() == Some(libc::EWOULDBLOCK) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn lock(file: &File) -> io::Result<()> {
+        flock(file, libc::LOCK_EX)
+    }
+
+    fn flock(file: &File, operation: libc::c_int) -> io::Result<()> {
+        loop {
+            if unsafe { libc::flock(file.as_raw_fd(), operation) } == 0 {
+                return Ok(());
+            }
+            let err = io::Error::last_os_error();
+            match err.raw_os_error() {
+                Some(libc::EINTR) => continue,
+                // Like Cargo, carry on without the lock on file systems that
+                // don't support locking, such as some network file systems.
+                Some(libc::ENOTSUP) | Some(libc::ENOLCK) => return Ok(()),
+                _ => return Err(err),
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::fs::File;
+    use std::io;
+    use std::mem;
+    use std::os::windows::io::AsRawHandle;
+    use winapi::shared::minwindef::DWORD;
+    use winapi::shared::winerror::ERROR_LOCK_VIOLATION;
+    use winapi::um::fileapi::LockFileEx;
+    use winapi::um::minwinbase::{LOCKFILE_EXCLUSIVE_LOCK, LOCKFILE_FAIL_IMMEDIATELY};
+
+    pub fn try_lock(file: &File) -> io::Result<bool> {
+        match lock_file(file, LOCKFILE_EXCLUSIVE_LOCK | LOCKFILE_FAIL_IMMEDIATELY) {
+            Ok(()) => Ok(true),
+            Err(ref e) if e.raw_os_error() == Some(ERROR_LOCK_VIOLATION as i32) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    pub fn lock(file: &File) -> io::Result<()> {
+        lock_file(file, LOCKFILE_EXCLUSIVE_LOCK)
+    }
+
+    fn lock_file(file: &File, flags: DWORD) -> io::Result<()> {
+        unsafe {
+            let mut overlapped = mem::zeroed();
+            if LockFileEx(file.as_raw_handle() as _, flags, 0, !0, !0, &mut overlapped) == 0 {
+                Err(io::Error::last_os_error())
+            } else {
+                Ok(())
+            }
+        }
+    }
+}
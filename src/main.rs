@@ -18,7 +18,7 @@ use structopt::StructOpt;
 use wasm_pack::{
     build::{self, WasmPackVersion},
     command::run_wasm_pack,
-    Cli, PBAR,
+    offline, Cli, PBAR,
 };
 
 mod installer;
@@ -59,8 +59,6 @@ fn main() {
 }
 
 fn run() -> Result<(), failure::Error> {
-    let wasm_pack_version = background_check_for_updates();
-
     // Deprecate `init`
     if let Some("init") = env::args().nth(1).as_ref().map(|arg| arg.as_str()) {
         println!("wasm-pack init is deprecated, consider using wasm-pack build");
@@ -81,7 +79,17 @@ fn run() -> Result<(), failure::Error> {
 
     let args = Cli::from_args();
 
+    if args.offline || offline::is_offline() {
+        offline::set_offline();
+    }
+    let wasm_pack_version = if offline::is_offline() {
+        mpsc::channel().1
+    } else {
+        background_check_for_updates()
+    };
+
     PBAR.set_log_level(args.log_level);
+    PBAR.set_message_format(args.message_format);
 
     if args.quiet {
         PBAR.set_quiet(true);
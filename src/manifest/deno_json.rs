@@ -0,0 +1,8 @@
+#[derive(Serialize)]
+pub struct DenoConfig {
+    pub name: String,
+    pub version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    pub exports: String,
+}
@@ -6,27 +6,35 @@
     clippy::redundant_closure
 )]
 
+mod deno_json;
 mod npm;
 
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+use self::deno_json::DenoConfig;
 use self::npm::{
-    repository::Repository, CommonJSPackage, ESModulesPackage, NoModulesPackage, NpmPackage,
+    repository::Repository, CommonJSPackage, ConditionalExports, ESModulesPackage,
+    MultiTargetPackage, NoModulesPackage, NpmPackage, WasiPackage,
 };
 use cargo_metadata::Metadata;
 use chrono::offset;
 use chrono::DateTime;
 use command::build::{BuildProfile, Target};
 use curl::easy;
+use deno;
 use failure::{Error, ResultExt};
+use hooks::Hooks;
+use offline;
 use serde::{self, Deserialize};
 use serde_json;
-use std::collections::BTreeSet;
-use std::env;
-use std::io::Write;
+use size::SizeBudget;
+use stamps;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
 use strsim::levenshtein;
 use toml;
+use wasi;
 use PBAR;
 
 const WASM_PACK_METADATA_KEY: &str = "package.metadata.wasm-pack";
@@ -34,6 +42,7 @@ const WASM_PACK_VERSION: Option<&'static str> = option_env!("CARGO_PKG_VERSION")
 const WASM_PACK_REPO_URL: &str = "https://github.com/rustwasm/wasm-pack";
 
 /// Store for metadata learned about a crate
+#[derive(Clone)]
 pub struct CrateData {
     data: Metadata,
     current_idx: usize,
@@ -42,12 +51,12 @@ pub struct CrateData {
 }
 
 #[doc(hidden)]
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize)]
 pub struct CargoManifest {
     package: CargoPackage,
 }
 
-#[derive(Deserialize)]
+#[derive(Clone, Deserialize)]
 struct CargoPackage {
     name: String,
     description: Option<String>,
@@ -61,37 +70,82 @@ struct CargoPackage {
     metadata: CargoMetadata,
 }
 
-#[derive(Default, Deserialize)]
+#[derive(Clone, Default, Deserialize)]
 struct CargoMetadata {
     #[serde(default, rename = "wasm-pack")]
     wasm_pack: CargoWasmPack,
 }
 
-#[derive(Default, Deserialize)]
+#[derive(Clone, Default, Deserialize)]
 struct CargoWasmPack {
     #[serde(default)]
     profile: CargoWasmPackProfiles,
+    #[serde(default)]
+    hooks: Hooks,
+    #[serde(default, deserialize_with = "deserialize_targets")]
+    target: Vec<Target>,
+    #[serde(default)]
+    scope: Option<String>,
+    #[serde(default, rename = "out-dir")]
+    out_dir: Option<String>,
+    #[serde(default, rename = "out-name")]
+    out_name: Option<String>,
+    #[serde(default, rename = "no-typescript")]
+    no_typescript: bool,
+    #[serde(default, rename = "extra-options")]
+    extra_options: Vec<String>,
 }
 
-#[derive(Deserialize)]
+/// Defaults for the options of `wasm-pack build`, which a crate sets in
+/// `[package.metadata.wasm-pack]`. Options given on the command line take
+/// precedence over them.
+#[derive(Clone, Debug, Default)]
+pub struct BuildDefaults {
+    /// The default for `--target`, or empty if there is none.
+    pub target: Vec<Target>,
+    /// The default for `--scope`.
+    pub scope: Option<String>,
+    /// The default for `--out-dir`.
+    pub out_dir: Option<String>,
+    /// The default for `--out-name`.
+    pub out_name: Option<String>,
+    /// Whether to pass `--no-typescript` by default.
+    pub no_typescript: bool,
+    /// The default extra options to pass to `cargo build`.
+    pub extra_options: Vec<String>,
+}
+
+/// Accept a target, a comma separated list of targets like `--target` does,
+/// or an array of targets.
+fn deserialize_targets<'de, D>(deserializer: D) -> Result<Vec<Target>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    let targets = match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::One(targets) => targets.split(',').map(str::to_string).collect(),
+        OneOrMany::Many(targets) => targets,
+    };
+    targets
+        .iter()
+        .map(|target| target.trim().parse().map_err(serde::de::Error::custom))
+        .collect()
+}
+
+#[derive(Clone)]
 struct CargoWasmPackProfiles {
-    #[serde(
-        default = "CargoWasmPackProfile::default_dev",
-        deserialize_with = "CargoWasmPackProfile::deserialize_dev"
-    )]
     dev: CargoWasmPackProfile,
-
-    #[serde(
-        default = "CargoWasmPackProfile::default_release",
-        deserialize_with = "CargoWasmPackProfile::deserialize_release"
-    )]
     release: CargoWasmPackProfile,
-
-    #[serde(
-        default = "CargoWasmPackProfile::default_profiling",
-        deserialize_with = "CargoWasmPackProfile::deserialize_profiling"
-    )]
     profiling: CargoWasmPackProfile,
+    /// The tables of custom cargo profiles. Until `resolve_custom_profiles`
+    /// runs, these hold only what is written in `Cargo.toml`.
+    custom: BTreeMap<String, CargoWasmPackProfile>,
 }
 
 impl Default for CargoWasmPackProfiles {
@@ -100,21 +154,117 @@ impl Default for CargoWasmPackProfiles {
             dev: CargoWasmPackProfile::default_dev(),
             release: CargoWasmPackProfile::default_release(),
             profiling: CargoWasmPackProfile::default_profiling(),
+            custom: BTreeMap::new(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for CargoWasmPackProfiles {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct ProfilesVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for ProfilesVisitor {
+            type Value = CargoWasmPackProfiles;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a table of profiles")
+            }
+
+            fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::MapAccess<'de>,
+            {
+                let mut profiles = CargoWasmPackProfiles::default();
+                while let Some(name) = map.next_key::<String>()? {
+                    let mut profile: CargoWasmPackProfile = map.next_value()?;
+                    match name.as_str() {
+                        "dev" => {
+                            profile.update_with_defaults(&CargoWasmPackProfile::default_dev());
+                            profiles.dev = profile;
+                        }
+                        "release" => {
+                            profile.update_with_defaults(&CargoWasmPackProfile::default_release());
+                            profiles.release = profile;
+                        }
+                        "profiling" => {
+                            profile
+                                .update_with_defaults(&CargoWasmPackProfile::default_profiling());
+                            profiles.profiling = profile;
+                        }
+                        _ => {
+                            profiles.custom.insert(name, profile);
+                        }
+                    }
+                }
+                Ok(profiles)
+            }
         }
+
+        deserializer.deserialize_map(ProfilesVisitor)
     }
 }
 
+/// A `[profile.<name>]` table of a cargo manifest. Only the keys that
+/// wasm-pack cares about are read.
+#[derive(Deserialize)]
+struct CargoProfile {
+    inherits: Option<String>,
+}
+
 /// This is where configuration goes for wasm-bindgen, wasm-opt, wasm-snip, or
 /// anything else that wasm-pack runs.
-#[derive(Default, Deserialize)]
+#[derive(Clone, Default, Deserialize)]
 pub struct CargoWasmPackProfile {
     #[serde(default, rename = "wasm-bindgen")]
     wasm_bindgen: CargoWasmPackProfileWasmBindgen,
     #[serde(default, rename = "wasm-opt")]
     wasm_opt: Option<CargoWasmPackProfileWasmOpt>,
+    #[serde(default, rename = "wasm-snip")]
+    wasm_snip: Option<CargoWasmPackProfileWasmSnip>,
+    #[serde(default, rename = "size-budget")]
+    size_budget: Option<SizeBudget>,
+    #[serde(default, rename = "split-debug-info")]
+    split_debug_info: Option<bool>,
+    #[serde(default)]
+    target: CargoWasmPackProfileTargets,
 }
 
-#[derive(Default, Deserialize)]
+/// The `[profile.<name>.target.<target>]` tables, which override the settings
+/// of a profile for a single `--target`.
+#[derive(Clone, Default, Deserialize)]
+struct CargoWasmPackProfileTargets {
+    #[serde(default)]
+    bundler: Option<Box<CargoWasmPackProfile>>,
+    #[serde(default)]
+    web: Option<Box<CargoWasmPackProfile>>,
+    #[serde(default)]
+    nodejs: Option<Box<CargoWasmPackProfile>>,
+    #[serde(default, rename = "no-modules")]
+    no_modules: Option<Box<CargoWasmPackProfile>>,
+    #[serde(default)]
+    deno: Option<Box<CargoWasmPackProfile>>,
+    #[serde(default)]
+    wasi: Option<Box<CargoWasmPackProfile>>,
+}
+
+impl CargoWasmPackProfileTargets {
+    fn get(&self, target: Target) -> Option<&CargoWasmPackProfile> {
+        let profile = match target {
+            Target::Bundler => &self.bundler,
+            Target::Web => &self.web,
+            Target::Nodejs => &self.nodejs,
+            Target::NoModules => &self.no_modules,
+            Target::Deno => &self.deno,
+            Target::Wasi => &self.wasi,
+        };
+        profile.as_ref().map(|p| &**p)
+    }
+}
+
+#[derive(Clone, Default, Deserialize)]
 struct CargoWasmPackProfileWasmBindgen {
     #[serde(default, rename = "debug-js-glue")]
     debug_js_glue: Option<bool>,
@@ -126,6 +276,21 @@ struct CargoWasmPackProfileWasmBindgen {
     dwarf_debug_info: Option<bool>,
 }
 
+#[derive(Clone, Default, Deserialize)]
+struct CargoWasmPackProfileWasmSnip {
+    #[serde(default)]
+    functions: Vec<String>,
+
+    #[serde(default)]
+    patterns: Vec<String>,
+
+    #[serde(default, rename = "snip-rust-fmt-code")]
+    snip_rust_fmt_code: bool,
+
+    #[serde(default, rename = "snip-rust-panicking-code")]
+    snip_rust_panicking_code: bool,
+}
+
 struct Collector(Vec<u8>);
 
 impl easy::Handler for Collector {
@@ -150,25 +315,25 @@ struct CrateInformation {
 impl Crate {
     /// Returns latest wasm-pack version
     pub fn return_wasm_pack_latest_version() -> Result<Option<String>, failure::Error> {
+        if offline::is_offline() {
+            return Ok(None);
+        }
         let current_time = chrono::offset::Local::now();
-        let old_metadata_file = Self::return_wasm_pack_file();
-
-        match old_metadata_file {
-            Some(ref file_contents) => {
-                let last_updated = Self::return_stamp_file_value(&file_contents, "created")
-                    .and_then(|t| DateTime::parse_from_str(t.as_str(), "%+").ok());
-
-                last_updated
-                    .map(|last_updated| {
-                        if current_time.signed_duration_since(last_updated).num_hours() > 24 {
-                            Self::return_api_call_result(current_time).map(Some)
-                        } else {
-                            Ok(Self::return_stamp_file_value(&file_contents, "version"))
-                        }
-                    })
-                    .unwrap_or_else(|| Ok(None))
+        let json = stamps::read_stamps_file_to_json().ok();
+        let stamp = |key| {
+            json.as_ref()
+                .and_then(|json| stamps::get_stamp_value(key, json).ok())
+        };
+        let last_checked = stamp(stamps::WASM_PACK_VERSION_CHECKED_STAMP)
+            .and_then(|t| DateTime::parse_from_str(t.as_str(), "%+").ok());
+
+        match last_checked {
+            Some(last_checked)
+                if current_time.signed_duration_since(last_checked).num_hours() <= 24 =>
+            {
+                Ok(stamp(stamps::WASM_PACK_VERSION_STAMP))
             }
-            None => Self::return_api_call_result(current_time).map(Some),
+            _ => Self::return_api_call_result(current_time).map(Some),
         }
     }
 
@@ -177,50 +342,22 @@ impl Crate {
     ) -> Result<String, failure::Error> {
         let version = Self::return_latest_wasm_pack_version();
 
-        // We always override the stamp file with the current time because we don't
-        // want to hit the API all the time if it fails. It should follow the same
+        // We always save the time of the check because we don't want to hit
+        // the API all the time if it fails. It should follow the same
         // "policy" as the success. This means that the 24 hours rate limiting
         // will be active regardless if the check succeeded or failed.
-        match version {
-            Ok(ref version) => Self::override_stamp_file(current_time, Some(&version)).ok(),
-            Err(_) => Self::override_stamp_file(current_time, None).ok(),
-        };
-
-        version
-    }
-
-    fn override_stamp_file(
-        current_time: DateTime<offset::Local>,
-        version: Option<&str>,
-    ) -> Result<(), failure::Error> {
-        let path = env::current_exe()?;
-
-        let mut file = fs::OpenOptions::new()
-            .read(true)
-            .write(true)
-            .append(true)
-            .create(true)
-            .open(path.with_extension("stamp"))?;
-
-        file.set_len(0)?;
-
-        write!(file, "created {:?}", current_time)?;
-
-        if let Some(version) = version {
-            write!(file, "\nversion {}", version)?;
+        if let Ok(ref version) = version {
+            drop(stamps::save_stamp_value(
+                stamps::WASM_PACK_VERSION_STAMP,
+                version,
+            ));
         }
+        drop(stamps::save_stamp_value(
+            stamps::WASM_PACK_VERSION_CHECKED_STAMP,
+            current_time.to_rfc3339(),
+        ));
 
-        Ok(())
-    }
-
-    /// Return stamp file where metadata is stored.
-    fn return_wasm_pack_file() -> Option<String> {
-        if let Ok(path) = env::current_exe() {
-            if let Ok(file) = fs::read_to_string(path.with_extension("stamp")) {
-                return Some(file);
-            }
-        }
-        None
+        version
     }
 
     /// Returns wasm-pack latest version (if it's received) by executing check_wasm_pack_latest_version function.
@@ -228,18 +365,9 @@ impl Crate {
         Self::check_wasm_pack_latest_version().map(|crt| crt.crt.max_version)
     }
 
-    /// Read the stamp file and return value assigned to a certain key.
-    fn return_stamp_file_value(file: &str, word: &str) -> Option<String> {
-        let created = file
-            .lines()
-            .find(|line| line.starts_with(word))
-            .and_then(|l| l.split_whitespace().nth(1));
-
-        created.map(|s| s.to_string())
-    }
-
     /// Call to the crates.io api and return the latest version of `wasm-pack`
     fn check_wasm_pack_latest_version() -> Result<Crate, Error> {
+        offline::ensure_online("check for a newer version of wasm-pack")?;
         let url = "https://crates.io/api/v1/crates/wasm-pack";
 
         let mut easy = easy::Easy2::new(Collector(Vec::new()));
@@ -293,6 +421,10 @@ impl CargoWasmPackProfile {
                 dwarf_debug_info: Some(false),
             },
             wasm_opt: None,
+            wasm_snip: None,
+            size_budget: None,
+            split_debug_info: Some(false),
+            target: CargoWasmPackProfileTargets::default(),
         }
     }
 
@@ -304,6 +436,10 @@ impl CargoWasmPackProfile {
                 dwarf_debug_info: Some(false),
             },
             wasm_opt: Some(CargoWasmPackProfileWasmOpt::Enabled(true)),
+            wasm_snip: None,
+            size_budget: None,
+            split_debug_info: Some(false),
+            target: CargoWasmPackProfileTargets::default(),
         }
     }
 
@@ -312,39 +448,16 @@ impl CargoWasmPackProfile {
             wasm_bindgen: CargoWasmPackProfileWasmBindgen {
                 debug_js_glue: Some(false),
                 demangle_name_section: Some(true),
-                dwarf_debug_info: Some(false),
+                dwarf_debug_info: Some(true),
             },
             wasm_opt: Some(CargoWasmPackProfileWasmOpt::Enabled(true)),
+            wasm_snip: None,
+            size_budget: None,
+            split_debug_info: Some(false),
+            target: CargoWasmPackProfileTargets::default(),
         }
     }
 
-    fn deserialize_dev<'de, D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let mut profile = <Option<Self>>::deserialize(deserializer)?.unwrap_or_default();
-        profile.update_with_defaults(&Self::default_dev());
-        Ok(profile)
-    }
-
-    fn deserialize_release<'de, D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let mut profile = <Option<Self>>::deserialize(deserializer)?.unwrap_or_default();
-        profile.update_with_defaults(&Self::default_release());
-        Ok(profile)
-    }
-
-    fn deserialize_profiling<'de, D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        let mut profile = <Option<Self>>::deserialize(deserializer)?.unwrap_or_default();
-        profile.update_with_defaults(&Self::default_profiling());
-        Ok(profile)
-    }
-
     fn update_with_defaults(&mut self, defaults: &Self) {
         macro_rules! d {
             ( $( $path:ident ).* ) => {
@@ -354,10 +467,32 @@ impl CargoWasmPackProfile {
         d!(wasm_bindgen.debug_js_glue);
         d!(wasm_bindgen.demangle_name_section);
         d!(wasm_bindgen.dwarf_debug_info);
+        d!(split_debug_info);
 
         if self.wasm_opt.is_none() {
             self.wasm_opt = defaults.wasm_opt.clone();
         }
+        if self.wasm_snip.is_none() {
+            self.wasm_snip = defaults.wasm_snip.clone();
+        }
+        if self.size_budget.is_none() {
+            self.size_budget = defaults.size_budget.clone();
+        }
+    }
+
+    /// The settings of this profile for `target`: those of its
+    /// `[target.<target>]` table, with the ones it doesn't set taken from this
+    /// profile.
+    fn for_target(&self, target: Target) -> CargoWasmPackProfile {
+        match self.target.get(target) {
+            Some(overrides) => {
+                let mut profile = overrides.clone();
+                profile.update_with_defaults(self);
+                profile.target = CargoWasmPackProfileTargets::default();
+                profile
+            }
+            None => self.clone(),
+        }
     }
 
     /// Get this profile's configured `[wasm-bindgen.debug-js-glue]` value.
@@ -376,12 +511,57 @@ impl CargoWasmPackProfile {
     }
 
     /// Get this profile's configured arguments for `wasm-opt`, if enabled.
+    /// These include `-g` if the profile keeps DWARF debug info, which
+    /// `wasm-opt` would strip otherwise.
     pub fn wasm_opt_args(&self) -> Option<Vec<String>> {
-        match self.wasm_opt.as_ref()? {
-            CargoWasmPackProfileWasmOpt::Enabled(false) => None,
-            CargoWasmPackProfileWasmOpt::Enabled(true) => Some(vec!["-O".to_string()]),
-            CargoWasmPackProfileWasmOpt::ExplicitArgs(s) => Some(s.clone()),
+        let mut args = match self.wasm_opt.as_ref()? {
+            CargoWasmPackProfileWasmOpt::Enabled(false) => return None,
+            CargoWasmPackProfileWasmOpt::Enabled(true) => vec!["-O".to_string()],
+            CargoWasmPackProfileWasmOpt::ExplicitArgs(s) => s.clone(),
+        };
+        if self.wasm_bindgen_dwarf_debug_info()
+            && !args.iter().any(|arg| arg == "-g" || arg == "--debuginfo")
+        {
+            args.push("-g".to_string());
         }
+        Some(args)
+    }
+
+    /// Get this profile's configured `[split-debug-info]` value. Debug info is
+    /// only split off when there is any, i.e. with `dwarf-debug-info`.
+    pub fn split_debug_info(&self) -> bool {
+        self.wasm_bindgen_dwarf_debug_info() && self.split_debug_info.unwrap()
+    }
+
+    /// Get this profile's configured arguments for `wasm-snip`, if there is
+    /// anything to snip.
+    pub fn wasm_snip_args(&self) -> Option<Vec<String>> {
+        let snip = self.wasm_snip.as_ref()?;
+        let mut args = Vec::new();
+        if snip.snip_rust_fmt_code {
+            args.push("--snip-rust-fmt-code".to_string());
+        }
+        if snip.snip_rust_panicking_code {
+            args.push("--snip-rust-panicking-code".to_string());
+        }
+        for pattern in &snip.patterns {
+            args.push("--pattern".to_string());
+            args.push(pattern.clone());
+        }
+        if !snip.functions.is_empty() {
+            args.push("--".to_string());
+            args.extend(snip.functions.iter().cloned());
+        }
+        if args.is_empty() {
+            None
+        } else {
+            Some(args)
+        }
+    }
+
+    /// Get this profile's configured `[size-budget]`, if any.
+    pub fn size_budget(&self) -> Option<&SizeBudget> {
+        self.size_budget.as_ref()
     }
 }
 
@@ -417,7 +597,8 @@ impl CrateData {
             .manifest_path(&manifest_path)
             .exec()?;
 
-        let manifest_and_keys = CrateData::parse_crate_data(&manifest_path)?;
+        let mut manifest_and_keys = CrateData::parse_crate_data(&manifest_path)?;
+        CrateData::resolve_custom_profiles(&mut manifest_and_keys, &data.workspace_root)?;
         CrateData::warn_for_unused_keys(&manifest_and_keys);
 
         let manifest = manifest_and_keys.manifest;
@@ -438,6 +619,72 @@ impl CrateData {
         })
     }
 
+    /// Reads the metadata of the workspace containing the crate or virtual
+    /// manifest inside `crate_path`, and returns the data for each of its
+    /// `cdylib` members.
+    ///
+    /// If `packages` is non-empty, only those members are returned, and it is
+    /// an error for any of them to not be a `cdylib` member. Members named in
+    /// `exclude` are skipped.
+    pub fn workspace_members(
+        crate_path: &Path,
+        packages: &[String],
+        exclude: &[String],
+        out_name: Option<String>,
+    ) -> Result<Vec<CrateData>, Error> {
+        let manifest_path = crate_path.join("Cargo.toml");
+        if !manifest_path.is_file() {
+            bail!(
+                "crate directory is missing a `Cargo.toml` file; is `{}` the \
+                 wrong directory?",
+                crate_path.display()
+            )
+        }
+
+        let data = cargo_metadata::MetadataCommand::new()
+            .manifest_path(&manifest_path)
+            .exec()?;
+
+        let mut members = vec![];
+        for id in &data.workspace_members {
+            let current_idx = data
+                .packages
+                .iter()
+                .position(|pkg| pkg.id == *id)
+                .ok_or_else(|| format_err!("failed to find package in metadata"))?;
+            let pkg = &data.packages[current_idx];
+            if exclude.contains(&pkg.name)
+                || (!packages.is_empty() && !packages.contains(&pkg.name))
+            {
+                continue;
+            }
+
+            let mut manifest_and_keys = CrateData::parse_crate_data(&pkg.manifest_path)?;
+            CrateData::resolve_custom_profiles(&mut manifest_and_keys, &data.workspace_root)?;
+            CrateData::warn_for_unused_keys(&manifest_and_keys);
+            let member = CrateData {
+                data: data.clone(),
+                manifest: manifest_and_keys.manifest,
+                current_idx,
+                out_name: out_name.clone(),
+            };
+            if member.check_crate_type().is_ok() {
+                members.push(member);
+            }
+        }
+
+        for name in packages {
+            if !members.iter().any(|m| m.package_name() == name) {
+                bail!(
+                    "package `{}` is not a member of the workspace with crate-type cdylib",
+                    name
+                )
+            }
+        }
+        members.sort_by(|a, b| a.package_name().cmp(b.package_name()));
+        Ok(members)
+    }
+
     fn is_same_path(path1: &Path, path2: &Path) -> bool {
         if let Ok(path1) = fs::canonicalize(&path1) {
             if let Ok(path2) = fs::canonicalize(&path2) {
@@ -480,6 +727,113 @@ impl CrateData {
         })
     }
 
+    /// Read the defaults for the options of `wasm-pack build` that the crate in
+    /// `crate_path` declares in `[package.metadata.wasm-pack]`. A virtual
+    /// manifest, or a missing one, declares none.
+    ///
+    /// Unknown keys aren't reported here, but when the crate data is read.
+    pub fn build_defaults(crate_path: &Path) -> Result<BuildDefaults, Error> {
+        #[derive(Deserialize)]
+        struct Manifest {
+            package: Option<Package>,
+        }
+        #[derive(Deserialize)]
+        struct Package {
+            #[serde(default)]
+            metadata: CargoMetadata,
+        }
+
+        let manifest_path = crate_path.join("Cargo.toml");
+        if !manifest_path.is_file() {
+            return Ok(BuildDefaults::default());
+        }
+        let manifest = fs::read_to_string(&manifest_path)
+            .with_context(|_| format!("failed to read: {}", manifest_path.display()))?;
+        let manifest: Manifest = toml::from_str(&manifest)
+            .with_context(|_| format!("failed to parse manifest: {}", manifest_path.display()))?;
+        let wasm_pack = match manifest.package {
+            Some(package) => package.metadata.wasm_pack,
+            None => return Ok(BuildDefaults::default()),
+        };
+        Ok(BuildDefaults {
+            target: wasm_pack.target,
+            scope: wasm_pack.scope,
+            out_dir: wasm_pack.out_dir,
+            out_name: wasm_pack.out_name,
+            no_typescript: wasm_pack.no_typescript,
+            extra_options: wasm_pack.extra_options,
+        })
+    }
+
+    /// Match the custom profiles configured in the manifest with the
+    /// `[profile.<name>]` tables of the workspace's `Cargo.toml`.
+    ///
+    /// Every custom cargo profile gets a wasm-pack profile, whose unset keys
+    /// default to those of `dev` or `release`, whichever the cargo profile
+    /// inherits from. Tables for profiles that cargo doesn't know about are
+    /// reported as unused keys.
+    pub fn resolve_custom_profiles(
+        manifest_and_keys: &mut ManifestAndUnsedKeys,
+        workspace_root: &Path,
+    ) -> Result<(), Error> {
+        let manifest_path = workspace_root.join("Cargo.toml");
+        let manifest = fs::read_to_string(&manifest_path)
+            .with_context(|_| format!("failed to read: {}", manifest_path.display()))?;
+        #[derive(Deserialize)]
+        struct WorkspaceManifest {
+            #[serde(default)]
+            profile: BTreeMap<String, CargoProfile>,
+        }
+        let cargo_profiles = toml::from_str::<WorkspaceManifest>(&manifest)
+            .with_context(|_| format!("failed to parse manifest: {}", manifest_path.display()))?
+            .profile;
+
+        let profiles = &mut manifest_and_keys
+            .manifest
+            .package
+            .metadata
+            .wasm_pack
+            .profile;
+        let unknown = profiles
+            .custom
+            .keys()
+            .filter(|name| !cargo_profiles.contains_key(*name))
+            .cloned()
+            .collect::<Vec<_>>();
+        for name in unknown {
+            profiles.custom.remove(&name);
+            manifest_and_keys
+                .unused_keys
+                .insert(format!("package.metadata.wasm-pack.profile.{}", name));
+        }
+
+        for name in cargo_profiles.keys() {
+            match name.as_str() {
+                "dev" | "release" | "profiling" | "test" | "bench" => continue,
+                _ => {}
+            }
+            // Follow the chain of `inherits` down to one of cargo's own
+            // profiles.
+            let mut base = name.as_str();
+            for _ in 0..cargo_profiles.len() {
+                match cargo_profiles.get(base).and_then(|p| p.inherits.as_ref()) {
+                    Some(inherits) => base = inherits,
+                    None => break,
+                }
+            }
+            let defaults = match base {
+                "dev" | "test" => CargoWasmPackProfile::default_dev(),
+                _ => CargoWasmPackProfile::default_release(),
+            };
+            profiles
+                .custom
+                .entry(name.clone())
+                .or_default()
+                .update_with_defaults(&defaults);
+        }
+        Ok(())
+    }
+
     /// Iterating through all the passed `unused_keys` and output
     /// a warning for each unknown key.
     pub fn warn_for_unused_keys(manifest_and_keys: &ManifestAndUnsedKeys) {
@@ -491,21 +845,68 @@ impl CrateData {
         });
     }
 
+    /// Get the hooks that the crate declares in
+    /// `[package.metadata.wasm-pack.hooks]`.
+    pub fn hooks(&self) -> &Hooks {
+        &self.manifest.package.metadata.wasm_pack.hooks
+    }
+
     /// Get the configured profile.
-    pub fn configured_profile(&self, profile: BuildProfile) -> &CargoWasmPackProfile {
+    pub fn configured_profile(&self, profile: &BuildProfile) -> &CargoWasmPackProfile {
+        let profiles = &self.manifest.package.metadata.wasm_pack.profile;
         match profile {
-            BuildProfile::Dev => &self.manifest.package.metadata.wasm_pack.profile.dev,
-            BuildProfile::Profiling => &self.manifest.package.metadata.wasm_pack.profile.profiling,
-            BuildProfile::Release => &self.manifest.package.metadata.wasm_pack.profile.release,
+            BuildProfile::Dev => &profiles.dev,
+            BuildProfile::Profiling => &profiles.profiling,
+            BuildProfile::Release => &profiles.release,
+            // Cargo itself rejects profiles that aren't defined, so this only
+            // falls back to the release profile for builds that will fail.
+            BuildProfile::Custom(name) => profiles.custom.get(name).unwrap_or(&profiles.release),
         }
     }
 
+    /// Get the configured profile as it applies to `target`, i.e. with the
+    /// settings of its `[target.<target>]` table, if there is one.
+    pub fn configured_target_profile(
+        &self,
+        profile: &BuildProfile,
+        target: Target,
+    ) -> CargoWasmPackProfile {
+        self.configured_profile(profile).for_target(target)
+    }
+
+    /// Get every configured profile, built-in and custom, as it applies to
+    /// each target.
+    pub fn all_configured_profiles(&self) -> Vec<CargoWasmPackProfile> {
+        let profiles = &self.manifest.package.metadata.wasm_pack.profile;
+        vec![&profiles.dev, &profiles.profiling, &profiles.release]
+            .into_iter()
+            .chain(profiles.custom.values())
+            .flat_map(|profile| {
+                Target::ALL
+                    .iter()
+                    .map(move |&target| profile.for_target(target))
+            })
+            .collect()
+    }
+
     /// Check that the crate the given path is properly configured.
     pub fn check_crate_config(&self) -> Result<(), Error> {
         self.check_crate_type()?;
         Ok(())
     }
 
+    /// Check that the crate has binaries to compile to WASI.
+    pub fn check_wasi_crate_config(&self) -> Result<(), Error> {
+        if self.bin_names().is_empty() {
+            bail!(
+                "`--target wasi` compiles the binaries of a crate, but {} has none. Add a \
+                 `src/main.rs`, or a `[[bin]]` target to its Cargo.toml.",
+                self.package_name()
+            )
+        }
+        Ok(())
+    }
+
     fn check_crate_type(&self) -> Result<(), Error> {
         let pkg = &self.data.packages[self.current_idx];
         let any_cdylib = pkg
@@ -524,6 +925,17 @@ impl CrateData {
         )
     }
 
+    /// Get the name of the package, as given in its `Cargo.toml`.
+    pub fn package_name(&self) -> &str {
+        &self.data.packages[self.current_idx].name
+    }
+
+    /// Returns the directory containing this crate's `Cargo.toml`.
+    pub fn manifest_dir(&self) -> &Path {
+        let manifest_path = &self.data.packages[self.current_idx].manifest_path;
+        manifest_path.parent().unwrap_or(manifest_path)
+    }
+
     /// Get the crate name for the crate at the given path.
     pub fn crate_name(&self) -> String {
         let pkg = &self.data.packages[self.current_idx];
@@ -537,6 +949,21 @@ impl CrateData {
         }
     }
 
+    /// Get the names of the crate's binaries.
+    pub fn bin_names(&self) -> Vec<String> {
+        let pkg = &self.data.packages[self.current_idx];
+        pkg.targets
+            .iter()
+            .filter(|t| t.kind.iter().any(|k| k == "bin"))
+            .map(|t| t.name.clone())
+            .collect()
+    }
+
+    /// Set the prefix for output file names, as `--out-name` does.
+    pub fn set_out_name(&mut self, out_name: Option<String>) {
+        self.out_name = out_name;
+    }
+
     /// Get the prefix for output file names
     pub fn name_prefix(&self) -> String {
         match &self.out_name {
@@ -566,6 +993,57 @@ impl CrateData {
         Path::new(&self.data.workspace_root)
     }
 
+    /// Returns the paths that affect the output of a build: the `Cargo.toml`
+    /// and sources of this crate and of every crate it depends on by path, and
+    /// the `Cargo.toml` and `Cargo.lock` of the workspace.
+    pub fn watch_paths(&self) -> Vec<PathBuf> {
+        let workspace_root = self.workspace_root();
+        let mut paths = vec![
+            workspace_root.join("Cargo.toml"),
+            workspace_root.join("Cargo.lock"),
+        ];
+        let mut seen = BTreeSet::new();
+        let mut pending = vec![self.current_idx];
+
+        while let Some(idx) = pending.pop() {
+            if !seen.insert(idx) {
+                continue;
+            }
+            let pkg = &self.data.packages[idx];
+            let root = match pkg.manifest_path.parent() {
+                Some(root) => root,
+                None => continue,
+            };
+            paths.push(pkg.manifest_path.clone());
+            paths.push(root.join("src"));
+            for target in &pkg.targets {
+                match target.src_path.parent() {
+                    Some(dir) if dir != root && !dir.starts_with(root.join("src")) => {
+                        paths.push(dir.to_path_buf())
+                    }
+                    _ => paths.push(target.src_path.clone()),
+                }
+            }
+
+            // Path dependencies are the only ones without a source.
+            let path_deps = pkg
+                .dependencies
+                .iter()
+                .filter(|dep| dep.source.is_none())
+                .filter_map(|dep| {
+                    self.data
+                        .packages
+                        .iter()
+                        .position(|p| p.name == dep.name && p.source.is_none())
+                });
+            pending.extend(path_deps);
+        }
+
+        paths.sort();
+        paths.dedup();
+        paths
+    }
+
     /// Generate a package.json file inside in `./pkg`.
     pub fn write_package_json(
         &self,
@@ -609,6 +1087,10 @@ impl CrateData {
                 Target::NoModules => self.to_nomodules(scope, disable_dts, out_dir),
                 Target::Bundler => self.to_esmodules(scope, disable_dts, out_dir),
                 Target::Web => self.to_web(scope, disable_dts, out_dir),
+                Target::Wasi => self.to_wasi(scope, out_dir),
+                Target::Deno => {
+                    bail!("`--target deno` generates a deno.json instead of a package.json")
+                }
             }
         };
 
@@ -618,6 +1100,45 @@ impl CrateData {
         Ok(())
     }
 
+    /// Generate a single package.json inside `out_dir` for a build that
+    /// emitted several targets, each one into its own `out_dir/<target>`
+    /// subdirectory. Consumers pick the right variant through the conditional
+    /// `exports` map.
+    pub fn write_multi_target_package_json(
+        &self,
+        out_dir: &Path,
+        scope: &Option<String>,
+        disable_dts: bool,
+        targets: &[Target],
+    ) -> Result<(), Error> {
+        let pkg_file_path = out_dir.join("package.json");
+        let npm_data = self.to_multi_target(scope, disable_dts, targets, out_dir);
+
+        let npm_json = serde_json::to_string_pretty(&npm_data)?;
+        fs::write(&pkg_file_path, npm_json)
+            .with_context(|_| format!("failed to write: {}", pkg_file_path.display()))?;
+        Ok(())
+    }
+
+    /// Generate a deno.json file that exports the `mod.ts` in `out_dir`.
+    pub fn write_deno_json(&self, out_dir: &Path, scope: &Option<String>) -> Result<(), Error> {
+        let pkg = &self.data.packages[self.current_idx];
+        let config = DenoConfig {
+            name: match scope {
+                Some(s) => format!("@{}/{}", s, pkg.name),
+                None => pkg.name.clone(),
+            },
+            version: pkg.version.to_string(),
+            license: self.manifest.package.license.clone(),
+            exports: format!("./{}", deno::MODULE_FILE),
+        };
+
+        let path = out_dir.join(deno::CONFIG_FILE);
+        let json = serde_json::to_string_pretty(&config)?;
+        fs::write(&path, json).with_context(|_| format!("failed to write: {}", path.display()))?;
+        Ok(())
+    }
+
     fn npm_data(
         &self,
         scope: &Option<String>,
@@ -656,17 +1177,7 @@ impl CrateData {
             None
         };
 
-        if let Ok(entries) = fs::read_dir(out_dir) {
-            let file_names = entries
-                .filter_map(|e| e.ok())
-                .filter(|e| e.metadata().map(|m| m.is_file()).unwrap_or(false))
-                .filter_map(|e| e.file_name().into_string().ok())
-                .filter(|f| f.starts_with("LICENSE"))
-                .filter(|f| f != "LICENSE");
-            for file_name in file_names {
-                files.push(file_name);
-            }
-        }
+        files.extend(CrateData::license_files(out_dir));
 
         NpmData {
             name: npm_name,
@@ -678,6 +1189,20 @@ impl CrateData {
         }
     }
 
+    /// Lists the extra `LICENSE-*` files that were copied into `out_dir`.
+    fn license_files(out_dir: &Path) -> Vec<String> {
+        match fs::read_dir(out_dir) {
+            Ok(entries) => entries
+                .filter_map(|e| e.ok())
+                .filter(|e| e.metadata().map(|m| m.is_file()).unwrap_or(false))
+                .filter_map(|e| e.file_name().into_string().ok())
+                .filter(|f| f.starts_with("LICENSE"))
+                .filter(|f| f != "LICENSE")
+                .collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
     fn license(&self) -> Option<String> {
         self.manifest.package.license.clone().or_else(|| {
             self.manifest.package.license_file.clone().map(|file| {
@@ -815,6 +1340,131 @@ impl CrateData {
         })
     }
 
+    fn to_wasi(&self, scope: &Option<String>, out_dir: &Path) -> NpmPackage {
+        let pkg = &self.data.packages[self.current_idx];
+        let npm_name = match scope {
+            Some(s) => format!("@{}/{}", s, pkg.name),
+            None => pkg.name.clone(),
+        };
+        let keywords = if !pkg.keywords.is_empty() {
+            Some(pkg.keywords.clone())
+        } else {
+            None
+        };
+
+        let mut files = vec![];
+        let mut bin = BTreeMap::new();
+        for name in self.bin_names() {
+            let launcher = wasi::launcher_file(&name);
+            files.push(wasi::wasm_file(&name));
+            files.push(launcher.clone());
+            bin.insert(name, launcher);
+        }
+        files.extend(CrateData::license_files(out_dir));
+
+        self.check_optional_fields();
+
+        NpmPackage::WasiPackage(WasiPackage {
+            name: npm_name,
+            collaborators: pkg.authors.clone(),
+            description: self.manifest.package.description.clone(),
+            version: pkg.version.to_string(),
+            license: self.license(),
+            repository: self
+                .manifest
+                .package
+                .repository
+                .clone()
+                .map(|repo_url| Repository {
+                    ty: "git".to_string(),
+                    url: repo_url,
+                }),
+            files,
+            bin,
+            homepage: self.manifest.package.homepage.clone(),
+            keywords,
+        })
+    }
+
+    fn to_multi_target(
+        &self,
+        scope: &Option<String>,
+        disable_dts: bool,
+        targets: &[Target],
+        out_dir: &Path,
+    ) -> NpmPackage {
+        let pkg = &self.data.packages[self.current_idx];
+        let mut files = vec![];
+        let mut entries = BTreeMap::new();
+        let mut name = None;
+        let mut types = None;
+        let mut homepage = None;
+        let mut keywords = None;
+
+        for target in targets {
+            let subdir = target.to_string();
+            let data = self.npm_data(
+                scope,
+                *target == Target::Bundler,
+                disable_dts,
+                &out_dir.join(&subdir),
+            );
+            files.extend(data.files.iter().map(|f| format!("{}/{}", subdir, f)));
+            if types.is_none() {
+                types = data.dts_file.map(|f| format!("./{}/{}", subdir, f));
+            }
+            entries.insert(subdir.clone(), format!("./{}/{}", subdir, data.main));
+            name = Some(data.name);
+            homepage = data.homepage;
+            keywords = data.keywords;
+        }
+        files.extend(CrateData::license_files(out_dir));
+
+        let module = entries
+            .get(&Target::Bundler.to_string())
+            .or_else(|| entries.get(&Target::Web.to_string()))
+            .cloned();
+        let main = entries.get(&Target::Nodejs.to_string()).cloned();
+
+        let mut exports = BTreeMap::new();
+        exports.insert(
+            ".".to_string(),
+            ConditionalExports {
+                types: types.clone(),
+                browser: module.clone(),
+                import: module.clone(),
+                require: main.clone(),
+            },
+        );
+
+        self.check_optional_fields();
+
+        NpmPackage::MultiTargetPackage(MultiTargetPackage {
+            name: name.unwrap_or_else(|| pkg.name.clone()),
+            collaborators: pkg.authors.clone(),
+            description: self.manifest.package.description.clone(),
+            version: pkg.version.to_string(),
+            license: self.license(),
+            repository: self
+                .manifest
+                .package
+                .repository
+                .clone()
+                .map(|repo_url| Repository {
+                    ty: "git".to_string(),
+                    url: repo_url,
+                }),
+            files,
+            main,
+            module,
+            homepage,
+            types,
+            exports,
+            side_effects: false,
+            keywords,
+        })
+    }
+
     fn check_optional_fields(&self) {
         let mut messages = vec![];
         if self.manifest.package.description.is_none() {
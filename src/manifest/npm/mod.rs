@@ -1,15 +1,21 @@
 mod commonjs;
 mod esmodules;
+mod multitarget;
 mod nomodules;
 pub mod repository;
+mod wasi;
 
 pub use self::commonjs::CommonJSPackage;
 pub use self::esmodules::ESModulesPackage;
+pub use self::multitarget::{ConditionalExports, MultiTargetPackage};
 pub use self::nomodules::NoModulesPackage;
+pub use self::wasi::WasiPackage;
 
 #[derive(Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum NpmPackage {
+    MultiTargetPackage(MultiTargetPackage),
+    WasiPackage(WasiPackage),
     CommonJSPackage(CommonJSPackage),
     ESModulesPackage(ESModulesPackage),
     NoModulesPackage(NoModulesPackage),
@@ -18,6 +24,8 @@ pub enum NpmPackage {
 impl NpmPackage {
     pub fn add_file(&mut self, file: String) {
         match self {
+            Self::MultiTargetPackage(pkg) => pkg.files.push(file),
+            Self::WasiPackage(pkg) => pkg.files.push(file),
             Self::CommonJSPackage(pkg) => pkg.files.push(file),
             Self::ESModulesPackage(pkg) => pkg.files.push(file),
             Self::NoModulesPackage(pkg) => pkg.files.push(file),
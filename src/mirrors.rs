@@ -0,0 +1,95 @@
+//! The URLs that the tools wasm-pack installs are downloaded from, which can be
+//! pointed at a mirror.
+//!
+//! A URL is made from a template in which `{version}`, `{target}` and `{ext}`
+//! are replaced. The template of a tool is taken from the
+//! `WASM_PACK_<TOOL>_URL` environment variable, e.g. `WASM_PACK_WASM_BINDGEN_URL`,
+//! then from the `[mirrors]` table of wasm-pack's config file, and defaults to
+//! the tool's official releases.
+
+use config;
+use failure::{Error, ResultExt};
+use std::env;
+
+/// The tools that are downloaded, with the templates of their official URLs.
+pub const DEFAULT_TEMPLATES: &[(&str, &str)] = &[
+    (
+        "wasm-bindgen",
+        "https://github.com/rustwasm/wasm-bindgen/releases/download/{version}/wasm-bindgen-{version}-{target}.{ext}",
+    ),
+    (
+        "cargo-generate",
+        "https://github.com/cargo-generate/cargo-generate/releases/download/v{version}/cargo-generate-v{version}-{target}.{ext}",
+    ),
+    (
+        "wasm-opt",
+        "https://github.com/WebAssembly/binaryen/releases/download/{version}/binaryen-{version}-{target}.{ext}",
+    ),
+    (
+        "chromedriver",
+        "https://chromedriver.storage.googleapis.com/{version}/chromedriver_{target}.{ext}",
+    ),
+    (
+        "geckodriver",
+        "https://github.com/mozilla/geckodriver/releases/download/{version}/geckodriver-{version}-{target}.{ext}",
+    ),
+];
+
+/// The environment variable with the URL template of `tool`.
+pub fn env_var(tool: &str) -> String {
+    format!("WASM_PACK_{}_URL", tool.to_uppercase().replace('-', "_"))
+}
+
+/// The URL template of `tool`, and where it was configured, if it is not the
+/// default one.
+pub fn template(tool: &str) -> Result<(String, Option<String>), Error> {
+    let var = env_var(tool);
+    if let Ok(template) = env::var(&var) {
+        return Ok((template, Some(var)));
+    }
+    if let Some(template) = config::read()?.mirrors.remove(tool) {
+        return Ok((template, Some(config::config_path()?.display().to_string())));
+    }
+    match DEFAULT_TEMPLATES.iter().find(|(name, _)| *name == tool) {
+        Some((_, template)) => Ok((template.to_string(), None)),
+        None => bail!("`{}` is not downloaded by wasm-pack", tool),
+    }
+}
+
+/// The URL that `version` of `tool` for `target` is downloaded from, as an
+/// archive with the extension `ext`.
+pub fn url(tool: &str, version: &str, target: &str, ext: &str) -> Result<String, Error> {
+    let (template, source) = template(tool)?;
+    let url = expand(&template, version, target, ext).with_context(|_| match source {
+        Some(source) => format!("invalid URL template for {} in {}", tool, source),
+        None => format!("invalid URL template for {}", tool),
+    })?;
+    Ok(url)
+}
+
+/// Replace the variables `{version}`, `{target}` and `{ext}` in `template`.
+pub fn expand(template: &str, version: &str, target: &str, ext: &str) -> Result<String, Error> {
+    let mut url = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        url.push_str(&rest[..start]);
+        let end = match rest[start..].find('}') {
+            Some(end) => start + end,
+            None => bail!("`{}` has an unclosed `{{`", template),
+        };
+        match &rest[start + 1..end] {
+            "version" => url.push_str(version),
+            "target" => url.push_str(target),
+            "ext" => url.push_str(ext),
+            name => bail!(
+                "`{}` has the unknown variable `{{{}}}`. The variables are `{{version}}`, \
+                 `{{target}}` and `{{ext}}`.",
+                template,
+                name
+            ),
+        }
+        rest = &rest[end + 1..];
+    }
+    url.push_str(rest);
+    Ok(url)
+}
@@ -0,0 +1,39 @@
+//! Offline mode, set with `--offline` or `WASM_PACK_OFFLINE`, in which
+//! wasm-pack never accesses the network. Tools are only taken from the cache
+//! or the `$PATH` then.
+
+use failure::Error;
+use std::env;
+
+/// The environment variable that turns offline mode on, like `--offline`.
+pub const ENV_VAR: &str = "WASM_PACK_OFFLINE";
+
+/// Whether wasm-pack is offline, i.e. `WASM_PACK_OFFLINE` is set to anything
+/// but an empty string, `0` or `false`.
+pub fn is_offline() -> bool {
+    match env::var(ENV_VAR) {
+        Ok(value) => !matches!(value.trim(), "" | "0" | "false"),
+        Err(_) => false,
+    }
+}
+
+/// Turn offline mode on for this process and the processes it starts. This
+/// sets `CARGO_NET_OFFLINE` too, so that cargo doesn't access the network
+/// either.
+pub fn set_offline() {
+    env::set_var(ENV_VAR, "1");
+    env::set_var("CARGO_NET_OFFLINE", "true");
+}
+
+/// Fail with an error saying that wasm-pack can't `what` because it is
+/// offline, if it is.
+pub fn ensure_online(what: &str) -> Result<(), Error> {
+    if is_offline() {
+        bail!(
+            "wasm-pack is offline (--offline or {}), so it can't {}",
+            ENV_VAR,
+            what
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,683 @@
+//! The execution plan that `wasm-pack build --dry-run` prints instead of
+//! building.
+
+use bindgen;
+use build;
+use command::build::{Build, BuildStep, Member, Target};
+use debug_info;
+use deno;
+use failure::Error;
+use hooks::{self, HookPoint};
+use install::{self, InstallPlan, Tool};
+use license;
+use lockfile::Lockfile;
+use manifest::{CargoWasmPackProfile, CrateData};
+use size;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use wasi;
+use wasm_opt;
+use wasm_snip;
+
+/// Everything a build would do.
+#[derive(Debug, Serialize)]
+pub struct Plan {
+    /// The build profile, e.g. `release`.
+    pub profile: String,
+    /// The install mode, e.g. `normal`.
+    pub mode: String,
+    /// The targets that bindings are generated for.
+    pub targets: Vec<String>,
+    /// The external tools the build needs.
+    pub tools: Vec<ToolPlan>,
+    /// The steps of the build, in the order they run.
+    pub steps: Vec<StepPlan>,
+}
+
+/// An external tool that a build needs.
+#[derive(Debug, Serialize)]
+pub struct ToolPlan {
+    /// The name of the tool.
+    pub tool: String,
+    /// The required version, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// How the tool would be obtained.
+    pub install: InstallPlan,
+}
+
+/// A single step of a build.
+#[derive(Debug, Serialize)]
+pub struct StepPlan {
+    /// The name of the step.
+    pub step: String,
+    /// The workspace member the step runs for, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+    /// The commands the step runs.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub commands: Vec<CommandPlan>,
+    /// The files the step writes.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub writes: Vec<PathBuf>,
+    /// Anything else worth knowing about the step.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+/// A command that a step runs.
+#[derive(Debug, Serialize)]
+pub struct CommandPlan {
+    /// The program followed by its arguments.
+    pub args: Vec<String>,
+    /// The directory the command runs in, if it isn't the current one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+    /// The environment variables that are set for the command.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+}
+
+impl<'a> From<&'a Command> for CommandPlan {
+    fn from(command: &'a Command) -> CommandPlan {
+        let mut args = vec![command.get_program().to_string_lossy().into_owned()];
+        args.extend(
+            command
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned()),
+        );
+        let env = command
+            .get_envs()
+            .filter_map(|(key, value)| {
+                let value = value?.to_string_lossy().into_owned();
+                Some((key.to_string_lossy().into_owned(), value))
+            })
+            .collect();
+        CommandPlan {
+            args,
+            cwd: command.get_current_dir().map(Path::to_path_buf),
+            env,
+        }
+    }
+}
+
+impl Plan {
+    /// Work out what `build` would do when run, without running anything that
+    /// has side effects. `steps` are the steps `build` runs, along with the
+    /// workspace member each of them runs for, if any.
+    pub fn new(
+        build: &Build,
+        steps: &[(&'static str, BuildStep, Option<&Member>)],
+    ) -> Result<Plan, Error> {
+        // A build of a single crate is planned like a workspace member.
+        let own = Member {
+            crate_data: build.crate_data.clone(),
+            out_dir: build.out_dir.clone(),
+            scope: build.scope.clone(),
+            out_name: build.out_name.clone(),
+            disable_dts: build.disable_dts,
+        };
+        let mut planner = Planner {
+            wasm_bindgen: None,
+            wasm_snip: None,
+            wasm_opt: None,
+        };
+        let steps = steps
+            .iter()
+            .map(|&(name, build_step, member)| {
+                let mut step = StepPlan {
+                    step: name.trim_start_matches("step_").to_string(),
+                    package: member.map(|member| member.crate_data.package_name().to_string()),
+                    commands: Vec::new(),
+                    writes: Vec::new(),
+                    notes: Vec::new(),
+                };
+                build_step.describe(&mut planner, build, member.unwrap_or(&own), &mut step)?;
+                Ok(step)
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let tools = planner
+            .wasm_bindgen
+            .into_iter()
+            .chain(planner.wasm_snip)
+            .chain(planner.wasm_opt)
+            .collect();
+
+        Ok(Plan {
+            profile: build.profile.name().to_string(),
+            mode: format!("{:?}", build.mode).to_lowercase(),
+            targets: build.targets.iter().map(|t| t.to_string()).collect(),
+            tools,
+            steps,
+        })
+    }
+}
+
+/// Describes a step of a build for `member` in its `StepPlan`.
+pub type DescribeStep = fn(&mut Planner, &Build, &Member, &mut StepPlan) -> Result<(), Error>;
+
+/// Describes each step of a build, and collects the tools that the steps
+/// need. It has a method for every step of `Build`, of the same name.
+pub struct Planner {
+    wasm_bindgen: Option<ToolPlan>,
+    wasm_snip: Option<ToolPlan>,
+    wasm_opt: Option<ToolPlan>,
+}
+
+impl Planner {
+    /// The command that prints the version of rustc.
+    pub fn step_check_rustc_version(
+        &mut self,
+        _build: &Build,
+        _member: &Member,
+        step: &mut StepPlan,
+    ) -> Result<(), Error> {
+        let mut cmd = Command::new("rustc");
+        cmd.arg("--version");
+        step.commands.push((&cmd).into());
+        Ok(())
+    }
+
+    /// What is checked in the crate's configuration.
+    pub fn step_check_crate_config(
+        &mut self,
+        build: &Build,
+        _member: &Member,
+        step: &mut StepPlan,
+    ) -> Result<(), Error> {
+        let note = if build.is_wasi() {
+            "check that the crate has binaries"
+        } else {
+            "check that the crate-type includes cdylib"
+        };
+        step.notes.push(note.to_string());
+        Ok(())
+    }
+
+    /// The rust target that has to be installed.
+    pub fn step_check_for_wasm_target(
+        &mut self,
+        build: &Build,
+        _member: &Member,
+        step: &mut StepPlan,
+    ) -> Result<(), Error> {
+        step.notes.push(format!(
+            "check that the {} target is installed, and add it with rustup if it isn't",
+            build.targets[0].rust_target()
+        ));
+        Ok(())
+    }
+
+    /// The pre-build hooks of the crate, or of every workspace member.
+    pub fn step_run_pre_build_hooks(
+        &mut self,
+        build: &Build,
+        member: &Member,
+        step: &mut StepPlan,
+    ) -> Result<(), Error> {
+        if build.members.is_empty() {
+            add_hooks(step, &member.crate_data, HookPoint::PreBuild);
+        } else {
+            for member in &build.members {
+                add_hooks(step, &member.crate_data, HookPoint::PreBuild);
+            }
+        }
+        Ok(())
+    }
+
+    /// The cargo command that compiles the crate, and the artifacts it writes.
+    pub fn step_build_wasm(
+        &mut self,
+        build: &Build,
+        member: &Member,
+        step: &mut StepPlan,
+    ) -> Result<(), Error> {
+        let cmd = build::cargo_build_wasm_command(
+            &build.crate_path,
+            &build.profile,
+            build.targets[0],
+            &build.cargo_build_options(),
+        );
+        step.commands.push((&cmd).into());
+        if build.is_wasi() {
+            step.writes
+                .extend(wasi::artifact_paths(&member.crate_data, &build.profile));
+        } else if build.members.is_empty() {
+            step.writes.push(bindgen::wasm_artifact_path(
+                &member.crate_data,
+                &build.profile,
+            ));
+        } else {
+            for member in &build.members {
+                step.writes.push(bindgen::wasm_artifact_path(
+                    &member.crate_data,
+                    &build.profile,
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The `.gitignore` of the out dir.
+    pub fn step_create_dir(
+        &mut self,
+        _build: &Build,
+        member: &Member,
+        step: &mut StepPlan,
+    ) -> Result<(), Error> {
+        step.writes.push(member.out_dir.join(".gitignore"));
+        Ok(())
+    }
+
+    /// The readme that is copied, if any.
+    pub fn step_copy_readme(
+        &mut self,
+        build: &Build,
+        member: &Member,
+        step: &mut StepPlan,
+    ) -> Result<(), Error> {
+        let crate_path = member.crate_data.manifest_dir();
+        if build.is_child {
+            step.notes.push("skipped for --is-child".to_string());
+        } else if crate_path.join("README.md").is_file() {
+            step.writes.push(member.out_dir.join("README.md"));
+        } else {
+            step.notes.push("the crate has no README.md".to_string());
+        }
+        Ok(())
+    }
+
+    /// The license files that are copied, if any.
+    pub fn step_copy_license(
+        &mut self,
+        build: &Build,
+        member: &Member,
+        step: &mut StepPlan,
+    ) -> Result<(), Error> {
+        let crate_data = &member.crate_data;
+        if build.is_child {
+            step.notes.push("skipped for --is-child".to_string());
+            return Ok(());
+        }
+        for file in license::crate_license_files(crate_data, crate_data.manifest_dir()) {
+            step.writes.push(member.out_dir.join(file));
+        }
+        if step.writes.is_empty() {
+            step.notes
+                .push("the crate has no license files".to_string());
+        }
+        Ok(())
+    }
+
+    /// The WASI binaries and their launchers.
+    pub fn step_copy_wasi_binaries(
+        &mut self,
+        _build: &Build,
+        member: &Member,
+        step: &mut StepPlan,
+    ) -> Result<(), Error> {
+        for name in member.crate_data.bin_names() {
+            step.writes
+                .push(member.out_dir.join(wasi::wasm_file(&name)));
+            step.writes
+                .push(member.out_dir.join(wasi::launcher_file(&name)));
+        }
+        Ok(())
+    }
+
+    /// How wasm-bindgen would be obtained.
+    pub fn step_install_wasm_bindgen(
+        &mut self,
+        build: &Build,
+        member: &Member,
+        step: &mut StepPlan,
+    ) -> Result<(), Error> {
+        let tool = self.wasm_bindgen(build, &member.crate_data)?;
+        step.notes.push(format!("wasm-bindgen: {}", tool.install));
+        Ok(())
+    }
+
+    /// The wasm-bindgen command of each target, and the files it writes.
+    pub fn step_run_wasm_bindgen(
+        &mut self,
+        build: &Build,
+        member: &Member,
+        step: &mut StepPlan,
+    ) -> Result<(), Error> {
+        let crate_data = &member.crate_data;
+        let name_prefix = crate_data.name_prefix();
+        let bindgen_path = self
+            .wasm_bindgen(build, crate_data)?
+            .install
+            .path()
+            .map(Path::to_path_buf);
+        for &target in &build.targets {
+            let dir = build.target_dir_in(&member.out_dir, target);
+            let target_args = match &bindgen_path {
+                Some(path) => bindgen::target_args(target, path)?,
+                None => vec!["--target".to_string(), target.to_string()],
+            };
+            let cmd = bindgen::wasm_bindgen_command(
+                crate_data,
+                bindgen_path
+                    .as_deref()
+                    .unwrap_or_else(|| Path::new("wasm-bindgen")),
+                &dir,
+                &member.out_name,
+                member.disable_dts,
+                target,
+                &target_args,
+                &build.profile,
+            );
+            step.commands.push((&cmd).into());
+            step.writes
+                .push(dir.join(format!("{}_bg.wasm", name_prefix)));
+            step.writes.push(dir.join(format!("{}.js", name_prefix)));
+            if target == Target::Bundler {
+                step.writes.push(dir.join(format!("{}_bg.js", name_prefix)));
+            }
+            if !member.disable_dts {
+                step.writes.push(dir.join(format!("{}.d.ts", name_prefix)));
+            }
+        }
+        Ok(())
+    }
+
+    /// The post-bindgen hooks of the crate.
+    pub fn step_run_post_bindgen_hooks(
+        &mut self,
+        _build: &Build,
+        member: &Member,
+        step: &mut StepPlan,
+    ) -> Result<(), Error> {
+        add_hooks(step, &member.crate_data, HookPoint::PostBindgen);
+        Ok(())
+    }
+
+    /// The wasm-snip command of each `.wasm` file, if the profile snips.
+    pub fn step_run_wasm_snip(
+        &mut self,
+        build: &Build,
+        member: &Member,
+        step: &mut StepPlan,
+    ) -> Result<(), Error> {
+        let crate_data = &member.crate_data;
+        for &target in &build.targets {
+            let args = match target_profile(build, crate_data, target).wasm_snip_args() {
+                Some(args) => args,
+                None => continue,
+            };
+            let wasm_snip_path = self.wasm_snip(build)?.install.path().map(Path::to_path_buf);
+            for wasm in build.target_wasm_files_in(crate_data, &member.out_dir, target) {
+                let cmd = wasm_snip::command(
+                    wasm_snip_path
+                        .as_deref()
+                        .unwrap_or_else(|| Path::new("wasm-snip")),
+                    &wasm,
+                    &wasm.with_extension("wasm-snip.wasm"),
+                    &args,
+                );
+                step.commands.push((&cmd).into());
+                step.writes.push(wasm);
+            }
+        }
+        Ok(())
+    }
+
+    /// The wasm-opt command of each `.wasm` file, unless the profile disables
+    /// wasm-opt.
+    pub fn step_run_wasm_opt(
+        &mut self,
+        build: &Build,
+        member: &Member,
+        step: &mut StepPlan,
+    ) -> Result<(), Error> {
+        let crate_data = &member.crate_data;
+        for &target in &build.targets {
+            let args = match target_profile(build, crate_data, target).wasm_opt_args() {
+                Some(args) => args,
+                None => {
+                    step.notes
+                        .push(for_target(build, target, "wasm-opt is disabled"));
+                    continue;
+                }
+            };
+            let wasm_opt_path = self.wasm_opt(build).install.path().map(Path::to_path_buf);
+            for wasm in build.target_wasm_files_in(crate_data, &member.out_dir, target) {
+                let cmd = wasm_opt::command(
+                    wasm_opt_path
+                        .as_deref()
+                        .unwrap_or_else(|| Path::new("wasm-opt")),
+                    &wasm,
+                    &wasm.with_extension("wasm-opt.wasm"),
+                    &args,
+                );
+                step.commands.push((&cmd).into());
+                step.writes.push(wasm);
+            }
+        }
+        Ok(())
+    }
+
+    /// The `.debug.wasm` files, if the profile splits off the debug info.
+    pub fn step_split_debug_info(
+        &mut self,
+        build: &Build,
+        member: &Member,
+        step: &mut StepPlan,
+    ) -> Result<(), Error> {
+        let crate_data = &member.crate_data;
+        for &target in &build.targets {
+            if !target_profile(build, crate_data, target).split_debug_info() {
+                step.notes
+                    .push(for_target(build, target, "debug info is not split off"));
+                continue;
+            }
+            for wasm in build.target_wasm_files_in(crate_data, &member.out_dir, target) {
+                step.writes.push(debug_info::debug_file_path(&wasm));
+                step.writes.push(wasm);
+            }
+        }
+        Ok(())
+    }
+
+    /// The size report, and the size budgets it is checked against.
+    pub fn step_report_size(
+        &mut self,
+        build: &Build,
+        member: &Member,
+        step: &mut StepPlan,
+    ) -> Result<(), Error> {
+        step.notes
+            .push("print the size report of the generated wasm".to_string());
+        if build.size_report {
+            step.writes
+                .push(member.out_dir.join(size::SIZE_REPORT_FILE));
+        }
+        for &target in &build.targets {
+            let profile = target_profile(build, &member.crate_data, target);
+            if let Some(budget) = profile.size_budget() {
+                step.notes.push(format!(
+                    "fail if the wasm{} exceeds the size budget of {}",
+                    if build.targets.len() > 1 {
+                        format!(" for {}", target)
+                    } else {
+                        String::new()
+                    },
+                    budget
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// The `package.json`, and the post-package-json hooks of the crate.
+    pub fn step_create_json(
+        &mut self,
+        _build: &Build,
+        member: &Member,
+        step: &mut StepPlan,
+    ) -> Result<(), Error> {
+        step.writes.push(member.out_dir.join("package.json"));
+        add_hooks(step, &member.crate_data, HookPoint::PostPackageJson);
+        Ok(())
+    }
+
+    /// The Deno module and `deno.json`, and the post-package-json hooks of
+    /// the crate.
+    pub fn step_create_deno_json(
+        &mut self,
+        _build: &Build,
+        member: &Member,
+        step: &mut StepPlan,
+    ) -> Result<(), Error> {
+        step.writes.push(member.out_dir.join(deno::MODULE_FILE));
+        step.writes.push(member.out_dir.join(deno::CONFIG_FILE));
+        add_hooks(step, &member.crate_data, HookPoint::PostPackageJson);
+        Ok(())
+    }
+
+    fn wasm_bindgen(&mut self, build: &Build, crate_data: &CrateData) -> Result<&ToolPlan, Error> {
+        if self.wasm_bindgen.is_none() {
+            let (version, install) = match Lockfile::new(crate_data) {
+                Ok(lockfile) => match lockfile.require_wasm_bindgen() {
+                    Ok(version) => {
+                        let install = install::plan_prebuilt_or_cargo_install(
+                            Tool::WasmBindgen,
+                            &build.cache,
+                            version,
+                            build.mode.install_permitted(),
+                        )?;
+                        (Some(version.to_string()), install)
+                    }
+                    Err(e) => (
+                        None,
+                        InstallPlan::Unavailable {
+                            reason: e.to_string(),
+                        },
+                    ),
+                },
+                Err(_) => (
+                    None,
+                    InstallPlan::Undetermined {
+                        reason: "the version is read from Cargo.lock, which `cargo build` \
+                                 hasn't written yet"
+                            .to_string(),
+                    },
+                ),
+            };
+            self.wasm_bindgen = Some(ToolPlan {
+                tool: Tool::WasmBindgen.to_string(),
+                version,
+                install,
+            });
+        }
+        Ok(self.wasm_bindgen.as_ref().unwrap())
+    }
+
+    fn wasm_snip(&mut self, build: &Build) -> Result<&ToolPlan, Error> {
+        if self.wasm_snip.is_none() {
+            self.wasm_snip = Some(ToolPlan {
+                tool: Tool::WasmSnip.to_string(),
+                version: Some(wasm_snip::WASM_SNIP_VERSION.to_string()),
+                install: install::plan_prebuilt_or_cargo_install(
+                    Tool::WasmSnip,
+                    &build.cache,
+                    wasm_snip::WASM_SNIP_VERSION,
+                    build.mode.install_permitted(),
+                )?,
+            });
+        }
+        Ok(self.wasm_snip.as_ref().unwrap())
+    }
+
+    fn wasm_opt(&mut self, build: &Build) -> &ToolPlan {
+        self.wasm_opt.get_or_insert_with(|| ToolPlan {
+            tool: Tool::WasmOpt.to_string(),
+            version: Some(wasm_opt::WASM_OPT_VERSION.to_string()),
+            install: wasm_opt::plan(&build.cache, build.mode.install_permitted()),
+        })
+    }
+}
+
+fn target_profile(build: &Build, crate_data: &CrateData, target: Target) -> CargoWasmPackProfile {
+    crate_data.configured_target_profile(&build.profile, target)
+}
+
+/// The note that `what` applies to the profile, or to `target` of it when
+/// there are several targets.
+fn for_target(build: &Build, target: Target, what: &str) -> String {
+    if build.targets.len() > 1 {
+        format!("{} for {} with this profile", what, target)
+    } else {
+        format!("{} for this profile", what)
+    }
+}
+
+/// Add the commands of the crate's hooks of `point` to `step`.
+fn add_hooks(step: &mut StepPlan, crate_data: &CrateData, point: HookPoint) {
+    for line in crate_data.hooks().commands(point) {
+        let cmd = hooks::command(line, crate_data.manifest_dir());
+        step.commands.push((&cmd).into());
+    }
+}
+
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Build plan ({} profile, {} mode, targets: {})",
+            self.profile,
+            self.mode,
+            self.targets.join(", ")
+        )?;
+        if !self.tools.is_empty() {
+            writeln!(f, "\nTools:")?;
+            for tool in &self.tools {
+                match &tool.version {
+                    Some(version) => writeln!(f, "  {} {}: {}", tool.tool, version, tool.install)?,
+                    None => writeln!(f, "  {}: {}", tool.tool, tool.install)?,
+                }
+            }
+        }
+        writeln!(f, "\nSteps:")?;
+        for (i, step) in self.steps.iter().enumerate() {
+            match &step.package {
+                Some(package) => writeln!(f, "  {}. {} ({})", i + 1, step.step, package)?,
+                None => writeln!(f, "  {}. {}", i + 1, step.step)?,
+            }
+            for command in &step.commands {
+                let args = command
+                    .args
+                    .iter()
+                    .map(|arg| {
+                        if arg.is_empty() || arg.contains(char::is_whitespace) {
+                            format!("{:?}", arg)
+                        } else {
+                            arg.clone()
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                let env = command
+                    .env
+                    .iter()
+                    .map(|(key, value)| format!("{}={} ", key, value))
+                    .collect::<String>();
+                write!(f, "       $ {}{}", env, args.join(" "))?;
+                match &command.cwd {
+                    Some(cwd) => writeln!(f, "  (in {})", cwd.display())?,
+                    None => writeln!(f)?,
+                }
+            }
+            for path in &step.writes {
+                writeln!(f, "       writes {}", path.display())?;
+            }
+            for note in &step.notes {
+                writeln!(f, "       {}", note)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -2,7 +2,10 @@
 
 use console::style;
 use emoji;
+use serde_json;
+use std::path::Path;
 use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::time::Duration;
 
 #[repr(u8)]
 #[derive(Debug, Clone, Copy)]
@@ -30,10 +33,111 @@ impl std::str::FromStr for LogLevel {
     }
 }
 
+/// How wasm-pack reports progress and results.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MessageFormat {
+    /// Human readable messages on stderr.
+    Human,
+    /// One JSON event per line on stdout.
+    Json,
+}
+
+impl std::str::FromStr for MessageFormat {
+    type Err = failure::Error;
+    fn from_str(s: &str) -> Result<Self, failure::Error> {
+        match s {
+            "human" => Ok(MessageFormat::Human),
+            "json" => Ok(MessageFormat::Json),
+            _ => bail!("Unknown message-format: {}", s),
+        }
+    }
+}
+
+/// The kinds of files a build produces.
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ArtifactKind {
+    /// The `.wasm` binary.
+    Wasm,
+    /// Generated JavaScript glue.
+    Js,
+    /// Generated TypeScript declarations.
+    Dts,
+    /// The generated `package.json`.
+    PackageJson,
+    /// The generated `mod.ts` of a Deno module.
+    Ts,
+    /// The generated `deno.json`.
+    DenoJson,
+}
+
+/// A machine-readable event, printed as a line of JSON when the message
+/// format is `json`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Event<'a> {
+    /// A step of the running command started.
+    StepStarted {
+        /// The name of the step.
+        step: &'a str,
+    },
+    /// A step of the running command finished.
+    StepFinished {
+        /// The name of the step.
+        step: &'a str,
+        /// Whether the step succeeded.
+        success: bool,
+        /// How long the step took, in milliseconds.
+        duration_ms: u64,
+    },
+    /// A message that would have been printed to a human.
+    Message {
+        /// One of `info`, `warn` or `error`.
+        level: &'a str,
+        /// The message text.
+        message: &'a str,
+    },
+    /// A file was produced.
+    Artifact {
+        /// What kind of file was produced.
+        kind: ArtifactKind,
+        /// Where the file was written to.
+        path: &'a Path,
+    },
+    /// The plan of `wasm-pack build --dry-run`, a serialized `plan::Plan`.
+    Plan(serde_json::Value),
+    /// The tools in wasm-pack's cache, as listed by `wasm-pack tools list`.
+    Tools {
+        /// The tools, serialized `command::tools::CachedTool`s.
+        tools: serde_json::Value,
+    },
+    /// The report of `wasm-pack doctor`.
+    Doctor {
+        /// The checks that were run, serialized `command::doctor::Check`s.
+        checks: serde_json::Value,
+    },
+    /// The command finished.
+    Finished {
+        /// Whether the command succeeded.
+        success: bool,
+        /// How long the command took, in milliseconds.
+        duration_ms: u64,
+        /// The error the command failed with, if any.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+}
+
+/// Convert a `Duration` to the milliseconds reported in events.
+pub fn duration_ms(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + u64::from(duration.subsec_millis())
+}
+
 /// Synchronized progress bar and status message printing.
 pub struct ProgressOutput {
     quiet: AtomicBool,
     log_level: AtomicU8,
+    json: AtomicBool,
 }
 
 impl ProgressOutput {
@@ -42,6 +146,7 @@ impl ProgressOutput {
         Self {
             quiet: AtomicBool::new(false),
             log_level: AtomicU8::new(LogLevel::Info as u8),
+            json: AtomicBool::new(false),
         }
     }
 
@@ -50,6 +155,26 @@ impl ProgressOutput {
         eprintln!("{}", message);
     }
 
+    /// Returns whether messages are reported as JSON events
+    pub fn json(&self) -> bool {
+        self.json.load(Ordering::SeqCst)
+    }
+
+    /// Sets how messages are reported
+    pub fn set_message_format(&self, format: MessageFormat) {
+        self.json
+            .store(format == MessageFormat::Json, Ordering::SeqCst);
+    }
+
+    /// Report an event. Does nothing unless the message format is `json`.
+    pub fn event(&self, event: &Event) {
+        if self.json() {
+            if let Ok(line) = serde_json::to_string(event) {
+                println!("{}", line);
+            }
+        }
+    }
+
     /// Returns whether it should silence stdout or not
     pub fn quiet(&self) -> bool {
         self.quiet.load(Ordering::SeqCst)
@@ -73,6 +198,12 @@ impl ProgressOutput {
     /// Add an informational message.
     pub fn info(&self, message: &str) {
         if !self.quiet() && self.is_log_enabled(LogLevel::Info) {
+            if self.json() {
+                return self.event(&Event::Message {
+                    level: "info",
+                    message,
+                });
+            }
             let info = format!("{}: {}", style("[INFO]").bold().dim(), message,);
             self.message(&info);
         }
@@ -81,6 +212,12 @@ impl ProgressOutput {
     /// Add a warning message.
     pub fn warn(&self, message: &str) {
         if !self.quiet() && self.is_log_enabled(LogLevel::Warn) {
+            if self.json() {
+                return self.event(&Event::Message {
+                    level: "warn",
+                    message,
+                });
+            }
             let warn = format!(
                 "{}: {} {}",
                 style("[WARN]").bold().dim(),
@@ -94,6 +231,12 @@ impl ProgressOutput {
     /// Add an error message.
     pub fn error(&self, message: &str) {
         if self.is_log_enabled(LogLevel::Error) {
+            if self.json() {
+                return self.event(&Event::Message {
+                    level: "error",
+                    message,
+                });
+            }
             let err = format!(
                 "{}: {} {}",
                 style("[ERR]").bold().dim(),
@@ -0,0 +1,640 @@
+//! Size reports for the `.wasm` binaries that a build produces.
+//!
+//! The report breaks a binary down into its sections and its largest
+//! functions, named through the `name` custom section where there is one.
+
+use brotli;
+use failure::{self, ResultExt};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{self, Deserialize};
+use serde_json;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// The name of the JSON file that `--size-report` writes into the out dir.
+pub const SIZE_REPORT_FILE: &str = "size-report.json";
+
+/// How many of the largest functions a report lists.
+const LARGEST_FUNCTIONS: usize = 10;
+
+/// How hard to compress a binary to measure its gzip and brotli sizes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Quality {
+    /// The sizes that a server compressing on the fly would roughly serve,
+    /// at a fraction of the time.
+    Fast,
+    /// The smallest sizes that gzip and brotli can reach, as when a package is
+    /// compressed ahead of time. This is slow for large binaries.
+    Best,
+}
+
+/// The size report of every `.wasm` file of a package.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct SizeReport {
+    /// One report per `.wasm` file.
+    pub files: Vec<FileSize>,
+}
+
+/// The size of a single `.wasm` file.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FileSize {
+    /// The path of the file, relative to the out dir.
+    pub path: String,
+    /// The size of the file in bytes.
+    pub size: u64,
+    /// The size of the file after gzip compression.
+    pub gzip_size: u64,
+    /// The size of the file after brotli compression.
+    pub brotli_size: u64,
+    /// The size of every section, including its header, in file order.
+    pub sections: Vec<SectionSize>,
+    /// The largest functions by body size, largest first.
+    pub largest_functions: Vec<FunctionSize>,
+}
+
+/// The size of a section of a `.wasm` file.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SectionSize {
+    /// The name of the section, e.g. `code`, or the name of a custom section.
+    pub name: String,
+    /// Whether this is a custom section.
+    #[serde(default)]
+    pub custom: bool,
+    /// The size of the section in bytes.
+    pub size: u64,
+}
+
+impl SectionSize {
+    fn display_name(&self) -> String {
+        if self.custom {
+            format!("custom \"{}\"", self.name)
+        } else {
+            self.name.clone()
+        }
+    }
+}
+
+/// The size of a function body.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FunctionSize {
+    /// The name of the function, or `func[<index>]` if it has none.
+    pub name: String,
+    /// The size of the function's body in bytes.
+    pub size: u64,
+}
+
+/// Limits on the size of each `.wasm` file, configured with the `size-budget`
+/// key of a profile.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct SizeBudget {
+    /// The limit on the uncompressed size.
+    #[serde(default, deserialize_with = "deserialize_size")]
+    pub raw: Option<u64>,
+    /// The limit on the gzipped size.
+    #[serde(default, deserialize_with = "deserialize_size")]
+    pub gzip: Option<u64>,
+}
+
+impl SizeBudget {
+    /// Check every file of `report` against the budget, failing with a list of
+    /// the files that exceed it.
+    pub fn check(&self, report: &SizeReport) -> Result<(), failure::Error> {
+        self.check_files(report.files.iter())
+    }
+
+    /// Check the given files of a report against the budget, like `check`.
+    pub fn check_files<'a, I>(&self, files: I) -> Result<(), failure::Error>
+    where
+        I: IntoIterator<Item = &'a FileSize>,
+    {
+        let mut exceeded = Vec::new();
+        for file in files {
+            let limits = [
+                ("raw", self.raw, file.size),
+                ("gzip", self.gzip, file.gzip_size),
+            ];
+            for &(kind, limit, size) in &limits {
+                if let Some(limit) = limit {
+                    if size > limit {
+                        exceeded.push(format!(
+                            "{} is {} {}, which exceeds the budget of {} by {}",
+                            file.path,
+                            human_size(size),
+                            kind,
+                            human_size(limit),
+                            human_size(size - limit),
+                        ));
+                    }
+                }
+            }
+        }
+        if !exceeded.is_empty() {
+            bail!(
+                "The size budget of the wasm was exceeded:\n  {}",
+                exceeded.join("\n  ")
+            );
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SizeBudget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let limits: Vec<_> = [("raw", self.raw), ("gzip", self.gzip)]
+            .iter()
+            .filter_map(|&(kind, limit)| limit.map(|l| format!("{} {}", human_size(l), kind)))
+            .collect();
+        write!(f, "{}", limits.join(", "))
+    }
+}
+
+/// Sizes are either a number of bytes or a string with a unit, e.g. `"200 KiB"`.
+fn deserialize_size<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Size {
+        Bytes(u64),
+        WithUnit(String),
+    }
+    match Size::deserialize(deserializer)? {
+        Size::Bytes(bytes) => Ok(Some(bytes)),
+        Size::WithUnit(s) => parse_size(&s).map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+/// Parse a size such as `512`, `200 KiB` or `1.5MB`.
+pub fn parse_size(s: &str) -> Result<u64, failure::Error> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format_err!("invalid size `{}`", s))?;
+    let multiplier = match unit.trim() {
+        "" | "B" => 1.0,
+        "KB" | "kB" => 1000.0,
+        "KiB" => 1024.0,
+        "MB" => 1000.0 * 1000.0,
+        "MiB" => 1024.0 * 1024.0,
+        unit => bail!(
+            "unknown unit `{}` in size `{}`, expected one of B, KB, KiB, MB or MiB",
+            unit,
+            s
+        ),
+    };
+    Ok((number * multiplier).round() as u64)
+}
+
+impl SizeReport {
+    /// Measure the `.wasm` files at `paths`, which are relative to `out_dir`.
+    pub fn new(
+        out_dir: &Path,
+        paths: &[String],
+        quality: Quality,
+    ) -> Result<SizeReport, failure::Error> {
+        let mut files = Vec::new();
+        for path in paths {
+            let full_path = out_dir.join(path);
+            let wasm = fs::read(&full_path)
+                .with_context(|_| format!("failed to read: {}", full_path.display()))?;
+            let mut file = FileSize::measure(&wasm, quality)
+                .with_context(|_| format!("failed to parse: {}", full_path.display()))?;
+            file.path = path.clone();
+            files.push(file);
+        }
+        Ok(SizeReport { files })
+    }
+
+    /// Get the report to compare a build against from `path`. That's either a
+    /// report written by `--size-report`, or a package directory, whose
+    /// report is used if it has one, or else whose `.wasm` files are measured.
+    pub fn for_comparison(path: &Path) -> Result<SizeReport, failure::Error> {
+        if !path.is_dir() {
+            return SizeReport::load(path);
+        }
+        if path.join(SIZE_REPORT_FILE).is_file() {
+            return SizeReport::load(&path.join(SIZE_REPORT_FILE));
+        }
+        let mut paths: Vec<String> = WalkDir::new(path)
+            .max_depth(2)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().ends_with("_bg.wasm"))
+            .filter_map(|e| {
+                e.path()
+                    .strip_prefix(path)
+                    .ok()
+                    .map(|p| p.to_string_lossy().into_owned())
+            })
+            .collect();
+        if paths.is_empty() {
+            bail!("{} contains no wasm files to compare with", path.display());
+        }
+        paths.sort();
+        SizeReport::new(path, &paths, Quality::Best)
+    }
+
+    /// Describe how the sizes changed from `old` to `self`, per file and per
+    /// section.
+    pub fn compare(&self, old: &SizeReport) -> String {
+        let mut lines = Vec::new();
+        for file in &self.files {
+            let old_file = match old.files.iter().find(|f| f.path == file.path) {
+                Some(old_file) => old_file,
+                None => {
+                    lines.push(format!("{}: {} (new)", file.path, human_size(file.size)));
+                    continue;
+                }
+            };
+            lines.push(format!(
+                "{}: {} ({}), gzip: {} ({}), brotli: {} ({})",
+                file.path,
+                human_size(file.size),
+                human_delta(old_file.size, file.size),
+                human_size(file.gzip_size),
+                human_delta(old_file.gzip_size, file.gzip_size),
+                human_size(file.brotli_size),
+                human_delta(old_file.brotli_size, file.brotli_size),
+            ));
+            let section_size = |file: &FileSize, section: &SectionSize| {
+                file.sections
+                    .iter()
+                    .find(|s| s.name == section.name && s.custom == section.custom)
+                    .map_or(0, |s| s.size)
+            };
+            let removed = old_file.sections.iter().filter(|old_section| {
+                !file
+                    .sections
+                    .iter()
+                    .any(|s| s.name == old_section.name && s.custom == old_section.custom)
+            });
+            for section in file.sections.iter().chain(removed) {
+                let old_size = section_size(old_file, section);
+                let new_size = section_size(file, section);
+                if old_size != new_size {
+                    lines.push(format!(
+                        "  {:>12}  {}",
+                        human_delta(old_size, new_size),
+                        section.display_name()
+                    ));
+                }
+            }
+        }
+        for old_file in &old.files {
+            if !self.files.iter().any(|f| f.path == old_file.path) {
+                lines.push(format!("{}: removed", old_file.path));
+            }
+        }
+        lines.join("\n")
+    }
+
+    /// Read a report that was previously written to `path`.
+    pub fn load(path: &Path) -> Result<SizeReport, failure::Error> {
+        let json = fs::read_to_string(path)
+            .with_context(|_| format!("failed to read: {}", path.display()))?;
+        let report = serde_json::from_str(&json)
+            .with_context(|_| format!("failed to parse: {}", path.display()))?;
+        Ok(report)
+    }
+
+    /// Write the report to `SIZE_REPORT_FILE` in `out_dir`.
+    pub fn save(&self, out_dir: &Path) -> Result<(), failure::Error> {
+        let path = out_dir.join(SIZE_REPORT_FILE);
+        let json = serde_json::to_string_pretty(self).context("JSON serialization failed")?;
+        fs::write(&path, json).with_context(|_| format!("failed to write: {}", path.display()))?;
+        Ok(())
+    }
+}
+
+impl FileSize {
+    /// Measure the given `.wasm` binary. The `path` of the result is empty.
+    pub fn new(wasm: &[u8]) -> Result<FileSize, failure::Error> {
+        FileSize::measure(wasm, Quality::Best)
+    }
+
+    /// Like `FileSize::new`, but compress the binary at the given quality.
+    pub fn measure(wasm: &[u8], quality: Quality) -> Result<FileSize, failure::Error> {
+        let module = Module::parse(wasm)?;
+
+        let mut largest_functions: Vec<_> = module
+            .function_sizes
+            .iter()
+            .enumerate()
+            .map(|(i, &size)| {
+                let index = module.imported_functions + i as u32;
+                let name = module
+                    .function_names
+                    .get(&index)
+                    .cloned()
+                    .unwrap_or_else(|| format!("func[{}]", index));
+                FunctionSize { name, size }
+            })
+            .collect();
+        largest_functions.sort_by_key(|f| Reverse(f.size));
+        largest_functions.truncate(LARGEST_FUNCTIONS);
+
+        Ok(FileSize {
+            path: String::new(),
+            size: wasm.len() as u64,
+            gzip_size: gzip_size(wasm, quality)?,
+            brotli_size: brotli_size(wasm, quality)?,
+            sections: module.sections,
+            largest_functions,
+        })
+    }
+}
+
+fn gzip_size(data: &[u8], quality: Quality) -> Result<u64, failure::Error> {
+    let level = match quality {
+        Quality::Fast => Compression::fast(),
+        Quality::Best => Compression::best(),
+    };
+    let mut encoder = GzEncoder::new(Vec::new(), level);
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?.len() as u64)
+}
+
+fn brotli_size(data: &[u8], quality: Quality) -> Result<u64, failure::Error> {
+    let level = match quality {
+        Quality::Fast => 4,
+        Quality::Best => 11,
+    };
+    let mut compressed = Vec::new();
+    {
+        let mut encoder = brotli::CompressorWriter::new(&mut compressed, 4096, level, 22);
+        encoder.write_all(data)?;
+    }
+    Ok(compressed.len() as u64)
+}
+
+/// Render the change from `old` to `new` bytes for humans, e.g. `+1.20 KiB`.
+pub fn human_delta(old: u64, new: u64) -> String {
+    if new >= old {
+        format!("+{}", human_size(new - old))
+    } else {
+        format!("-{}", human_size(old - new))
+    }
+}
+
+/// Render a number of bytes for humans, e.g. `12.34 KiB`.
+pub fn human_size(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.2} KiB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.2} MiB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+impl fmt::Display for FileSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: {} (gzip: {}, brotli: {})",
+            self.path,
+            human_size(self.size),
+            human_size(self.gzip_size),
+            human_size(self.brotli_size)
+        )?;
+        writeln!(f, "  Sections:")?;
+        for section in &self.sections {
+            writeln!(
+                f,
+                "    {:>12}  {}",
+                human_size(section.size),
+                section.display_name()
+            )?;
+        }
+        if !self.largest_functions.is_empty() {
+            write!(f, "  Largest functions:")?;
+            for function in &self.largest_functions {
+                write!(
+                    f,
+                    "\n    {:>12}  {}",
+                    human_size(function.size),
+                    function.name
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The parts of a wasm module that a size report needs.
+struct Module {
+    sections: Vec<SectionSize>,
+    imported_functions: u32,
+    function_sizes: Vec<u64>,
+    function_names: BTreeMap<u32, String>,
+}
+
+impl Module {
+    fn parse(wasm: &[u8]) -> Result<Module, failure::Error> {
+        if wasm.len() < 8 || &wasm[..4] != b"\0asm" {
+            bail!("not a wasm binary");
+        }
+        let mut module = Module {
+            sections: Vec::new(),
+            imported_functions: 0,
+            function_sizes: Vec::new(),
+            function_names: BTreeMap::new(),
+        };
+
+        let mut reader = Reader::new(&wasm[8..]);
+        while !reader.is_empty() {
+            let start = reader.pos;
+            let id = reader.byte()?;
+            let len = reader.u32()? as usize;
+            let mut payload = Reader::new(reader.bytes(len)?);
+            let size = (reader.pos - start) as u64;
+
+            let name = match id {
+                0 => {
+                    let name = payload.name()?;
+                    if name == "name" {
+                        // A malformed name section only costs us the names.
+                        let _ = module.parse_names(&mut payload);
+                    }
+                    module.sections.push(SectionSize {
+                        name,
+                        custom: true,
+                        size,
+                    });
+                    continue;
+                }
+                1 => "type",
+                2 => {
+                    module.parse_imports(&mut payload)?;
+                    "import"
+                }
+                3 => "function",
+                4 => "table",
+                5 => "memory",
+                6 => "global",
+                7 => "export",
+                8 => "start",
+                9 => "element",
+                10 => {
+                    module.parse_code(&mut payload)?;
+                    "code"
+                }
+                11 => "data",
+                12 => "datacount",
+                _ => "unknown",
+            };
+            module.sections.push(SectionSize {
+                name: name.to_string(),
+                custom: false,
+                size,
+            });
+        }
+        Ok(module)
+    }
+
+    fn parse_imports(&mut self, reader: &mut Reader) -> Result<(), failure::Error> {
+        for _ in 0..reader.u32()? {
+            reader.name()?;
+            reader.name()?;
+            match reader.byte()? {
+                // function: type index
+                0x00 => {
+                    reader.u32()?;
+                    self.imported_functions += 1;
+                }
+                // table: element type and limits
+                0x01 => {
+                    reader.byte()?;
+                    reader.limits()?;
+                }
+                // memory: limits
+                0x02 => reader.limits()?,
+                // global: value type and mutability
+                0x03 => {
+                    reader.byte()?;
+                    reader.byte()?;
+                }
+                // tag: attribute and type index
+                0x04 => {
+                    reader.byte()?;
+                    reader.u32()?;
+                }
+                kind => bail!("unknown import kind {}", kind),
+            }
+        }
+        Ok(())
+    }
+
+    fn parse_code(&mut self, reader: &mut Reader) -> Result<(), failure::Error> {
+        for _ in 0..reader.u32()? {
+            let size = reader.u32()?;
+            reader.bytes(size as usize)?;
+            self.function_sizes.push(u64::from(size));
+        }
+        Ok(())
+    }
+
+    fn parse_names(&mut self, reader: &mut Reader) -> Result<(), failure::Error> {
+        while !reader.is_empty() {
+            let id = reader.byte()?;
+            let len = reader.u32()? as usize;
+            let mut subsection = Reader::new(reader.bytes(len)?);
+            // Subsection 1 holds the function names.
+            if id == 1 {
+                for _ in 0..subsection.u32()? {
+                    let index = subsection.u32()?;
+                    let name = subsection.name()?;
+                    self.function_names.insert(index, name);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads the primitives of the wasm binary format.
+pub struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// Read `data` from its start.
+    pub fn new(data: &'a [u8]) -> Reader<'a> {
+        Reader { data, pos: 0 }
+    }
+
+    /// How many bytes have been read so far.
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    /// Whether everything has been read.
+    pub fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+
+    /// Read a single byte.
+    pub fn byte(&mut self) -> Result<u8, failure::Error> {
+        match self.data.get(self.pos) {
+            Some(&b) => {
+                self.pos += 1;
+                Ok(b)
+            }
+            None => bail!("unexpected end of wasm binary"),
+        }
+    }
+
+    /// Read `len` bytes.
+    pub fn bytes(&mut self, len: usize) -> Result<&'a [u8], failure::Error> {
+        if self.data.len() - self.pos < len {
+            bail!("unexpected end of wasm binary");
+        }
+        let bytes = &self.data[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    /// Read an unsigned LEB128 encoded integer.
+    pub fn u32(&mut self) -> Result<u32, failure::Error> {
+        let mut result = 0u32;
+        let mut shift = 0;
+        loop {
+            let byte = self.byte()?;
+            if shift >= 32 {
+                bail!("integer too large in wasm binary");
+            }
+            result |= u32::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+            shift += 7;
+        }
+    }
+
+    /// Read a length-prefixed UTF-8 name.
+    pub fn name(&mut self) -> Result<String, failure::Error> {
+        let len = self.u32()? as usize;
+        Ok(String::from_utf8_lossy(self.bytes(len)?).into_owned())
+    }
+
+    fn limits(&mut self) -> Result<(), failure::Error> {
+        let flags = self.byte()?;
+        self.u32()?;
+        if flags & 0x01 != 0 {
+            self.u32()?;
+        }
+        Ok(())
+    }
+}
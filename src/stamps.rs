@@ -1,7 +1,52 @@
-//! Key-value store in `*.stamps` file.
+//! Key-value store in wasm-pack's state file.
+//!
+//! The state file is `state.json` in wasm-pack's cache directory, or the file
+//! that `WASM_PACK_STATE` points to. It keeps what wasm-pack remembers
+//! between runs, such as the versions of the webdrivers it looked up and when
+//! it last checked for a new version of itself.
+//!
+//! Older versions of wasm-pack kept these next to their executable, in a
+//! `*.stamps` JSON file and a `*.stamp` text file. Their values are migrated
+//! into the state file.
 
+use cache;
 use failure::{self, ResultExt};
-use std::{env, fs, path::PathBuf};
+use lock::{self, FileLock};
+use log::info;
+use serde_json;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::{env, fs};
+
+/// The environment variable with the path of wasm-pack's state file.
+pub const STATE_ENV_VAR: &str = "WASM_PACK_STATE";
+
+/// The version of the format of the state file that this wasm-pack writes.
+pub const STATE_VERSION: u32 = 1;
+
+/// The stamp with the latest version of wasm-pack on crates.io.
+pub const WASM_PACK_VERSION_STAMP: &str = "wasm-pack version";
+
+/// The stamp with when wasm-pack last looked up its latest version, in RFC
+/// 3339 format.
+pub const WASM_PACK_VERSION_CHECKED_STAMP: &str = "wasm-pack version checked";
+
+/// The contents of the state file.
+#[derive(Debug, Deserialize, Serialize)]
+struct State {
+    version: u32,
+    #[serde(default)]
+    stamps: BTreeMap<String, String>,
+}
+
+impl Default for State {
+    fn default() -> State {
+        State {
+            version: STATE_VERSION,
+            stamps: BTreeMap::new(),
+        }
+    }
+}
 
 /// Get a value corresponding to the key from the JSON value.
 ///
@@ -18,43 +63,149 @@ pub fn get_stamp_value(
 }
 
 /// Save the key-value pair to the store.
+///
+/// Other wasm-pack processes wait to save their values until this one is
+/// done, so that none of the values are lost.
 pub fn save_stamp_value(
     key: impl Into<String>,
     value: impl AsRef<str>,
 ) -> Result<(), failure::Error> {
-    let mut json = read_stamps_file_to_json().unwrap_or_else(|_| serde_json::Map::new().into());
-
-    {
-        let stamps = json
-            .as_object_mut()
-            .ok_or_else(|| failure::err_msg("stamps file doesn't contain JSON object"))?;
-        stamps.insert(key.into(), value.as_ref().into());
+    let path = get_stamps_file_path()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|_| format!("cannot create the directory of {}", path.display()))?;
     }
-
-    write_to_stamps_file(json)
+    let _lock = FileLock::acquire(&path.with_extension("lock"), None)?;
+    let mut state = match read_state() {
+        Ok(state) => state,
+        // Never overwrite the state of a newer wasm-pack.
+        Err(e) if newer_version(&path).is_some() => return Err(e),
+        Err(e) => {
+            info!("starting a new state file, because {}", e);
+            State::default()
+        }
+    };
+    state.stamps.insert(key.into(), value.as_ref().to_owned());
+    write_state(&state)?;
+    remove_legacy_files();
+    Ok(())
 }
 
-/// Get the path of the `*.stamps` file that is used as the store.
+/// Get the path of the state file that is used as the store.
 pub fn get_stamps_file_path() -> Result<PathBuf, failure::Error> {
-    let path = env::current_exe()
-        .map(|path| path.with_extension("stamps"))
-        .context("cannot get stamps file path")?;
-    Ok(path)
+    match env::var_os(STATE_ENV_VAR) {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => Ok(cache::get_wasm_pack_cache()
+            .context("cannot get stamps file path")?
+            .join("state.json".as_ref())),
+    }
 }
 
-/// Read `*.stamps` file and convert its content to the JSON value.
+/// Read the stamps of the state file and convert them to a JSON object.
 pub fn read_stamps_file_to_json() -> Result<serde_json::Value, failure::Error> {
-    let stamps_file_path = get_stamps_file_path()?;
-    let stamps_file_content =
-        fs::read_to_string(stamps_file_path).context("cannot find or read stamps file")?;
-    let json: serde_json::Value = serde_json::from_str(&stamps_file_content)
-        .context("stamps file doesn't contain valid JSON")?;
-    Ok(json)
+    let state = read_state()?;
+    Ok(serde_json::to_value(state.stamps)?)
 }
 
-fn write_to_stamps_file(json: serde_json::Value) -> Result<(), failure::Error> {
+/// Read the state file, or else migrate the files of older versions of
+/// wasm-pack. The migrated state is only written by the next save.
+fn read_state() -> Result<State, failure::Error> {
+    let path = get_stamps_file_path()?;
+    if !path.is_file() {
+        return migrate_legacy_files()
+            .ok_or_else(|| failure::err_msg("cannot find or read stamps file"));
+    }
+    if let Some(version) = newer_version(&path) {
+        bail!(
+            "{} has version {} of wasm-pack's state, but this wasm-pack only understands \
+             version {}. Upgrade wasm-pack, or point {} at another file.",
+            path.display(),
+            version,
+            STATE_VERSION,
+            STATE_ENV_VAR
+        );
+    }
+    let contents = fs::read_to_string(&path).context("cannot find or read stamps file")?;
+    let state: State =
+        serde_json::from_str(&contents).context("stamps file doesn't contain valid JSON")?;
+    Ok(state)
+}
+
+/// The version of the state file at `path`, if it is newer than this
+/// wasm-pack understands.
+fn newer_version(path: &Path) -> Option<u64> {
+    let contents = fs::read_to_string(path).ok()?;
+    let json: serde_json::Value = serde_json::from_str(&contents).ok()?;
+    let version = json.get("version")?.as_u64()?;
+    if version > u64::from(STATE_VERSION) {
+        Some(version)
+    } else {
+        None
+    }
+}
+
+fn write_state(state: &State) -> Result<(), failure::Error> {
     let stamps_file_path = get_stamps_file_path()?;
-    let pretty_json = serde_json::to_string_pretty(&json).context("JSON serialization failed")?;
-    fs::write(stamps_file_path, pretty_json).context("cannot write to stamps file")?;
+    let pretty_json = serde_json::to_string_pretty(state).context("JSON serialization failed")?;
+    lock::write_atomically(&stamps_file_path, pretty_json.as_bytes())
+        .context("cannot write to stamps file")?;
     Ok(())
 }
+
+/// The `*.stamps` and `*.stamp` files that older versions of wasm-pack kept
+/// next to their executable.
+fn legacy_paths() -> Option<(PathBuf, PathBuf)> {
+    let exe = env::current_exe().ok()?;
+    Some((exe.with_extension("stamps"), exe.with_extension("stamp")))
+}
+
+/// The state in the files of older versions of wasm-pack, if there are any.
+fn migrate_legacy_files() -> Option<State> {
+    let (stamps_path, stamp_path) = legacy_paths()?;
+    let stamps = fs::read_to_string(&stamps_path).ok();
+    let stamp = fs::read_to_string(&stamp_path).ok();
+    if stamps.is_none() && stamp.is_none() {
+        return None;
+    }
+    info!(
+        "migrating {} and {} into wasm-pack's state file",
+        stamps_path.display(),
+        stamp_path.display()
+    );
+
+    let mut state = State::default();
+    let json = stamps.and_then(|stamps| serde_json::from_str::<serde_json::Value>(&stamps).ok());
+    if let Some(stamps) = json.as_ref().and_then(|json| json.as_object()) {
+        for (key, value) in stamps {
+            if let Some(value) = value.as_str() {
+                state.stamps.insert(key.clone(), value.to_owned());
+            }
+        }
+    }
+    // The `*.stamp` file has lines of `<key> <value>`.
+    for line in stamp.as_ref().map_or("", String::as_str).lines() {
+        let mut words = line.split_whitespace();
+        let key = match words.next() {
+            Some("created") => WASM_PACK_VERSION_CHECKED_STAMP,
+            Some("version") => WASM_PACK_VERSION_STAMP,
+            _ => continue,
+        };
+        if let Some(value) = words.next() {
+            state.stamps.insert(key.to_owned(), value.to_owned());
+        }
+    }
+    Some(state)
+}
+
+/// Remove the files of older versions of wasm-pack, now that their values
+/// are in the state file. They stay if the executable's directory is
+/// read-only, which is harmless.
+fn remove_legacy_files() {
+    if let Some((stamps_path, stamp_path)) = legacy_paths() {
+        for path in &[stamps_path, stamp_path] {
+            if path.is_file() {
+                drop(fs::remove_file(path));
+            }
+        }
+    }
+}
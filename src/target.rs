@@ -1,7 +1,7 @@
 //! Information about the target wasm-pack is currently being compiled for.
 //!
 //! That is, whether we are building wasm-pack for windows vs linux, and x86 vs
-//! x86-64, etc.
+//! x86-64 vs aarch64, etc.
 
 #![allow(missing_docs)]
 
@@ -13,3 +13,19 @@ pub const WINDOWS: bool = cfg!(target_os = "windows");
 pub const x86_64: bool = cfg!(target_arch = "x86_64");
 #[allow(non_upper_case_globals)]
 pub const x86: bool = cfg!(target_arch = "x86");
+#[allow(non_upper_case_globals)]
+pub const aarch64: bool = cfg!(target_arch = "aarch64");
+
+/// The name of the platform, e.g. `aarch64 Linux`, for messages.
+pub fn platform() -> String {
+    let os = if LINUX {
+        "Linux"
+    } else if MACOS {
+        "macOS"
+    } else if WINDOWS {
+        "Windows"
+    } else {
+        std::env::consts::OS
+    };
+    format!("{} {}", std::env::consts::ARCH, os)
+}
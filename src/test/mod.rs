@@ -9,11 +9,12 @@ use std::ffi::OsStr;
 use std::path::Path;
 use std::process::Command;
 
-/// Run `cargo test` with the `nightly` toolchain and targeting
-/// `wasm32-unknown-unknown`.
+/// Run `cargo test` with the `nightly` toolchain and targeting `rust_target`,
+/// e.g. `wasm32-unknown-unknown`.
 pub fn cargo_test_wasm<I, K, V>(
     path: &Path,
     release: bool,
+    rust_target: &str,
     envs: I,
     extra_options: &[String],
 ) -> Result<(), failure::Error>
@@ -35,7 +36,7 @@ where
         cmd.arg("--release");
     }
 
-    cmd.arg("--target").arg("wasm32-unknown-unknown");
+    cmd.arg("--target").arg(rust_target);
 
     cmd.args(extra_options);
 
@@ -6,6 +6,9 @@ mod safaridriver;
 
 use binary_install::Cache;
 use failure;
+use install::download;
+use install::index::{self, Source};
+use offline;
 use std::path::PathBuf;
 use PBAR;
 
@@ -21,18 +24,26 @@ fn get_and_notify(
     cache: &Cache,
     installation_allowed: bool,
     name: &str,
+    version: &str,
     url: &str,
+    digest: Option<&str>,
 ) -> Result<Option<PathBuf>, failure::Error> {
-    if let Some(dl) = cache.download(false, name, &[name], &url)? {
-        return Ok(Some(dl.binary(name)?));
-    }
-    if installation_allowed {
-        PBAR.info(&format!("Getting {}...", name));
-    }
-    match cache.download(installation_allowed, name, &[name], &url)? {
-        Some(dl) => Ok(Some(dl.binary(name)?)),
-        None => Ok(None),
-    }
+    let dl = match cache.download(false, name, &[name], &url)? {
+        Some(dl) => dl,
+        None => {
+            let installation_allowed = installation_allowed && !offline::is_offline();
+            if installation_allowed {
+                PBAR.info(&format!("Getting {}...", name));
+            }
+            match download::download(cache, installation_allowed, name, &[name], &url, digest)? {
+                Some(dl) => dl,
+                None => return Ok(None),
+            }
+        }
+    };
+    let dirname = download::hashed_dirname(url, name);
+    index::record_use(cache, &dirname, name, version, Source::Download, Some(url));
+    Ok(Some(dl.binary(name)?))
 }
 
 struct Collector(Vec<u8>);
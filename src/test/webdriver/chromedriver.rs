@@ -1,8 +1,11 @@
 use super::{get_and_notify, Collector};
 use binary_install::Cache;
+use checksum;
 use chrono::DateTime;
 use failure::{self, ResultExt};
 use install::InstallMode;
+use mirrors;
+use offline;
 use stamps;
 use std::path::PathBuf;
 use target;
@@ -35,33 +38,48 @@ pub fn install_chromedriver(
         "linux64"
     } else if target::MACOS && target::x86_64 {
         "mac64"
+    } else if target::MACOS && target::aarch64 {
+        "mac64_m1"
     } else if target::WINDOWS {
         "win32"
     } else {
-        bail!("chromedriver binaries are unavailable for this target")
+        bail!(
+            "there are no prebuilt chromedriver binaries for {}. Install chromedriver \
+             and put it on the $PATH, or pass its path with `--chromedriver`.",
+            target::platform()
+        )
     };
 
-    let url = get_chromedriver_url(target);
-
-    match get_and_notify(cache, installation_allowed, "chromedriver", &url)? {
+    let version = get_chromedriver_version();
+    let url = assemble_chromedriver_url(&version, target)?;
+    let digest = checksum::expected("chromedriver", &version, target)?;
+
+    match get_and_notify(
+        cache,
+        installation_allowed,
+        "chromedriver",
+        &version,
+        &url,
+        digest.as_deref(),
+    )? {
         Some(path) => Ok(path),
         None => bail!(
             "No cached `chromedriver` binary found, and could not find a global \
              `chromedriver` on the `$PATH`. Not installing `chromedriver` because of noinstall \
-             mode."
+             mode or offline mode."
         ),
     }
 }
 
-/// Get `chromedriver` download URL.
+/// Get the version of `chromedriver` to install.
 ///
 /// _Algorithm_:
 /// 1. Try to open `*.stamps` file and deserialize its content to JSON object.
 /// 2. Try to compare current time with the saved one.
 /// 3. If the saved time is older than 1 day or something failed
 ///    => fetch a new version and save version & time.
+///    When wasm-pack is offline, the saved version is used however old it is.
 /// 4. If everything failed, use the default version.
-/// 5. Return URL.
 ///
 /// _Notes:_
 ///
@@ -71,13 +89,13 @@ pub fn install_chromedriver(
 ///
 /// The official algorithm for `chromedriver` version selection:
 /// https://chromedriver.chromium.org/downloads/version-selection
-fn get_chromedriver_url(target: &str) -> String {
+fn get_chromedriver_version() -> String {
     let fetch_and_save_version =
         || fetch_chromedriver_version().and_then(save_chromedriver_version);
 
-    let chromedriver_version = match stamps::read_stamps_file_to_json() {
+    match stamps::read_stamps_file_to_json() {
         Ok(json) => {
-            if should_load_chromedriver_version_from_stamp(&json) {
+            if offline::is_offline() || should_load_chromedriver_version_from_stamp(&json) {
                 stamps::get_stamp_value(CHROMEDRIVER_VERSION_STAMP, &json)
             } else {
                 fetch_and_save_version()
@@ -93,11 +111,10 @@ fn get_chromedriver_url(target: &str) -> String {
             error
         );
         DEFAULT_CHROMEDRIVER_VERSION.to_owned()
-    });
-    assemble_chromedriver_url(&chromedriver_version, target)
+    })
 }
 
-// ------ `get_chromedriver_url` helpers ------
+// ------ `get_chromedriver_version` helpers ------
 
 fn save_chromedriver_version(version: String) -> Result<String, failure::Error> {
     stamps::save_stamp_value(CHROMEDRIVER_VERSION_STAMP, &version)?;
@@ -123,6 +140,7 @@ fn should_load_chromedriver_version_from_stamp(json: &serde_json::Value) -> bool
 }
 
 fn fetch_chromedriver_version() -> Result<String, failure::Error> {
+    offline::ensure_online("fetch chromedriver's latest version")?;
     let mut handle = curl::easy::Easy2::new(Collector(Vec::new()));
     handle
         .url("https://chromedriver.storage.googleapis.com/LATEST_RELEASE")
@@ -137,10 +155,9 @@ fn fetch_chromedriver_version() -> Result<String, failure::Error> {
     Ok(version)
 }
 
-fn assemble_chromedriver_url(chromedriver_version: &str, target: &str) -> String {
-    format!(
-        "https://chromedriver.storage.googleapis.com/{version}/chromedriver_{target}.zip",
-        version = chromedriver_version,
-        target = target,
-    )
+fn assemble_chromedriver_url(
+    chromedriver_version: &str,
+    target: &str,
+) -> Result<String, failure::Error> {
+    mirrors::url("chromedriver", chromedriver_version, target, "zip")
 }
@@ -1,8 +1,11 @@
 use super::{get_and_notify, Collector};
 use binary_install::Cache;
+use checksum;
 use chrono::DateTime;
 use failure::{self, ResultExt};
 use install::InstallMode;
+use mirrors;
+use offline;
 use stamps;
 use std::path::PathBuf;
 use target;
@@ -46,50 +49,69 @@ pub fn install_geckodriver(
         ("linux32", "tar.gz")
     } else if target::LINUX && target::x86_64 {
         ("linux64", "tar.gz")
-    } else if target::MACOS {
+    } else if target::LINUX && target::aarch64 {
+        ("linux-aarch64", "tar.gz")
+    } else if target::MACOS && target::x86_64 {
         ("macos", "tar.gz")
+    } else if target::MACOS && target::aarch64 {
+        ("macos-aarch64", "tar.gz")
     } else if target::WINDOWS && target::x86 {
         ("win32", "zip")
     } else if target::WINDOWS && target::x86_64 {
         ("win64", "zip")
     } else {
-        bail!("geckodriver binaries are unavailable for this target")
+        bail!(
+            "there are no prebuilt geckodriver binaries for {}. Install geckodriver \
+             and put it on the $PATH, or pass its path with `--geckodriver`.",
+            target::platform()
+        )
     };
 
-    let url = get_geckodriver_url(target, ext);
-
-    match get_and_notify(cache, installation_allowed, "geckodriver", &url)? {
+    let version = get_geckodriver_version();
+    let url = assemble_geckodriver_url(&version, target, ext)?;
+    log::info!("[geckodriver] Fetching geckodriver at {}", url);
+    let digest = checksum::expected("geckodriver", &version, target)?;
+
+    match get_and_notify(
+        cache,
+        installation_allowed,
+        "geckodriver",
+        &version,
+        &url,
+        digest.as_deref(),
+    )? {
         Some(path) => Ok(path),
         None => bail!(
             "No cached `geckodriver` binary found, and could not find a global `geckodriver` \
-             on the `$PATH`. Not installing `geckodriver` because of noinstall mode."
+             on the `$PATH`. Not installing `geckodriver` because of noinstall mode or \
+             offline mode."
         ),
     }
 }
 
-/// Get `geckodriver` download URL.
+/// Get the version of `geckodriver` to install.
 ///
 /// _Algorithm_:
 /// 1. Try to open `*.stamps` file and deserialize its content to JSON object.
 /// 2. Try to compare current time with the saved one.
 /// 3. If the saved time is older than 1 day or something failed
 ///    => fetch a new version and save version & time.
+///    When wasm-pack is offline, the saved version is used however old it is.
 /// 4. If everything failed, use the default version.
-/// 5. Return URL.
 ///
 /// _Notes:_
 ///
 /// It returns the latest one without checking the installed `Firefox` version
 /// - it should be relatively safe because each `geckodriver` supports many `Firefox` versions:
 /// https://firefox-source-docs.mozilla.org/testing/geckodriver/Support.html#supported-platforms
-fn get_geckodriver_url(target: &str, ext: &str) -> String {
+fn get_geckodriver_version() -> String {
     let fetch_and_save_version = || {
         fetch_latest_geckodriver_tag_json()
             .and_then(get_version_from_json)
             .and_then(save_geckodriver_version)
     };
 
-    let geckodriver_version = if target::WINDOWS {
+    if target::WINDOWS {
         log::info!(
             "[geckodriver] Windows detected, holding geckodriver version to {}",
             DEFAULT_WINDOWS_GECKODRIVER_VERSION
@@ -99,7 +121,7 @@ fn get_geckodriver_url(target: &str, ext: &str) -> String {
         log::info!("[geckodriver] Looking up latest version of geckodriver...");
         match stamps::read_stamps_file_to_json() {
             Ok(json) => {
-                if should_load_geckodriver_version_from_stamp(&json) {
+                if offline::is_offline() || should_load_geckodriver_version_from_stamp(&json) {
                     stamps::get_stamp_value(GECKODRIVER_VERSION_STAMP, &json)
                 } else {
                     fetch_and_save_version()
@@ -116,13 +138,10 @@ fn get_geckodriver_url(target: &str, ext: &str) -> String {
             );
             DEFAULT_GECKODRIVER_VERSION.to_owned()
         })
-    };
-    let url = assemble_geckodriver_url(&geckodriver_version, target, ext);
-    log::info!("[geckodriver] Fetching geckodriver at {}", url);
-    url
+    }
 }
 
-// ------ `get_geckodriver_url` helpers  ------
+// ------ `get_geckodriver_version` helpers  ------
 
 fn save_geckodriver_version(version: String) -> Result<String, failure::Error> {
     stamps::save_stamp_value(GECKODRIVER_VERSION_STAMP, &version)?;
@@ -148,6 +167,7 @@ fn should_load_geckodriver_version_from_stamp(json: &serde_json::Value) -> bool
 }
 
 fn fetch_latest_geckodriver_tag_json() -> Result<String, failure::Error> {
+    offline::ensure_online("fetch geckodriver's latest release data")?;
     let mut headers = curl::easy::List::new();
     headers
         .append("Accept: application/json")
@@ -186,11 +206,6 @@ fn get_version_from_json(json: impl AsRef<str>) -> Result<String, failure::Error
         })
 }
 
-fn assemble_geckodriver_url(tag: &str, target: &str, ext: &str) -> String {
-    format!(
-        "https://github.com/mozilla/geckodriver/releases/download/{tag}/geckodriver-{tag}-{target}.{ext}",
-        tag=tag,
-        target=target,
-        ext=ext,
-    )
+fn assemble_geckodriver_url(tag: &str, target: &str, ext: &str) -> Result<String, failure::Error> {
+    mirrors::url("geckodriver", tag, target, ext)
 }
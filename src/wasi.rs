@@ -0,0 +1,110 @@
+//! Packaging binaries compiled to WASI, and running them with Node.js's
+//! `node:wasi` module.
+
+use build;
+use command::build::{BuildProfile, Target};
+use failure::{Error, ResultExt};
+use manifest::CrateData;
+use serde_json;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A Node.js script that runs the WASI binary at `WASM_PATH` with the
+/// arguments `WASI_ARGS`, giving it access to the current directory.
+const NODE_SCRIPT: &str = r#"#!/usr/bin/env node
+"use strict";
+
+const { readFileSync } = require("fs");
+const { join } = require("path");
+const { WASI } = require("wasi");
+
+const wasi = new WASI({
+  version: "preview1",
+  args: WASI_ARGS,
+  env: process.env,
+  preopens: { ".": process.cwd() },
+  returnOnExit: true,
+});
+const wasm = new WebAssembly.Module(readFileSync(WASM_PATH));
+const instance = new WebAssembly.Instance(wasm, {
+  wasi_snapshot_preview1: wasi.wasiImport,
+});
+process.exitCode = wasi.start(instance);
+"#;
+
+/// The name of the file in a package that holds the binary `name`.
+pub fn wasm_file(name: &str) -> String {
+    format!("{}.wasm", name)
+}
+
+/// The name of the file in a package that launches the binary `name`.
+pub fn launcher_file(name: &str) -> String {
+    format!("{}.js", name)
+}
+
+/// The paths of the `.wasm` files that `cargo build` produces for the crate's
+/// binaries with the given profile.
+pub fn artifact_paths(data: &CrateData, profile: &BuildProfile) -> Vec<PathBuf> {
+    let dir = build::artifact_dir(data, Target::Wasi.rust_target(), profile);
+    data.bin_names()
+        .iter()
+        .map(|name| dir.join(wasm_file(name)))
+        .collect()
+}
+
+/// Copy the crate's binaries into `out_dir`, each along with a launcher that
+/// runs it with Node.js.
+pub fn copy_binaries(
+    data: &CrateData,
+    profile: &BuildProfile,
+    out_dir: &Path,
+) -> Result<(), Error> {
+    for (name, artifact) in data.bin_names().iter().zip(artifact_paths(data, profile)) {
+        let wasm = out_dir.join(wasm_file(name));
+        fs::copy(&artifact, &wasm).with_context(|_| {
+            format!(
+                "failed to copy {} to {}",
+                artifact.display(),
+                wasm.display()
+            )
+        })?;
+        write_script(&out_dir.join(launcher_file(name)), &launcher(name))?;
+    }
+    Ok(())
+}
+
+/// The launcher of the binary `name`, which is next to it in the package.
+pub fn launcher(name: &str) -> String {
+    // JSON strings are valid JavaScript strings.
+    let wasm = serde_json::to_string(&wasm_file(name)).unwrap();
+    let name = serde_json::to_string(name).unwrap();
+    NODE_SCRIPT
+        .replace(
+            "WASI_ARGS",
+            &format!("[{}, ...process.argv.slice(2)]", name),
+        )
+        .replace("WASM_PATH", &format!("join(__dirname, {})", wasm))
+}
+
+/// Write the script that `cargo test` uses as the runner of WASI tests, and
+/// return its path. It is passed the test binary followed by its arguments.
+pub fn write_test_runner(data: &CrateData) -> Result<PathBuf, Error> {
+    let dir = data.target_directory().join("wasm-pack");
+    fs::create_dir_all(&dir)?;
+    let path = dir.join("wasi-test-runner.js");
+    let script = NODE_SCRIPT
+        .replace("WASI_ARGS", "process.argv.slice(2)")
+        .replace("WASM_PATH", "process.argv[2]");
+    write_script(&path, &script)?;
+    Ok(path)
+}
+
+fn write_script(path: &Path, contents: &str) -> Result<(), Error> {
+    fs::write(path, contents).with_context(|_| format!("failed to write {}", path.display()))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755))?;
+    }
+    Ok(())
+}
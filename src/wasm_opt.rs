@@ -1,12 +1,17 @@
 //! Support for downloading and executing `wasm-opt`
 
 use crate::child;
-use crate::install::{self, Tool};
+use crate::install::{self, InstallPlan, Tool};
+use crate::target;
 use crate::PBAR;
-use binary_install::Cache;
+use binary_install::{Cache, Download};
 use std::path::Path;
 use std::process::Command;
 
+/// The binaryen release that `wasm-opt` is downloaded from, and the version
+/// it is cached under.
+pub const WASM_OPT_VERSION: &str = "version_90";
+
 /// Execute `wasm-opt` over wasm binaries found in `out_dir`, downloading if
 /// necessary into `cache`. Passes `args` to each invocation of `wasm-opt`.
 pub fn run(
@@ -22,7 +27,11 @@ pub fn run(
             return Ok(());
         }
         install::Status::PlatformNotSupported => {
-            PBAR.info("Skipping wasm-opt because it is not supported on this platform");
+            PBAR.info(&format!(
+                "Skipping wasm-opt because there are no prebuilt binaries of it for {}. \
+                 Install binaryen and put wasm-opt on the $PATH to optimize wasm binaries.",
+                target::platform()
+            ));
             return Ok(());
         }
     };
@@ -38,8 +47,7 @@ pub fn run(
         }
 
         let tmp = path.with_extension("wasm-opt.wasm");
-        let mut cmd = Command::new(&wasm_opt_path);
-        cmd.arg(&path).arg("-o").arg(&tmp).args(args);
+        let cmd = command(&wasm_opt_path, &path, &tmp, args);
         child::run(cmd, "wasm-opt")?;
         std::fs::rename(&tmp, &path)?;
     }
@@ -47,22 +55,42 @@ pub fn run(
     Ok(())
 }
 
-/// Attempts to find `wasm-opt` in `PATH` locally, or failing that downloads a
-/// precompiled binary.
+/// The command that optimizes `wasm` into `output`.
+pub fn command(wasm_opt_path: &Path, wasm: &Path, output: &Path, args: &[String]) -> Command {
+    let mut cmd = Command::new(wasm_opt_path);
+    cmd.arg(wasm).arg("-o").arg(output).args(args);
+    cmd
+}
+
+/// Attempts to download a precompiled `wasm-opt`, or on platforms without
+/// precompiled binaries, to find one in `PATH`.
 ///
-/// Returns `Some` if a binary was found or it was successfully downloaded.
-/// Returns `None` if a binary wasn't found in `PATH` and this platform doesn't
-/// have precompiled binaries. Returns an error if we failed to download the
-/// binary.
+/// Returns `Found` if a binary was found or it was successfully downloaded.
+/// Returns `PlatformNotSupported` if this platform doesn't have precompiled
+/// binaries and a binary wasn't found in `PATH`. Returns an error if we failed
+/// to download the binary.
 pub fn find_wasm_opt(
     cache: &Cache,
     install_permitted: bool,
 ) -> Result<install::Status, failure::Error> {
-    let version = "version_78";
+    if !install::has_prebuilt(&Tool::WasmOpt) {
+        return Ok(match which::which("wasm-opt") {
+            Ok(path) => install::Status::Found(Download::at(path.parent().unwrap())),
+            Err(_) => install::Status::PlatformNotSupported,
+        });
+    }
     Ok(install::download_prebuilt(
         &install::Tool::WasmOpt,
         cache,
-        version,
+        WASM_OPT_VERSION,
         install_permitted,
     )?)
 }
+
+/// Like `find_wasm_opt`, but only reports how `wasm-opt` would be obtained.
+pub fn plan(cache: &Cache, install_permitted: bool) -> InstallPlan {
+    match which::which("wasm-opt") {
+        Ok(path) if !install::has_prebuilt(&Tool::WasmOpt) => InstallPlan::Global { path },
+        _ => install::plan_prebuilt(&Tool::WasmOpt, cache, WASM_OPT_VERSION, install_permitted),
+    }
+}
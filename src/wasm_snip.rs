@@ -0,0 +1,55 @@
+//! Support for installing and executing `wasm-snip`
+
+use crate::child;
+use crate::install::{self, Tool};
+use crate::PBAR;
+use binary_install::Cache;
+use std::path::Path;
+use std::process::Command;
+
+/// The version of `wasm-snip` that is installed from crates.io.
+pub const WASM_SNIP_VERSION: &str = "0.4.0";
+
+/// Execute `wasm-snip` over wasm binaries found in `out_dir`, installing it
+/// if necessary into `cache`. Passes `args` to each invocation of
+/// `wasm-snip`.
+pub fn run(
+    cache: &Cache,
+    out_dir: &Path,
+    args: &[String],
+    install_permitted: bool,
+) -> Result<(), failure::Error> {
+    let status = install::download_prebuilt_or_cargo_install(
+        Tool::WasmSnip,
+        cache,
+        WASM_SNIP_VERSION,
+        install_permitted,
+    )?;
+    let wasm_snip_path =
+        install::get_tool_path(&status, Tool::WasmSnip)?.binary(&Tool::WasmSnip.to_string())?;
+    PBAR.info("Snipping functions out of wasm binaries with `wasm-snip`...");
+
+    for file in out_dir.read_dir()? {
+        let file = file?;
+        let path = file.path();
+        if path.extension().and_then(|s| s.to_str()) != Some("wasm") {
+            continue;
+        }
+
+        let tmp = path.with_extension("wasm-snip.wasm");
+        let cmd = command(&wasm_snip_path, &path, &tmp, args);
+        child::run(cmd, "wasm-snip")?;
+        std::fs::rename(&tmp, &path)?;
+    }
+
+    Ok(())
+}
+
+/// The command that snips functions out of `wasm` and writes the result to
+/// `output`.
+pub fn command(wasm_snip_path: &Path, wasm: &Path, output: &Path, args: &[String]) -> Command {
+    let mut cmd = Command::new(wasm_snip_path);
+    // `args` end with the names of the functions to snip.
+    cmd.arg(wasm).arg("--output").arg(output).args(args);
+    cmd
+}
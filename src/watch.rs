@@ -0,0 +1,85 @@
+//! Polling file watcher used by `wasm-pack build --watch`.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use walkdir::WalkDir;
+
+/// How often the watched paths are polled for changes.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How long the watched paths must stay unchanged before a rebuild starts, so
+/// that a burst of saves only triggers a single build.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// The modification times of every file below a set of watched paths.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Snapshot {
+    files: BTreeMap<PathBuf, SystemTime>,
+}
+
+impl Snapshot {
+    /// Record the current state of all files below `paths`. Paths that don't
+    /// exist are skipped, so that they can be created later on.
+    pub fn take(paths: &[PathBuf]) -> Snapshot {
+        let mut files = BTreeMap::new();
+        for path in paths {
+            let entries = WalkDir::new(path)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file());
+            for entry in entries {
+                if let Some(modified) = entry.metadata().ok().and_then(|m| m.modified().ok()) {
+                    files.insert(entry.into_path(), modified);
+                }
+            }
+        }
+        Snapshot { files }
+    }
+
+    /// List the files that were added, removed or modified between `self` and
+    /// `newer`.
+    pub fn changed_paths<'a>(&'a self, newer: &'a Snapshot) -> Vec<&'a Path> {
+        let mut changed: Vec<&Path> = newer
+            .files
+            .iter()
+            .filter(|(path, modified)| self.files.get(*path) != Some(modified))
+            .map(|(path, _)| path.as_path())
+            .collect();
+        changed.extend(
+            self.files
+                .keys()
+                .filter(|path| !newer.files.contains_key(*path))
+                .map(|path| path.as_path()),
+        );
+        changed
+    }
+}
+
+/// Block until something below `paths` differs from `last`, then wait for the
+/// changes to settle and return the new snapshot.
+pub fn wait_for_changes(paths: &[PathBuf], last: &Snapshot) -> Snapshot {
+    let mut current = loop {
+        thread::sleep(POLL_INTERVAL);
+        let current = Snapshot::take(paths);
+        if current != *last {
+            break current;
+        }
+    };
+    loop {
+        thread::sleep(DEBOUNCE);
+        let settled = Snapshot::take(paths);
+        if settled == current {
+            return settled;
+        }
+        current = settled;
+    }
+}
+
+/// Whether a change to `path` needs a full build rather than a rebuild: a
+/// `Cargo.toml` can change the crate's metadata, and a `Cargo.lock` the
+/// version of wasm-bindgen that has to be installed.
+pub fn needs_full_build(path: &Path) -> bool {
+    path.ends_with("Cargo.toml") || path.ends_with("Cargo.lock")
+}
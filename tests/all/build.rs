@@ -1,4 +1,5 @@
 use assert_cmd::prelude::*;
+use predicates::boolean::PredicateBooleanExt;
 use std::fs;
 use std::path::Path;
 use utils;
@@ -270,6 +271,112 @@ fn build_with_arbitrary_cargo_options() {
         .success();
 }
 
+#[test]
+fn build_multiple_targets() {
+    let fixture = utils::fixture::js_hello_world();
+    fixture.install_local_wasm_bindgen();
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--target")
+        .arg("bundler,nodejs,web")
+        .assert()
+        .success();
+
+    let pkg_dir = fixture.path.join("pkg");
+    for target in &["bundler", "nodejs", "web"] {
+        assert!(pkg_dir
+            .join(target)
+            .join("js_hello_world_bg.wasm")
+            .is_file());
+    }
+    assert!(pkg_dir.join("package.json").is_file());
+    assert!(!pkg_dir.join("bundler").join("package.json").exists());
+}
+
+#[test]
+fn dry_run_applies_the_defaults_of_each_workspace_member() {
+    let fixture = utils::fixture::cdylib_workspace();
+    fixture.file(
+        Path::new("member_a").join("Cargo.toml"),
+        r#"
+            [package]
+            authors = ["The wasm-pack developers"]
+            description = "so awesome rust+wasm package"
+            license = "WTFPL"
+            name = "member_a"
+            repository = "https://github.com/rustwasm/wasm-pack.git"
+            version = "0.1.0"
+
+            [lib]
+            crate-type = ["cdylib"]
+
+            [dependencies]
+            wasm-bindgen = "0.2"
+
+            [package.metadata.wasm-pack]
+            target = "nodejs"
+            out-dir = "www"
+            out-name = "a"
+            no-typescript = true
+        "#,
+    );
+    let root = fixture.path.clone();
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--workspace")
+        .arg("--dry-run")
+        .assert()
+        .success()
+        .stderr(predicates::str::contains(
+            "member_a sets a default `target`, which a workspace build ignores",
+        ))
+        .stdout(predicates::str::contains(format!(
+            "--out-dir {} --no-typescript --target bundler --out-name a",
+            root.join("member_a").join("www").display()
+        )))
+        .stdout(predicates::str::contains(format!(
+            "--out-dir {} --typescript --target bundler",
+            root.join("pkg").join("member_b").display()
+        )));
+}
+
+#[test]
+fn build_workspace_members() {
+    let fixture = utils::fixture::cdylib_workspace();
+    fixture.install_local_wasm_bindgen();
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--workspace")
+        .arg("--exclude")
+        .arg("member_b")
+        .assert()
+        .success();
+
+    let pkg_dir = fixture.path.join("pkg");
+    assert!(pkg_dir.join("member_a").join("package.json").is_file());
+    assert!(!pkg_dir.join("member_b").exists());
+    assert!(!pkg_dir.join("not_wasm").exists());
+
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("-p")
+        .arg("member_b")
+        .arg("--out-dir")
+        .arg("{dir}/pkg")
+        .assert()
+        .success();
+    assert!(fixture
+        .path
+        .join("member_b")
+        .join("pkg")
+        .join("member_b_bg.wasm")
+        .is_file());
+}
+
 #[test]
 fn build_no_install() {
     let fixture = utils::fixture::js_hello_world();
@@ -376,3 +483,572 @@ fn build_crates_with_same_names() {
         .assert()
         .success();
 }
+
+#[test]
+fn dry_run_prints_the_plan_without_building() {
+    let fixture = utils::fixture::js_hello_world();
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--dry-run")
+        .arg("--target")
+        .arg("web")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(
+            "Build plan (release profile, normal mode, targets: web)",
+        ))
+        .stdout(predicates::str::contains(
+            "$ cargo build --lib --release --target wasm32-unknown-unknown",
+        ))
+        .stdout(predicates::str::contains("--target web"))
+        .stdout(predicates::str::contains("js_hello_world_bg.wasm"));
+    assert!(!fixture.path.join("pkg").exists());
+}
+
+#[test]
+fn dry_run_prints_the_plan_as_json() {
+    let fixture = utils::fixture::js_hello_world();
+    let output = fixture
+        .wasm_pack()
+        .arg("--message-format")
+        .arg("json")
+        .arg("build")
+        .arg("--dry-run")
+        .arg("--dev")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let plan: serde_json::Value = serde_json::from_str(stdout.lines().next().unwrap()).unwrap();
+    assert_eq!(plan["event"], "plan");
+    assert_eq!(plan["profile"], "dev");
+    let steps = plan["steps"].as_array().unwrap();
+    let step = |name: &str| steps.iter().find(|s| s["step"] == name).unwrap();
+    assert_eq!(step("build_wasm")["commands"][0]["args"][1], "build");
+    assert_eq!(
+        step("create_json")["writes"][0],
+        fixture
+            .path
+            .join("pkg")
+            .join("package.json")
+            .to_str()
+            .unwrap()
+    );
+    assert_eq!(
+        step("run_wasm_opt")["notes"][0],
+        "wasm-opt is disabled for this profile"
+    );
+    assert!(!fixture.path.join("pkg").exists());
+}
+
+#[test]
+fn offline_dry_run_does_not_download_tools() {
+    let fixture = utils::fixture::js_hello_world();
+    fixture
+        .wasm_pack()
+        .env("WASM_PACK_CACHE", fixture.path.join("empty-cache"))
+        .arg("--offline")
+        .arg("build")
+        .arg("--dry-run")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(
+            "is not installed, and wasm-pack is offline",
+        ))
+        .stdout(predicates::str::contains("download from").not());
+}
+
+#[test]
+fn dry_run_downloads_tools_from_the_configured_mirrors() {
+    let fixture = utils::fixture::js_hello_world();
+    fixture.file(
+        "wasm-pack.toml",
+        r#"
+            [mirrors]
+            wasm-opt = "http://mirror.test/binaryen/{version}/{target}.{ext}"
+        "#,
+    );
+    fixture
+        .wasm_pack()
+        .env("WASM_PACK_CACHE", fixture.path.join("empty-cache"))
+        .env("WASM_PACK_CONFIG", fixture.path.join("wasm-pack.toml"))
+        .env(
+            "WASM_PACK_WASM_BINDGEN_URL",
+            "http://mirror.test/wasm-bindgen/{version}/{target}.{ext}",
+        )
+        .arg("build")
+        .arg("--dry-run")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(
+            "download from http://mirror.test/wasm-bindgen/0.2.74/",
+        ))
+        .stdout(predicates::str::contains(
+            "download from http://mirror.test/binaryen/version_90/",
+        ));
+}
+
+#[test]
+fn build_rejects_mirrors_for_unknown_tools() {
+    let fixture = utils::fixture::js_hello_world();
+    fixture.file(
+        "wasm-pack.toml",
+        "[mirrors]\nwasm-bindgn = \"http://mirror.test\"\n",
+    );
+    fixture
+        .wasm_pack()
+        .env("WASM_PACK_CACHE", fixture.path.join("empty-cache"))
+        .env("WASM_PACK_CONFIG", fixture.path.join("wasm-pack.toml"))
+        .arg("build")
+        .arg("--dry-run")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "configures `wasm-bindgn` in `[mirrors]`, which wasm-pack doesn't download",
+        ));
+}
+
+#[test]
+fn dry_run_uses_a_custom_cargo_profile() {
+    let fixture = utils::fixture::Fixture::new();
+    fixture.readme().hello_world_src_lib().file(
+        "Cargo.toml",
+        r#"
+            [package]
+            authors = ["The wasm-pack developers"]
+            description = "so awesome rust+wasm package"
+            license = "WTFPL"
+            name = "whatever"
+            repository = "https://github.com/rustwasm/wasm-pack.git"
+            version = "0.1.0"
+
+            [lib]
+            crate-type = ["cdylib"]
+
+            [dependencies]
+            wasm-bindgen = "0.2"
+
+            [profile.wasm-size]
+            inherits = "release"
+            opt-level = "z"
+
+            [package.metadata.wasm-pack.profile.wasm-size]
+            wasm-opt = ["-Oz"]
+            "#,
+    );
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--dry-run")
+        .arg("--profile")
+        .arg("wasm-size")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(
+            "Build plan (wasm-size profile, normal mode, targets: bundler)",
+        ))
+        .stdout(predicates::str::contains(
+            "$ cargo build --lib --profile wasm-size --target wasm32-unknown-unknown",
+        ))
+        .stdout(predicates::str::contains(
+            Path::new("wasm32-unknown-unknown")
+                .join("wasm-size")
+                .join("whatever.wasm")
+                .to_str()
+                .unwrap(),
+        ))
+        .stdout(predicates::str::contains("-Oz"));
+}
+
+#[test]
+fn dry_run_finds_the_artifacts_of_the_bench_profile_in_the_release_dir() {
+    let fixture = utils::fixture::js_hello_world();
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--dry-run")
+        .arg("--profile")
+        .arg("bench")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(
+            "$ cargo build --lib --profile bench --target wasm32-unknown-unknown",
+        ))
+        .stdout(predicates::str::contains(
+            Path::new("wasm32-unknown-unknown")
+                .join("release")
+                .join("js_hello_world.wasm")
+                .to_str()
+                .unwrap(),
+        ));
+}
+
+#[test]
+fn dry_run_uses_the_config_of_each_target() {
+    let fixture = utils::fixture::Fixture::new();
+    fixture.readme().hello_world_src_lib().file(
+        "Cargo.toml",
+        r#"
+            [package]
+            authors = ["The wasm-pack developers"]
+            description = "so awesome rust+wasm package"
+            license = "WTFPL"
+            name = "whatever"
+            repository = "https://github.com/rustwasm/wasm-pack.git"
+            version = "0.1.0"
+
+            [lib]
+            crate-type = ["cdylib"]
+
+            [dependencies]
+            wasm-bindgen = "0.2"
+
+            [package.metadata.wasm-pack.profile.release.target.web]
+            wasm-opt = ["-O", "--asyncify"]
+
+            [package.metadata.wasm-pack.profile.release.target.nodejs]
+            wasm-opt = false
+            "#,
+    );
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--dry-run")
+        .arg("--target")
+        .arg("web,nodejs")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("-O --asyncify"))
+        .stdout(predicates::str::contains(
+            "wasm-opt is disabled for nodejs with this profile",
+        ));
+}
+
+#[test]
+fn build_rejects_profile_with_other_profile_flags() {
+    let fixture = utils::fixture::js_hello_world();
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--release")
+        .arg("--profile")
+        .arg("wasm-size")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "Can only supply one of the --dev, --release, --profiling, or --profile flags",
+        ));
+}
+
+#[test]
+fn dry_run_enables_debug_info_for_profiling_builds() {
+    let fixture = utils::fixture::js_hello_world();
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--dry-run")
+        .arg("--profiling")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(
+            "$ CARGO_PROFILE_RELEASE_DEBUG=true CARGO_PROFILE_RELEASE_STRIP=none cargo build \
+             --lib --release --target wasm32-unknown-unknown",
+        ))
+        .stdout(predicates::str::contains("--keep-debug"))
+        .stdout(predicates::str::contains("-O -g"));
+}
+
+#[test]
+fn dry_run_builds_the_binaries_of_a_wasi_crate() {
+    let fixture = utils::fixture::wasi_hello_world();
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--dry-run")
+        .arg("--target")
+        .arg("wasi")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(
+            "$ cargo build --bins --release --target wasm32-wasi",
+        ))
+        .stdout(predicates::str::contains("copy_wasi_binaries"))
+        .stdout(predicates::str::contains(
+            fixture
+                .path
+                .join("pkg")
+                .join("hello.wasm")
+                .to_str()
+                .unwrap(),
+        ))
+        .stdout(predicates::str::contains(
+            fixture.path.join("pkg").join("hello.js").to_str().unwrap(),
+        ))
+        .stdout(predicates::str::contains("run_wasm_bindgen").not());
+}
+
+#[test]
+fn build_rejects_wasi_with_other_targets() {
+    let fixture = utils::fixture::wasi_hello_world();
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--target")
+        .arg("wasi")
+        .arg("--target")
+        .arg("web")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "--target wasi cannot be combined with other targets",
+        ));
+}
+
+#[test]
+fn build_wasi_requires_a_binary() {
+    let fixture = utils::fixture::js_hello_world();
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--target")
+        .arg("wasi")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("but js-hello-world has none"));
+}
+
+#[test]
+fn dry_run_writes_a_deno_json_for_the_deno_target() {
+    let fixture = utils::fixture::js_hello_world();
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--dry-run")
+        .arg("--target")
+        .arg("deno")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("--target deno"))
+        .stdout(predicates::str::contains("create_deno_json"))
+        .stdout(predicates::str::contains(
+            fixture.path.join("pkg").join("mod.ts").to_str().unwrap(),
+        ))
+        .stdout(predicates::str::contains(
+            fixture.path.join("pkg").join("deno.json").to_str().unwrap(),
+        ))
+        .stdout(predicates::str::contains("package.json").not());
+}
+
+#[test]
+fn build_rejects_deno_with_other_targets() {
+    let fixture = utils::fixture::js_hello_world();
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--target")
+        .arg("deno,web")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "--target deno cannot be combined with other targets",
+        ));
+}
+
+fn hooks_fixture(hooks: &str) -> utils::fixture::Fixture {
+    let fixture = utils::fixture::Fixture::new();
+    fixture.readme().hello_world_src_lib().file(
+        "Cargo.toml",
+        format!(
+            r#"
+            [package]
+            authors = ["The wasm-pack developers"]
+            description = "so awesome rust+wasm package"
+            license = "WTFPL"
+            name = "hooks"
+            repository = "https://github.com/rustwasm/wasm-pack.git"
+            version = "0.1.0"
+
+            [lib]
+            crate-type = ["cdylib"]
+
+            [dependencies]
+            wasm-bindgen = "0.2"
+
+            [package.metadata.wasm-pack.hooks]
+            {}
+            "#,
+            hooks
+        ),
+    );
+    fixture
+}
+
+#[test]
+fn dry_run_lists_the_hooks() {
+    let fixture = hooks_fixture(
+        r#"
+            pre-build = "./prepare.sh"
+            post-bindgen = ["node patch.js", "tsc --noEmit"]
+            post-package-json = "cp -r assets pkg"
+        "#,
+    );
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--dry-run")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("run_pre_build_hooks"))
+        .stdout(predicates::str::contains("./prepare.sh"))
+        .stdout(predicates::str::contains("run_post_bindgen_hooks"))
+        .stdout(predicates::str::contains("\"node patch.js\""))
+        .stdout(predicates::str::contains("\"tsc --noEmit\""))
+        .stdout(predicates::str::contains("\"cp -r assets pkg\""));
+}
+
+#[test]
+#[cfg(unix)]
+fn pre_build_hooks_run_with_the_build_environment() {
+    let fixture = hooks_fixture(
+        r#"
+            pre-build = [
+                "echo $WASM_PACK_PROFILE $WASM_PACK_TARGET $WASM_PACK_OUT_DIR > hook.txt",
+                "exit 3",
+            ]
+        "#,
+    );
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--mode")
+        .arg("force")
+        .arg("--dev")
+        .arg("--target")
+        .arg("web")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("the pre-build hook failed"))
+        .stderr(predicates::str::contains("failed to execute `exit 3`"));
+    let written = fs::read_to_string(fixture.path.join("hook.txt")).unwrap();
+    assert_eq!(
+        written.trim(),
+        format!("dev web {}", fixture.path.join("pkg").display())
+    );
+}
+
+fn build_defaults_fixture() -> utils::fixture::Fixture {
+    let fixture = utils::fixture::Fixture::new();
+    fixture.readme().hello_world_src_lib().file(
+        "Cargo.toml",
+        r#"
+            [package]
+            authors = ["The wasm-pack developers"]
+            description = "so awesome rust+wasm package"
+            license = "WTFPL"
+            name = "defaults"
+            repository = "https://github.com/rustwasm/wasm-pack.git"
+            version = "0.1.0"
+
+            [lib]
+            crate-type = ["cdylib"]
+
+            [dependencies]
+            wasm-bindgen = "0.2"
+
+            [package.metadata.wasm-pack]
+            target = "web"
+            scope = "myorg"
+            out-dir = "www/pkg"
+            out-name = "core"
+            no-typescript = true
+            extra-options = ["--locked"]
+        "#,
+    );
+    fixture
+}
+
+#[test]
+fn build_options_default_to_the_crate_metadata() {
+    let fixture = build_defaults_fixture();
+    let out_dir = fixture.path.join("www").join("pkg");
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--dry-run")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("targets: web)"))
+        .stdout(predicates::str::contains(
+            "$ cargo build --lib --release --target wasm32-unknown-unknown --locked",
+        ))
+        .stdout(predicates::str::contains(format!(
+            "--out-dir {} --no-typescript --target web --out-name core",
+            out_dir.display()
+        )))
+        .stdout(predicates::str::contains(
+            out_dir.join("package.json").to_str().unwrap(),
+        ));
+}
+
+#[test]
+fn build_options_on_the_command_line_override_the_crate_metadata() {
+    let fixture = build_defaults_fixture();
+    let out_dir = fixture.path.join("out");
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--dry-run")
+        .arg("--target")
+        .arg("nodejs")
+        .arg("--out-dir")
+        .arg("out")
+        .arg("--out-name")
+        .arg("other")
+        .arg("--")
+        .arg("--offline")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("targets: nodejs)"))
+        .stdout(predicates::str::contains(
+            "$ cargo build --lib --release --target wasm32-unknown-unknown --offline",
+        ))
+        .stdout(predicates::str::contains(format!(
+            "--out-dir {} --no-typescript --target nodejs --out-name other",
+            out_dir.display()
+        )));
+}
+
+#[test]
+fn typescript_flag_overrides_no_typescript_in_the_crate_metadata() {
+    let fixture = build_defaults_fixture();
+    let out_dir = fixture.path.join("www").join("pkg");
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--dry-run")
+        .arg("--typescript")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(format!(
+            "--out-dir {} --typescript --target web --out-name core",
+            out_dir.display()
+        )));
+}
+
+#[test]
+fn typescript_flags_conflict() {
+    let fixture = build_defaults_fixture();
+    fixture
+        .wasm_pack()
+        .arg("build")
+        .arg("--dry-run")
+        .arg("--typescript")
+        .arg("--no-typescript")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "Can only supply one of the --typescript or --no-typescript flags",
+        ));
+}
@@ -0,0 +1,93 @@
+use std::fs;
+use tempfile;
+use wasm_pack::debug_info;
+use wasm_pack::size::FileSize;
+
+/// A custom section called `name` with the given contents.
+fn custom_section(name: &str, contents: &[u8]) -> Vec<u8> {
+    let mut payload = vec![name.len() as u8];
+    payload.extend(name.as_bytes());
+    payload.extend(contents);
+    let mut section = vec![0, payload.len() as u8];
+    section.extend(payload);
+    section
+}
+
+/// A module with a single empty function, and the given custom sections.
+fn wasm(custom_sections: &[Vec<u8>]) -> Vec<u8> {
+    let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+    // type section: one `() -> ()` type
+    wasm.extend(&[1, 4, 1, 0x60, 0, 0]);
+    // function section: one function of type 0
+    wasm.extend(&[3, 2, 1, 0]);
+    // code section: an empty body
+    wasm.extend(&[10, 4, 1, 2, 0, 0x0b]);
+    for section in custom_sections {
+        wasm.extend(section);
+    }
+    wasm
+}
+
+#[test]
+fn it_moves_dwarf_into_an_external_file() {
+    let producers = custom_section("producers", &[0]);
+    let original = wasm(&[
+        custom_section(".debug_info", &[1, 2, 3]),
+        producers.clone(),
+        custom_section(".debug_line", &[4, 5]),
+    ]);
+    let stripped = debug_info::strip(&original, "foo_bg.debug.wasm")
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        stripped,
+        wasm(&[
+            producers,
+            custom_section("external_debug_info", b"\x11foo_bg.debug.wasm"),
+        ])
+    );
+
+    let sections: Vec<_> = FileSize::new(&stripped)
+        .unwrap()
+        .sections
+        .into_iter()
+        .map(|s| s.name)
+        .collect();
+    assert_eq!(
+        sections,
+        vec![
+            "type",
+            "function",
+            "code",
+            "producers",
+            "external_debug_info"
+        ]
+    );
+}
+
+#[test]
+fn it_leaves_wasm_without_dwarf_alone() {
+    let original = wasm(&[custom_section("producers", &[0])]);
+    assert!(debug_info::strip(&original, "foo_bg.debug.wasm")
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn it_writes_the_debug_file_next_to_the_wasm() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("foo_bg.wasm");
+    let original = wasm(&[custom_section(".debug_info", &[1, 2, 3])]);
+    fs::write(&path, &original).unwrap();
+
+    assert!(debug_info::split(&path).unwrap());
+    let debug_file = dir.path().join("foo_bg.debug.wasm");
+    assert_eq!(debug_info::debug_file_path(&path), debug_file);
+    assert_eq!(fs::read(&debug_file).unwrap(), original);
+    assert_eq!(
+        fs::read(&path).unwrap(),
+        debug_info::strip(&original, "foo_bg.debug.wasm")
+            .unwrap()
+            .unwrap()
+    );
+}
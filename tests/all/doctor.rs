@@ -0,0 +1,73 @@
+use assert_cmd::prelude::*;
+use serde_json::Value;
+use utils;
+
+#[test]
+fn doctor_reports_the_checks() {
+    let fixture = utils::fixture::js_hello_world();
+    fixture
+        .wasm_pack()
+        .env("WASM_PACK_CACHE", fixture.path.join("cache"))
+        .arg("doctor")
+        .assert()
+        .stdout(predicates::str::is_match(r"(PASS|FAIL) +rustc +").unwrap())
+        .stdout(predicates::str::is_match(r"(PASS|WARN|FAIL) +wasm-bindgen +").unwrap())
+        .stdout(predicates::str::is_match(r"PASS +cache +.* is writable, with 0 tools").unwrap());
+}
+
+#[test]
+fn doctor_fails_when_wasm_bindgen_is_unavailable_offline() {
+    let fixture = utils::fixture::js_hello_world();
+    fixture
+        .wasm_pack()
+        .env("WASM_PACK_CACHE", fixture.path.join("cache"))
+        .arg("--offline")
+        .arg("doctor")
+        .assert()
+        .failure()
+        .stdout(predicates::str::contains(
+            "which is unavailable: wasm-bindgen v0.2.74 is not installed, and wasm-pack is \
+             offline",
+        ))
+        .stdout(predicates::str::contains(
+            "fix: Run `wasm-pack prefetch` while online",
+        ))
+        .stderr(predicates::str::contains(
+            "wasm-pack doctor's checks failed",
+        ));
+}
+
+#[test]
+fn doctor_fails_when_the_cache_is_not_writable() {
+    let fixture = utils::fixture::not_a_crate();
+    fixture.file("not-a-directory", "");
+    let output = fixture
+        .wasm_pack()
+        .env(
+            "WASM_PACK_CACHE",
+            fixture.path.join("not-a-directory/cache"),
+        )
+        .arg("--message-format")
+        .arg("json")
+        .arg("doctor")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let events: Vec<Value> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let report = events.iter().find(|e| e["event"] == "doctor").unwrap();
+    let cache = report["checks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|check| check["name"] == "cache")
+        .unwrap();
+    assert_eq!(cache["status"], "fail");
+    assert!(cache["fix"]
+        .as_str()
+        .unwrap()
+        .contains("set WASM_PACK_CACHE to a writable directory"));
+}
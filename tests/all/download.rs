@@ -58,3 +58,156 @@ fn can_download_prebuilt_cargo_generate() {
         assert!(false, "Download Failed");
     }
 }
+
+#[test]
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+fn prebuilt_tools_are_available_on_x86_64_linux() {
+    assert!(install::has_prebuilt(&Tool::WasmBindgen));
+    assert!(install::has_prebuilt(&Tool::CargoGenerate));
+    assert!(install::has_prebuilt(&Tool::WasmOpt));
+    assert!(!install::has_prebuilt(&Tool::WasmSnip));
+}
+
+#[test]
+#[cfg(all(any(target_os = "linux", target_os = "macos"), target_arch = "aarch64"))]
+fn only_wasm_bindgen_is_prebuilt_for_aarch64() {
+    assert!(install::has_prebuilt(&Tool::WasmBindgen));
+    assert!(!install::has_prebuilt(&Tool::WasmOpt));
+
+    let dir = tempfile::TempDir::new().unwrap();
+    let cache = Cache::at(dir.path());
+    let error = install::download_prebuilt(&Tool::CargoGenerate, &cache, "latest", true)
+        .err()
+        .unwrap();
+    assert!(error
+        .to_string()
+        .starts_with("there are no prebuilt cargo-generate binaries for aarch64"));
+
+    let error = install::download_prebuilt(&Tool::WasmOpt, &cache, "version_90", true)
+        .err()
+        .unwrap();
+    assert!(error.to_string().contains("put wasm-opt on the $PATH"));
+}
+
+#[test]
+fn mirror_templates_are_expanded() {
+    assert_eq!(
+        wasm_pack::mirrors::expand(
+            "http://localhost:8080/{version}/wasm-bindgen-{target}.{ext}",
+            "0.2.74",
+            "x86_64-unknown-linux-musl",
+            "tar.gz"
+        )
+        .unwrap(),
+        "http://localhost:8080/0.2.74/wasm-bindgen-x86_64-unknown-linux-musl.tar.gz"
+    );
+    assert!(wasm_pack::mirrors::expand("http://localhost/{name}", "1", "x", "zip").is_err());
+    assert!(wasm_pack::mirrors::expand("http://localhost/{version", "1", "x", "zip").is_err());
+}
+
+#[test]
+fn sha256_matches_the_test_vectors() {
+    use wasm_pack::checksum::sha256_hex;
+    assert_eq!(
+        sha256_hex(b""),
+        "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+    );
+    assert_eq!(
+        sha256_hex(b"abc"),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+    assert_eq!(
+        sha256_hex(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"),
+        "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"
+    );
+    assert_eq!(
+        sha256_hex(
+            b"abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmno\
+              ijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu"
+        ),
+        "cf5b16a778af8380036ce59e7b0492370b249b11e8f07a51afac45037afee9d1"
+    );
+    assert_eq!(
+        sha256_hex(&[b'a'; 1_000_000]),
+        "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+    );
+}
+
+#[test]
+fn downloads_with_the_wrong_digest_are_not_cached() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let archive = dir.path().join("wasm-bindgen.tar.gz");
+    std::fs::write(&archive, b"not what was expected").unwrap();
+    let url = format!("file://{}", archive.display());
+    let cache = Cache::at(&dir.path().join("cache"));
+
+    let expected = wasm_pack::checksum::sha256_hex(b"something else");
+    let error = install::download::download(
+        &cache,
+        true,
+        "wasm-bindgen",
+        &["wasm-bindgen"],
+        &url,
+        Some(&expected),
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("Refusing to install it"));
+    // Only the lock file that guarded the download is left in the cache.
+    let dirname = install::download::hashed_dirname(&url, "wasm-bindgen");
+    let entries: Vec<_> = std::fs::read_dir(dir.path().join("cache"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(entries, vec![format!(".{}.lock", dirname)]);
+
+    // With the right digest, the archive is extracted, and found to be none.
+    let expected = wasm_pack::checksum::sha256_hex(b"not what was expected");
+    let error = install::download::download(
+        &cache,
+        true,
+        "wasm-bindgen",
+        &["wasm-bindgen"],
+        &url,
+        Some(&expected),
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("failed to extract tarball"));
+}
+
+#[test]
+fn tampered_archives_of_known_versions_are_rejected() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let archive = dir.path().join("wasm-bindgen.tar.gz");
+    std::fs::write(&archive, b"tampered").unwrap();
+    let url = format!("file://{}", archive.display());
+    let cache = Cache::at(&dir.path().join("cache"));
+
+    // Neither `WASM_PACK_WASM_BINDGEN_SHA256` nor the config file is set, so
+    // the digest comes from the table of known digests.
+    let genuine = wasm_pack::checksum::sha256_hex(b"genuine");
+    let known = [(
+        "wasm-bindgen",
+        "0.2.74",
+        "x86_64-unknown-linux-musl",
+        genuine.as_str(),
+    )];
+    let expected = wasm_pack::checksum::expected_in(
+        &known,
+        "wasm-bindgen",
+        "0.2.74",
+        "x86_64-unknown-linux-musl",
+    )
+    .unwrap();
+    assert_eq!(expected.as_deref(), Some(genuine.as_str()));
+
+    let error = install::download::download(
+        &cache,
+        true,
+        "wasm-bindgen",
+        &["wasm-bindgen"],
+        &url,
+        expected.as_deref(),
+    )
+    .unwrap_err();
+    assert!(error.to_string().contains("Refusing to install it"));
+}
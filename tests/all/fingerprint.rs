@@ -0,0 +1,47 @@
+use utils::fixture;
+use wasm_pack::fingerprint::{Fingerprint, Fingerprints};
+
+#[test]
+fn fingerprints_depend_on_every_input() {
+    let a = Fingerprint::new().add("ab").add("c").finish();
+    let b = Fingerprint::new().add("a").add("bc").finish();
+    let c = Fingerprint::new().add("ab").add("c").finish();
+    assert_ne!(a, b);
+    assert_eq!(a, c);
+}
+
+#[test]
+fn fingerprints_track_file_contents() {
+    let fixture = fixture::Fixture::new();
+    let path = fixture.path.join("input");
+    let missing = Fingerprint::new().add_file(&path).finish();
+
+    fixture.file("input", "one");
+    let one = Fingerprint::new().add_file(&path).finish();
+    fixture.file("input", "two");
+    let two = Fingerprint::new().add_file(&path).finish();
+
+    assert_ne!(missing, one);
+    assert_ne!(one, two);
+}
+
+#[test]
+fn fingerprints_are_persisted_in_the_out_dir() {
+    let fixture = fixture::Fixture::new();
+    fixture.file("pkg/.gitignore", "*");
+    let out_dir = fixture.path.join("pkg");
+
+    let mut fingerprints = Fingerprints::load(&out_dir);
+    assert!(!fingerprints.is_fresh("readme", "1234"));
+    fingerprints.record("readme", "1234".to_string()).unwrap();
+    fingerprints.record("license", "5678".to_string()).unwrap();
+
+    let mut fingerprints = Fingerprints::load(&out_dir);
+    assert!(fingerprints.is_fresh("readme", "1234"));
+    assert!(!fingerprints.is_fresh("readme", "5678"));
+    fingerprints.forget("readme").unwrap();
+
+    let fingerprints = Fingerprints::load(&out_dir);
+    assert!(!fingerprints.is_fresh("readme", "1234"));
+    assert!(fingerprints.is_fresh("license", "5678"));
+}
@@ -19,3 +19,18 @@ fn new_with_name_succeeds() {
         .assert()
         .success();
 }
+
+#[test]
+fn new_fails_when_offline() {
+    let fixture = utils::fixture::not_a_crate();
+    fixture
+        .wasm_pack()
+        .env("WASM_PACK_OFFLINE", "1")
+        .arg("new")
+        .arg("hello")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "wasm-pack is offline (--offline or WASM_PACK_OFFLINE), so it can't download the template",
+        ));
+}
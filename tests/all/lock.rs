@@ -0,0 +1,44 @@
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tempfile;
+use wasm_pack::lock::{self, FileLock};
+
+#[test]
+fn file_lock_waits_for_the_holder_to_release_it() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("test.lock");
+    let released = Arc::new(AtomicBool::new(false));
+
+    let held = FileLock::acquire(&path, None).unwrap();
+    let waiter = {
+        let path = path.clone();
+        let released = released.clone();
+        thread::spawn(move || {
+            let _lock = FileLock::acquire(&path, Some("testing")).unwrap();
+            assert!(released.load(Ordering::SeqCst));
+        })
+    };
+    thread::sleep(Duration::from_millis(200));
+    released.store(true, Ordering::SeqCst);
+    drop(held);
+    waiter.join().unwrap();
+}
+
+#[test]
+fn write_atomically_replaces_the_file() {
+    let dir = tempfile::TempDir::new().unwrap();
+    let path = dir.path().join("data.json");
+    lock::write_atomically(&path, b"old").unwrap();
+    lock::write_atomically(&path, b"new").unwrap();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+    let leftovers: Vec<_> = fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .filter(|name| name.to_string_lossy().ends_with(".tmp"))
+        .collect();
+    assert!(leftovers.is_empty(), "{:?}", leftovers);
+}
@@ -14,15 +14,24 @@ extern crate tempfile;
 extern crate wasm_pack;
 
 mod build;
+mod debug_info;
+mod doctor;
 mod download;
+mod fingerprint;
 mod generate;
 mod license;
+mod lock;
 mod lockfile;
 mod log_level;
 mod manifest;
+mod message_format;
+mod prefetch;
 mod readme;
+mod size;
 mod stamps;
 mod test;
+mod tools;
 mod utils;
 mod wasm_opt;
+mod watch;
 mod webdriver;
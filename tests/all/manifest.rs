@@ -3,7 +3,7 @@ use std::collections::HashSet;
 use std::fs;
 use std::path::PathBuf;
 use utils::{self, fixture};
-use wasm_pack::command::build::Target;
+use wasm_pack::command::build::{BuildProfile, Target};
 use wasm_pack::command::utils::get_crate_path;
 use wasm_pack::{self, license, manifest};
 
@@ -240,6 +240,57 @@ fn it_creates_a_pkg_json_with_correct_files_on_nomodules() {
     assert_eq!(actual_files, expected_files);
 }
 
+#[test]
+fn it_creates_a_pkg_json_with_exports_for_multiple_targets() {
+    let fixture = fixture::js_hello_world();
+    let out_dir = fixture.path.join("pkg");
+    let crate_data = manifest::CrateData::new(&fixture.path, None).unwrap();
+    wasm_pack::command::utils::create_pkg_dir(&out_dir).unwrap();
+    assert!(crate_data
+        .write_multi_target_package_json(
+            &out_dir,
+            &None,
+            false,
+            &[Target::Bundler, Target::Nodejs, Target::Web],
+        )
+        .is_ok());
+    let pkg: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(out_dir.join("package.json")).unwrap()).unwrap();
+    assert_eq!(pkg["name"], "js-hello-world");
+    assert_eq!(pkg["main"], "./nodejs/js_hello_world.js");
+    assert_eq!(pkg["module"], "./bundler/js_hello_world.js");
+    assert_eq!(pkg["types"], "./bundler/js_hello_world.d.ts");
+
+    let exports = &pkg["exports"]["."];
+    assert_eq!(exports["types"], "./bundler/js_hello_world.d.ts");
+    assert_eq!(exports["browser"], "./bundler/js_hello_world.js");
+    assert_eq!(exports["import"], "./bundler/js_hello_world.js");
+    assert_eq!(exports["require"], "./nodejs/js_hello_world.js");
+
+    let actual_files: HashSet<String> = pkg["files"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|f| f.as_str().unwrap().to_string())
+        .collect();
+    let expected_files: HashSet<String> = [
+        "bundler/js_hello_world.d.ts",
+        "bundler/js_hello_world_bg.js",
+        "bundler/js_hello_world_bg.wasm",
+        "bundler/js_hello_world.js",
+        "nodejs/js_hello_world.d.ts",
+        "nodejs/js_hello_world_bg.wasm",
+        "nodejs/js_hello_world.js",
+        "web/js_hello_world.d.ts",
+        "web/js_hello_world_bg.wasm",
+        "web/js_hello_world.js",
+    ]
+    .iter()
+    .map(|&s| String::from(s))
+    .collect();
+    assert_eq!(actual_files, expected_files);
+}
+
 #[test]
 fn it_creates_a_package_json_with_correct_files_when_out_name_is_provided() {
     let fixture = fixture::js_hello_world();
@@ -543,6 +594,219 @@ fn configure_wasm_bindgen_debug_incorrectly_is_error() {
         ));
 }
 
+#[test]
+fn it_reads_the_size_budget_of_a_profile() {
+    let fixture = utils::fixture::Fixture::new();
+    fixture.readme().hello_world_src_lib().file(
+        "Cargo.toml",
+        r#"
+            [package]
+            authors = ["The wasm-pack developers"]
+            description = "so awesome rust+wasm package"
+            license = "WTFPL"
+            name = "whatever"
+            repository = "https://github.com/rustwasm/wasm-pack.git"
+            version = "0.1.0"
+
+            [lib]
+            crate-type = ["cdylib"]
+
+            [dependencies]
+            wasm-bindgen = "0.2"
+
+            [package.metadata.wasm-pack.profile.release]
+            size-budget = { raw = "1.5 MiB", gzip = 200000 }
+            "#,
+    );
+    let crate_data = manifest::CrateData::new(&fixture.path, None).unwrap();
+    let budget = crate_data
+        .configured_profile(&BuildProfile::Release)
+        .size_budget()
+        .unwrap();
+    assert_eq!(budget.raw, Some(1572864));
+    assert_eq!(budget.gzip, Some(200000));
+    assert!(crate_data
+        .configured_profile(&BuildProfile::Dev)
+        .size_budget()
+        .is_none());
+}
+
+#[test]
+fn it_reads_the_wasm_snip_config_of_a_profile() {
+    let fixture = utils::fixture::Fixture::new();
+    fixture.readme().hello_world_src_lib().file(
+        "Cargo.toml",
+        r#"
+            [package]
+            authors = ["The wasm-pack developers"]
+            description = "so awesome rust+wasm package"
+            license = "WTFPL"
+            name = "whatever"
+            repository = "https://github.com/rustwasm/wasm-pack.git"
+            version = "0.1.0"
+
+            [lib]
+            crate-type = ["cdylib"]
+
+            [dependencies]
+            wasm-bindgen = "0.2"
+
+            [package.metadata.wasm-pack.profile.release.wasm-snip]
+            functions = ["foo", "bar"]
+            patterns = ["core::fmt::.*"]
+            snip-rust-panicking-code = true
+            "#,
+    );
+    let crate_data = manifest::CrateData::new(&fixture.path, None).unwrap();
+    assert_eq!(
+        crate_data
+            .configured_profile(&BuildProfile::Release)
+            .wasm_snip_args()
+            .unwrap(),
+        vec![
+            "--snip-rust-panicking-code",
+            "--pattern",
+            "core::fmt::.*",
+            "--",
+            "foo",
+            "bar"
+        ]
+    );
+    assert!(crate_data
+        .configured_profile(&BuildProfile::Dev)
+        .wasm_snip_args()
+        .is_none());
+}
+
+#[test]
+fn it_keeps_debug_info_in_profiling_builds() {
+    let fixture = utils::fixture::Fixture::new();
+    fixture.readme().hello_world_src_lib().file(
+        "Cargo.toml",
+        r#"
+            [package]
+            authors = ["The wasm-pack developers"]
+            description = "so awesome rust+wasm package"
+            license = "WTFPL"
+            name = "whatever"
+            repository = "https://github.com/rustwasm/wasm-pack.git"
+            version = "0.1.0"
+
+            [lib]
+            crate-type = ["cdylib"]
+
+            [dependencies]
+            wasm-bindgen = "0.2"
+
+            [package.metadata.wasm-pack.profile.profiling]
+            wasm-opt = ["-O3"]
+            split-debug-info = true
+
+            [package.metadata.wasm-pack.profile.release]
+            split-debug-info = true
+            "#,
+    );
+    let crate_data = manifest::CrateData::new(&fixture.path, None).unwrap();
+
+    let profiling = crate_data.configured_profile(&BuildProfile::Profiling);
+    assert!(profiling.wasm_bindgen_dwarf_debug_info());
+    assert_eq!(profiling.wasm_opt_args().unwrap(), vec!["-O3", "-g"]);
+    assert!(profiling.split_debug_info());
+
+    // Without DWARF, there's nothing to split off.
+    let release = crate_data.configured_profile(&BuildProfile::Release);
+    assert_eq!(release.wasm_opt_args().unwrap(), vec!["-O"]);
+    assert!(!release.split_debug_info());
+}
+
+#[test]
+fn it_reads_the_config_of_a_custom_profile() {
+    let fixture = utils::fixture::Fixture::new();
+    fixture.readme().hello_world_src_lib().file(
+        "Cargo.toml",
+        r#"
+            [package]
+            authors = ["The wasm-pack developers"]
+            description = "so awesome rust+wasm package"
+            license = "WTFPL"
+            name = "whatever"
+            repository = "https://github.com/rustwasm/wasm-pack.git"
+            version = "0.1.0"
+
+            [lib]
+            crate-type = ["cdylib"]
+
+            [dependencies]
+            wasm-bindgen = "0.2"
+
+            [profile.wasm-size]
+            inherits = "release"
+            opt-level = "z"
+
+            [profile.fast-dev]
+            inherits = "dev"
+            opt-level = 1
+
+            [package.metadata.wasm-pack.profile.wasm-size]
+            wasm-opt = ["-Oz"]
+            "#,
+    );
+    let crate_data = manifest::CrateData::new(&fixture.path, None).unwrap();
+
+    let wasm_size = crate_data.configured_profile(&BuildProfile::Custom("wasm-size".to_string()));
+    assert_eq!(wasm_size.wasm_opt_args().unwrap(), vec!["-Oz"]);
+    assert!(!wasm_size.wasm_bindgen_debug_js_glue());
+
+    // Profiles without a table of their own take the defaults of the profile
+    // they inherit from.
+    let fast_dev = crate_data.configured_profile(&BuildProfile::Custom("fast-dev".to_string()));
+    assert!(fast_dev.wasm_opt_args().is_none());
+    assert!(fast_dev.wasm_bindgen_debug_js_glue());
+}
+
+#[test]
+fn it_merges_the_config_of_a_target_into_its_profile() {
+    let fixture = utils::fixture::Fixture::new();
+    fixture.readme().hello_world_src_lib().file(
+        "Cargo.toml",
+        r#"
+            [package]
+            authors = ["The wasm-pack developers"]
+            description = "so awesome rust+wasm package"
+            license = "WTFPL"
+            name = "whatever"
+            repository = "https://github.com/rustwasm/wasm-pack.git"
+            version = "0.1.0"
+
+            [lib]
+            crate-type = ["cdylib"]
+
+            [dependencies]
+            wasm-bindgen = "0.2"
+
+            [package.metadata.wasm-pack.profile.release]
+            wasm-opt = ["-Oz"]
+
+            [package.metadata.wasm-pack.profile.release.target.web]
+            wasm-opt = ["-Oz", "--asyncify"]
+
+            [package.metadata.wasm-pack.profile.release.target.web.wasm-bindgen]
+            debug-js-glue = true
+            "#,
+    );
+    let crate_data = manifest::CrateData::new(&fixture.path, None).unwrap();
+
+    let web = crate_data.configured_target_profile(&BuildProfile::Release, Target::Web);
+    assert_eq!(web.wasm_opt_args().unwrap(), vec!["-Oz", "--asyncify"]);
+    assert!(web.wasm_bindgen_debug_js_glue());
+    // What the target doesn't set is taken from the profile.
+    assert!(web.wasm_bindgen_demangle_name_section());
+
+    let nodejs = crate_data.configured_target_profile(&BuildProfile::Release, Target::Nodejs);
+    assert_eq!(nodejs.wasm_opt_args().unwrap(), vec!["-Oz"]);
+    assert!(!nodejs.wasm_bindgen_debug_js_glue());
+}
+
 #[test]
 fn parse_crate_data_returns_unused_keys_in_cargo_toml() {
     let fixture = utils::fixture::Fixture::new();
@@ -613,6 +877,87 @@ fn it_lists_license_files_in_files_field_of_package_json() {
     );
 }
 
+#[test]
+fn it_creates_a_pkg_json_with_launchers_on_wasi() {
+    let fixture = fixture::wasi_hello_world();
+    let out_dir = fixture.path.join("pkg");
+    let crate_data = manifest::CrateData::new(&fixture.path, None).unwrap();
+    wasm_pack::command::utils::create_pkg_dir(&out_dir).unwrap();
+    crate_data
+        .write_package_json(&out_dir, &None, false, Target::Wasi, false)
+        .unwrap();
+
+    let pkg: serde_json::Value =
+        serde_json::from_slice(&fs::read(out_dir.join("package.json")).unwrap()).unwrap();
+    assert_eq!(pkg["name"], "wasi-hello-world");
+    assert_eq!(pkg["bin"]["hello"], "hello.js");
+    assert_eq!(pkg["files"][0], "hello.wasm");
+    assert_eq!(pkg["files"][1], "hello.js");
+    assert!(pkg.get("main").is_none());
+}
+
+#[test]
+fn it_creates_a_deno_json_with_a_scope() {
+    let fixture = fixture::js_hello_world();
+    let out_dir = fixture.path.join("pkg");
+    let crate_data = manifest::CrateData::new(&fixture.path, None).unwrap();
+    wasm_pack::command::utils::create_pkg_dir(&out_dir).unwrap();
+    crate_data
+        .write_deno_json(&out_dir, &Some("test".to_string()))
+        .unwrap();
+
+    let config: serde_json::Value =
+        serde_json::from_slice(&fs::read(out_dir.join("deno.json")).unwrap()).unwrap();
+    assert_eq!(config["name"], "@test/js-hello-world");
+    assert_eq!(config["version"], "0.1.0");
+    assert_eq!(config["license"], "WTFPL");
+    assert_eq!(config["exports"], "./mod.ts");
+    assert!(!out_dir.join("package.json").exists());
+}
+
+#[test]
+fn it_re_exports_the_bindings_from_the_deno_module() {
+    assert_eq!(
+        wasm_pack::deno::module("js_hello_world", false),
+        "// @deno-types=\"./js_hello_world.d.ts\"\nexport * from \"./js_hello_world.js\";\n"
+    );
+    assert_eq!(
+        wasm_pack::deno::module("js_hello_world", true),
+        "export * from \"./js_hello_world.js\";\n"
+    );
+}
+
+#[test]
+fn it_reads_the_build_defaults() {
+    let fixture = utils::fixture::Fixture::new();
+    fixture.hello_world_src_lib().file(
+        "Cargo.toml",
+        r#"
+            [package]
+            name = "defaults"
+            version = "0.1.0"
+
+            [lib]
+            crate-type = ["cdylib"]
+
+            [package.metadata.wasm-pack]
+            target = ["web", "nodejs"]
+            scope = "myorg"
+        "#,
+    );
+    let defaults = manifest::CrateData::build_defaults(&fixture.path).unwrap();
+    assert_eq!(defaults.target, vec![Target::Web, Target::Nodejs]);
+    assert_eq!(defaults.scope, Some("myorg".to_string()));
+    assert_eq!(defaults.out_dir, None);
+    assert!(!defaults.no_typescript);
+
+    let manifest_path = fixture.path.join("Cargo.toml");
+    let keys = manifest::CrateData::parse_crate_data(&manifest_path)
+        .unwrap()
+        .unused_keys;
+    assert!(keys.is_empty(), "unexpected unused keys: {:?}", keys);
+}
+
 #[test]
 fn it_recurses_up_the_path_to_find_cargo_toml() {
     let fixture = utils::fixture::Fixture::new();
@@ -655,3 +1000,22 @@ fn it_doesnt_recurse_up_the_path_to_find_cargo_toml_when_default() {
     let crate_data = manifest::CrateData::new(&path, None);
     assert!(crate_data.is_err());
 }
+
+#[test]
+fn it_finds_the_cdylib_members_of_a_workspace() {
+    let fixture = fixture::cdylib_workspace();
+
+    let members = manifest::CrateData::workspace_members(&fixture.path, &[], &[], None).unwrap();
+    let names: Vec<&str> = members.iter().map(|m| m.package_name()).collect();
+    assert_eq!(names, vec!["member_a", "member_b"]);
+
+    let members =
+        manifest::CrateData::workspace_members(&fixture.path, &[], &["member_a".to_string()], None)
+            .unwrap();
+    let names: Vec<&str> = members.iter().map(|m| m.package_name()).collect();
+    assert_eq!(names, vec!["member_b"]);
+
+    let members =
+        manifest::CrateData::workspace_members(&fixture.path, &["not_wasm".to_string()], &[], None);
+    assert!(members.is_err());
+}
@@ -0,0 +1,83 @@
+use assert_cmd::prelude::*;
+use predicates::prelude::predicate::str::contains;
+use serde_json::{self, Value};
+use utils;
+
+fn events(stdout: &[u8]) -> Vec<Value> {
+    String::from_utf8_lossy(stdout)
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("every line should be a JSON event"))
+        .collect()
+}
+
+#[test]
+fn json_reports_steps_artifacts_and_result() {
+    let fixture = utils::fixture::Fixture::new();
+    fixture.cargo_toml("js-hello-world").hello_world_src_lib();
+    let output = fixture
+        .wasm_pack()
+        .arg("--message-format")
+        .arg("json")
+        .arg("build")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let events = events(&output.stdout);
+    assert!(events
+        .iter()
+        .any(|e| e["event"] == "step-started" && e["step"] == "build_wasm"));
+    assert!(events.iter().any(|e| e["event"] == "step-finished"
+        && e["step"] == "build_wasm"
+        && e["success"] == true
+        && e["duration_ms"].is_u64()));
+    assert!(events
+        .iter()
+        .any(|e| e["event"] == "message" && e["level"] == "warn"));
+    for kind in &["wasm", "js", "dts", "package-json"] {
+        assert!(
+            events
+                .iter()
+                .any(|e| e["event"] == "artifact" && e["kind"] == *kind),
+            "missing {} artifact",
+            kind
+        );
+    }
+    let last = events.last().unwrap();
+    assert_eq!(last["event"], "finished");
+    assert_eq!(last["success"], true);
+}
+
+#[test]
+fn json_reports_failures() {
+    let fixture = utils::fixture::Fixture::new();
+    fixture.cargo_toml("js-hello-world");
+    let output = fixture
+        .wasm_pack()
+        .arg("--message-format")
+        .arg("json")
+        .arg("build")
+        .arg("does-not-exist")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let events = events(&output.stdout);
+    let last = events.last().unwrap();
+    assert_eq!(last["event"], "finished");
+    assert_eq!(last["success"], false);
+    assert!(last["error"].is_string());
+}
+
+#[test]
+fn unknown_message_format() {
+    utils::fixture::Fixture::new()
+        .cargo_toml("js-hello-world")
+        .wasm_pack()
+        .arg("--message-format")
+        .arg("xml")
+        .arg("build")
+        .assert()
+        .failure()
+        .stderr(contains("Unknown message-format: xml"));
+}
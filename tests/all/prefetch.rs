@@ -0,0 +1,67 @@
+use assert_cmd::prelude::*;
+use utils;
+
+#[test]
+fn prefetch_fails_when_offline() {
+    let fixture = utils::fixture::js_hello_world();
+    fixture
+        .wasm_pack()
+        .arg("--offline")
+        .arg("prefetch")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "wasm-pack is offline (--offline or WASM_PACK_OFFLINE), so it can't prefetch tools",
+        ));
+}
+
+#[test]
+#[cfg(all(unix, target_arch = "x86_64"))]
+fn prefetch_uses_the_tools_in_the_cache() {
+    use std::os::unix::fs::PermissionsExt;
+    use wasm_pack::install::download::hashed_dirname;
+
+    let fixture = utils::fixture::js_hello_world();
+    let binaries = [
+        (
+            "http://mirror.test/wasm-bindgen/0.2.74.tar.gz",
+            "wasm-bindgen",
+            "wasm-bindgen",
+        ),
+        (
+            "http://mirror.test/wasm-bindgen/0.2.74.tar.gz",
+            "wasm-bindgen",
+            "wasm-bindgen-test-runner",
+        ),
+        (
+            "http://mirror.test/binaryen/version_90.tar.gz",
+            "wasm-opt",
+            "wasm-opt",
+        ),
+    ];
+    for &(url, tool, binary) in &binaries {
+        let path = format!("cache/{}/{}", hashed_dirname(url, tool), binary);
+        fixture.file(&path, "");
+        std::fs::set_permissions(
+            fixture.path.join(&path),
+            std::fs::Permissions::from_mode(0o755),
+        )
+        .unwrap();
+    }
+    fixture
+        .wasm_pack()
+        .env("WASM_PACK_CACHE", fixture.path.join("cache"))
+        .env(
+            "WASM_PACK_WASM_BINDGEN_URL",
+            "http://mirror.test/wasm-bindgen/{version}.{ext}",
+        )
+        .env(
+            "WASM_PACK_WASM_OPT_URL",
+            "http://mirror.test/binaryen/{version}.{ext}",
+        )
+        .arg("prefetch")
+        .assert()
+        .success()
+        .stderr(predicates::str::contains("Prefetched wasm-bindgen 0.2.74"))
+        .stderr(predicates::str::contains("Prefetched wasm-opt version_90"));
+}
@@ -0,0 +1,124 @@
+use wasm_pack::size::{self, FileSize, Quality, SizeBudget, SizeReport};
+
+/// A module importing one function and defining two more, the second of which
+/// is named `big` in the name section.
+fn wasm() -> Vec<u8> {
+    let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+    // type section: one `() -> ()` type
+    wasm.extend(&[1, 4, 1, 0x60, 0, 0]);
+    // import section: function `m.f`
+    wasm.extend(&[2, 7, 1, 1, b'm', 1, b'f', 0, 0]);
+    // function section: two functions of type 0
+    wasm.extend(&[3, 3, 2, 0, 0]);
+    // code section: bodies of 2 and 4 bytes
+    wasm.extend(&[10, 9, 2, 2, 0, 0x0b, 4, 0, 1, 1, 0x0b]);
+    // name section: function 2 is called `big`
+    wasm.extend(&[
+        0, 13, 4, b'n', b'a', b'm', b'e', 1, 6, 1, 2, 3, b'b', b'i', b'g',
+    ]);
+    wasm
+}
+
+#[test]
+fn it_reports_section_sizes() {
+    let wasm = wasm();
+    let report = FileSize::new(&wasm).unwrap();
+    assert_eq!(report.size, wasm.len() as u64);
+    assert!(report.gzip_size > 0);
+    assert!(report.brotli_size > 0);
+
+    let sections: Vec<_> = report
+        .sections
+        .iter()
+        .map(|s| (s.name.as_str(), s.custom, s.size))
+        .collect();
+    assert_eq!(
+        sections,
+        vec![
+            ("type", false, 6),
+            ("import", false, 9),
+            ("function", false, 5),
+            ("code", false, 11),
+            ("name", true, 15),
+        ]
+    );
+    let total: u64 = report.sections.iter().map(|s| s.size).sum();
+    assert_eq!(total + 8, report.size);
+}
+
+#[test]
+fn it_names_the_largest_functions() {
+    let report = FileSize::new(&wasm()).unwrap();
+    let functions: Vec<_> = report
+        .largest_functions
+        .iter()
+        .map(|f| (f.name.as_str(), f.size))
+        .collect();
+    assert_eq!(functions, vec![("big", 4), ("func[1]", 2)]);
+}
+
+#[test]
+fn it_measures_the_same_sections_at_any_quality() {
+    let wasm = wasm();
+    let fast = FileSize::measure(&wasm, Quality::Fast).unwrap();
+    let best = FileSize::measure(&wasm, Quality::Best).unwrap();
+    assert_eq!(fast.size, best.size);
+    assert_eq!(fast.sections.len(), best.sections.len());
+    assert!(fast.gzip_size > 0);
+    assert!(fast.brotli_size > 0);
+}
+
+#[test]
+fn it_rejects_files_that_are_not_wasm() {
+    assert!(FileSize::new(b"not wasm at all").is_err());
+}
+
+fn report(size: u64, gzip_size: u64, code: u64) -> SizeReport {
+    let mut file = FileSize::new(&wasm()).unwrap();
+    file.path = "hello_bg.wasm".to_string();
+    file.size = size;
+    file.gzip_size = gzip_size;
+    file.sections
+        .iter_mut()
+        .find(|s| s.name == "code")
+        .unwrap()
+        .size = code;
+    SizeReport { files: vec![file] }
+}
+
+#[test]
+fn it_parses_sizes_with_units() {
+    assert_eq!(size::parse_size("512").unwrap(), 512);
+    assert_eq!(size::parse_size("512 B").unwrap(), 512);
+    assert_eq!(size::parse_size("200KiB").unwrap(), 200 * 1024);
+    assert_eq!(size::parse_size("1.5 MB").unwrap(), 1_500_000);
+    assert!(size::parse_size("1 GB").is_err());
+    assert!(size::parse_size("lots").is_err());
+}
+
+#[test]
+fn it_fails_when_the_size_budget_is_exceeded() {
+    let budget = SizeBudget {
+        raw: Some(1000),
+        gzip: Some(500),
+    };
+    assert!(budget.check(&report(1000, 500, 10)).is_ok());
+
+    let err = budget
+        .check(&report(1001, 600, 10))
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("hello_bg.wasm is 1001 B raw, which exceeds the budget of 1000 B by 1 B"));
+    assert!(err.contains("hello_bg.wasm is 600 B gzip, which exceeds the budget of 500 B by 100 B"));
+}
+
+#[test]
+fn it_compares_reports_per_file_and_section() {
+    let old = report(2048, 1024, 100);
+    let new = report(3072, 1000, 1124);
+    let comparison = new.compare(&old);
+    let lines: Vec<_> = comparison.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with("hello_bg.wasm: 3.00 KiB (+1.00 KiB), gzip: 1000 B (-24 B), "));
+    assert_eq!(lines[1].trim(), "+1.00 KiB  code");
+}
@@ -1,31 +1,21 @@
-use std::{fs, panic};
+use std::{env, fs, panic};
+use tempfile;
 use wasm_pack::stamps;
 
 fn run_test<T>(test: T) -> ()
 where
     T: FnOnce() -> () + panic::UnwindSafe,
 {
-    before();
+    // Each test gets a state file of its own, which is deleted along with its
+    // lock file when the directory is.
+    let dir = tempfile::TempDir::new().unwrap();
+    env::set_var(stamps::STATE_ENV_VAR, dir.path().join("state.json"));
     let result = panic::catch_unwind(|| test());
-    after();
+    env::remove_var(stamps::STATE_ENV_VAR);
+    drop(dir);
     assert!(result.is_ok())
 }
 
-fn before() {
-    remove_stamps_file()
-}
-
-fn after() {
-    remove_stamps_file()
-}
-
-fn remove_stamps_file() {
-    let stamps_file_path = stamps::get_stamps_file_path().unwrap();
-    if stamps_file_path.exists() {
-        fs::remove_file(stamps_file_path).unwrap();
-    }
-}
-
 #[test]
 #[should_panic]
 #[serial]
@@ -69,3 +59,82 @@ fn update_stamp() {
         assert_eq!(stamp_value, "John");
     })
 }
+
+#[test]
+#[serial]
+fn concurrent_saves_keep_every_stamp() {
+    run_test(|| {
+        // ACT
+        let threads: Vec<_> = (0..8)
+            .map(|i| {
+                std::thread::spawn(move || {
+                    stamps::save_stamp_value(format!("Key{}", i), "Value").unwrap();
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        // ASSERT
+        let json = stamps::read_stamps_file_to_json().unwrap();
+        for i in 0..8 {
+            assert_eq!(
+                stamps::get_stamp_value(format!("Key{}", i), &json).unwrap(),
+                "Value"
+            );
+        }
+    })
+}
+
+#[test]
+#[serial]
+fn stamps_of_older_versions_are_migrated() {
+    run_test(|| {
+        // ARRANGE
+        let exe = env::current_exe().unwrap();
+        fs::write(exe.with_extension("stamps"), r#"{ "Foo": "Bar" }"#).unwrap();
+        fs::write(
+            exe.with_extension("stamp"),
+            "created 2021-06-02T10:00:00.123+02:00\nversion 0.9.1",
+        )
+        .unwrap();
+
+        // ACT
+        let json = stamps::read_stamps_file_to_json().unwrap();
+        stamps::save_stamp_value("Baz", "Qux").unwrap();
+
+        // ASSERT
+        assert_eq!(stamps::get_stamp_value("Foo", &json).unwrap(), "Bar");
+        assert_eq!(
+            stamps::get_stamp_value(stamps::WASM_PACK_VERSION_STAMP, &json).unwrap(),
+            "0.9.1"
+        );
+        assert!(!exe.with_extension("stamps").exists());
+        assert!(!exe.with_extension("stamp").exists());
+        let state = fs::read_to_string(stamps::get_stamps_file_path().unwrap()).unwrap();
+        assert!(state.contains(r#""version": 1"#));
+        assert!(state.contains(r#""Foo": "Bar""#));
+        assert!(state.contains(r#""wasm-pack version checked": "2021-06-02T10:00:00.123+02:00""#));
+        assert!(state.contains(r#""Baz": "Qux""#));
+    })
+}
+
+#[test]
+#[serial]
+fn state_of_newer_versions_is_not_overwritten() {
+    run_test(|| {
+        // ARRANGE
+        let path = stamps::get_stamps_file_path().unwrap();
+        fs::write(&path, r#"{ "version": 99, "stamps": {} }"#).unwrap();
+
+        // ACT
+        let error = stamps::save_stamp_value("Foo", "Bar").unwrap_err();
+
+        // ASSERT
+        assert!(error
+            .to_string()
+            .contains("version 99 of wasm-pack's state"));
+        assert!(fs::read_to_string(&path).unwrap().contains("99"));
+    })
+}
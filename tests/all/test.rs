@@ -158,6 +158,39 @@ fn the_headless_flag_requires_a_browser() {
         .stderr(predicates::str::contains("only applies to browser tests"));
 }
 
+#[test]
+fn wasi_tests_cannot_be_combined_with_node_tests() {
+    let fixture = fixture::wasi_hello_world();
+    fixture
+        .wasm_pack()
+        .arg("test")
+        .arg("--wasi")
+        .arg("--node")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "`--wasi` tests cannot be combined with `--node`, `--deno` or browser tests",
+        ));
+}
+
+#[test]
+fn deno_tests_need_a_recent_test_runner() {
+    assert!(wasm_pack::deno::check_test_runner("0.2.84").is_ok());
+    assert!(wasm_pack::deno::check_test_runner("0.2.92").is_ok());
+    let err = wasm_pack::deno::check_test_runner("0.2.74").unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("wasm-bindgen 0.2.74 can't run tests in Deno"));
+}
+
+#[test]
+fn deno_tests_run_the_deno_that_was_found() {
+    let deno = env::temp_dir().join("deno-bin").join("deno");
+    let path = wasm_pack::deno::path_with(&deno).unwrap();
+    let first = env::split_paths(&path).next().unwrap();
+    assert_eq!(first, env::temp_dir().join("deno-bin"));
+}
+
 #[test]
 fn complains_about_missing_wasm_bindgen_test_dependency() {
     let fixture = fixture::Fixture::new();
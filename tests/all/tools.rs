@@ -0,0 +1,253 @@
+use assert_cmd::prelude::*;
+use utils;
+
+#[test]
+fn tools_list_shows_the_cached_tools() {
+    let fixture = utils::fixture::not_a_crate();
+    fixture
+        .file("cache/wasm-snip-cargo-install-0.4.0/wasm-snip", "")
+        .file("cache/wasm-opt-0123456789abcdef/wasm-opt", "");
+    fixture
+        .wasm_pack()
+        .env("WASM_PACK_CACHE", fixture.path.join("cache"))
+        .arg("tools")
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicates::str::is_match(r"wasm-snip +0\.4\.0 +cargo install").unwrap())
+        .stdout(predicates::str::is_match(r"wasm-opt +- +download").unwrap());
+}
+
+#[test]
+fn tools_prune_removes_the_tools_no_build_used_recently() {
+    let fixture = utils::fixture::not_a_crate();
+    fixture
+        .file("cache/wasm-snip-cargo-install-0.4.0/wasm-snip", "")
+        .file("cache/wasm-snip-cargo-install-0.3.0/wasm-snip", "")
+        .file(
+            "cache/tools.json",
+            r#"{
+                "wasm-snip-cargo-install-0.3.0": {
+                    "tool": "wasm-snip",
+                    "version": "0.3.0",
+                    "source": "cargo-install",
+                    "last_used": "2019-01-01T00:00:00+00:00"
+                },
+                "wasm-snip-cargo-install-0.4.0": {
+                    "tool": "wasm-snip",
+                    "version": "0.4.0",
+                    "source": "cargo-install",
+                    "last_used": "2999-01-01T00:00:00+00:00"
+                }
+            }"#,
+        );
+    fixture
+        .wasm_pack()
+        .env("WASM_PACK_CACHE", fixture.path.join("cache"))
+        .arg("tools")
+        .arg("prune")
+        .assert()
+        .success()
+        .stderr(predicates::str::contains("Removed wasm-snip 0.3.0"));
+    assert!(!fixture
+        .path
+        .join("cache/wasm-snip-cargo-install-0.3.0")
+        .exists());
+    assert!(fixture
+        .path
+        .join("cache/wasm-snip-cargo-install-0.4.0")
+        .exists());
+    let index = std::fs::read_to_string(fixture.path.join("cache/tools.json")).unwrap();
+    assert!(!index.contains("0.3.0"));
+}
+
+#[test]
+fn tools_which_prints_the_cached_binary() {
+    let fixture = utils::fixture::not_a_crate();
+    fixture.file("cache/wasm-snip-cargo-install-0.4.0/wasm-snip", "");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let binary = fixture
+            .path
+            .join("cache/wasm-snip-cargo-install-0.4.0/wasm-snip");
+        std::fs::set_permissions(binary, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    fixture
+        .wasm_pack()
+        .env("WASM_PACK_CACHE", fixture.path.join("cache"))
+        .arg("tools")
+        .arg("which")
+        .arg("wasm-snip@0.4.0")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(
+            fixture
+                .path
+                .join("cache/wasm-snip-cargo-install-0.4.0/wasm-snip")
+                .to_str()
+                .unwrap(),
+        ));
+}
+
+#[test]
+fn tools_which_fails_for_tools_that_are_not_installed() {
+    let fixture = utils::fixture::not_a_crate();
+    fixture
+        .wasm_pack()
+        .env("WASM_PACK_CACHE", fixture.path.join("cache"))
+        .arg("--offline")
+        .arg("tools")
+        .arg("which")
+        .arg("wasm-snip@0.4.0")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "wasm-snip 0.4.0 isn't installed (unavailable: wasm-snip v0.4.0 is not installed, \
+             and wasm-pack is offline)",
+        ));
+}
+
+#[test]
+fn tools_export_and_import_copy_the_cache() {
+    let fixture = utils::fixture::not_a_crate();
+    fixture
+        .file("cache/wasm-snip-cargo-install-0.4.0/wasm-snip", "snip")
+        .file(
+            "cache/tools.json",
+            r#"{
+                "wasm-snip-cargo-install-0.4.0": {
+                    "tool": "wasm-snip",
+                    "version": "0.4.0",
+                    "source": "cargo-install",
+                    "last_used": "2021-01-01T00:00:00+00:00"
+                }
+            }"#,
+        );
+    fixture
+        .wasm_pack()
+        .env("WASM_PACK_CACHE", fixture.path.join("cache"))
+        .arg("tools")
+        .arg("export")
+        .arg("tools.tar.gz")
+        .assert()
+        .success()
+        .stderr(predicates::str::contains("Exported 1 tools"));
+    fixture
+        .wasm_pack()
+        .env("WASM_PACK_CACHE", fixture.path.join("other-cache"))
+        .arg("tools")
+        .arg("import")
+        .arg("tools.tar.gz")
+        .assert()
+        .success()
+        .stderr(predicates::str::contains("Imported 1 tools"));
+
+    let snip = fixture
+        .path
+        .join("other-cache/wasm-snip-cargo-install-0.4.0/wasm-snip");
+    assert_eq!(std::fs::read_to_string(snip).unwrap(), "snip");
+    let index = std::fs::read_to_string(fixture.path.join("other-cache/tools.json")).unwrap();
+    assert!(index.contains("2021-01-01T00:00:00+00:00"));
+}
+
+#[test]
+fn tools_import_keeps_the_local_stamps() {
+    let fixture = utils::fixture::not_a_crate();
+    fixture
+        .file("cache/wasm-snip-cargo-install-0.4.0/wasm-snip", "snip")
+        .file(
+            "state.json",
+            r#"{
+                "version": 1,
+                "stamps": { "chromedriver version": "old", "geckodriver version": "0.30.0" }
+            }"#,
+        )
+        .file(
+            "other-state.json",
+            r#"{ "version": 1, "stamps": { "chromedriver version": "new" } }"#,
+        );
+    fixture
+        .wasm_pack()
+        .env("WASM_PACK_CACHE", fixture.path.join("cache"))
+        .env("WASM_PACK_STATE", fixture.path.join("state.json"))
+        .arg("tools")
+        .arg("export")
+        .arg("tools.tar.gz")
+        .assert()
+        .success();
+    fixture
+        .wasm_pack()
+        .env("WASM_PACK_CACHE", fixture.path.join("other-cache"))
+        .env("WASM_PACK_STATE", fixture.path.join("other-state.json"))
+        .arg("tools")
+        .arg("import")
+        .arg("tools.tar.gz")
+        .assert()
+        .success();
+
+    let state = std::fs::read_to_string(fixture.path.join("other-state.json")).unwrap();
+    assert!(state.contains(r#""chromedriver version": "new""#));
+    assert!(state.contains(r#""geckodriver version": "0.30.0""#));
+}
+
+#[test]
+fn tools_export_and_import_copy_the_stamps() {
+    let fixture = utils::fixture::not_a_crate();
+    fixture.file(
+        "state.json",
+        r#"{
+            "version": 1,
+            "stamps": {
+                "chromedriver version": "89.0.4389.23",
+                "geckodriver version": "0.30.0",
+                "wasm-pack version checked": "2021-01-01T00:00:00+00:00"
+            }
+        }"#,
+    );
+    fixture
+        .wasm_pack()
+        .env("WASM_PACK_CACHE", fixture.path.join("cache"))
+        .env("WASM_PACK_STATE", fixture.path.join("state.json"))
+        .arg("tools")
+        .arg("export")
+        .arg("tools.tar.gz")
+        .assert()
+        .success();
+    fixture
+        .wasm_pack()
+        .env("WASM_PACK_CACHE", fixture.path.join("other-cache"))
+        .env("WASM_PACK_STATE", fixture.path.join("other-state.json"))
+        .arg("tools")
+        .arg("import")
+        .arg("tools.tar.gz")
+        .assert()
+        .success();
+
+    let read_stamps = |file: &str| {
+        let state = std::fs::read_to_string(fixture.path.join(file)).unwrap();
+        serde_json::from_str::<serde_json::Value>(&state).unwrap()["stamps"].clone()
+    };
+    assert_eq!(read_stamps("other-state.json"), read_stamps("state.json"));
+}
+
+#[test]
+fn tools_install_refuses_archives_without_a_digest_when_digests_are_required() {
+    let fixture = utils::fixture::not_a_crate();
+    fixture.file("binaryen.tar.gz", "not checked");
+    fixture
+        .wasm_pack()
+        .env("WASM_PACK_CACHE", fixture.path.join("cache"))
+        .env("WASM_PACK_CONFIG", fixture.path.join("config.toml"))
+        .env(
+            "WASM_PACK_WASM_OPT_URL",
+            format!("file://{}", fixture.path.join("binaryen.tar.gz").display()),
+        )
+        .env("WASM_PACK_REQUIRE_CHECKSUMS", "1")
+        .arg("tools")
+        .arg("install")
+        .arg("wasm-opt@version_90")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("digests are required"));
+}
@@ -348,6 +348,9 @@ impl Fixture {
         let mut cmd = Command::cargo_bin(env!("CARGO_PKG_NAME")).unwrap();
         cmd.current_dir(&self.path);
         cmd.env("WASM_PACK_CACHE", self.cache_dir());
+        // Don't pass on the state file that the stamps tests point this
+        // process at.
+        cmd.env_remove("WASM_PACK_STATE");
         cmd
     }
 
@@ -423,6 +426,37 @@ pub fn no_cdylib() -> Fixture {
     fixture
 }
 
+pub fn wasi_hello_world() -> Fixture {
+    let fixture = Fixture::new();
+    fixture
+        .readme()
+        .file(
+            "Cargo.toml",
+            r#"
+                [package]
+                authors = ["The wasm-pack developers"]
+                description = "so awesome rust+wasm package"
+                license = "WTFPL"
+                name = "wasi-hello-world"
+                repository = "https://github.com/rustwasm/wasm-pack.git"
+                version = "0.1.0"
+
+                [[bin]]
+                name = "hello"
+                path = "src/main.rs"
+            "#,
+        )
+        .file(
+            "src/main.rs",
+            r#"
+                fn main() {
+                    println!("Hello from WASI!");
+                }
+            "#,
+        );
+    fixture
+}
+
 pub fn not_a_crate() -> Fixture {
     let fixture = Fixture::new();
     fixture.file("README.md", "This is not a Rust crate!");
@@ -567,6 +601,57 @@ pub fn wbg_test_node() -> Fixture {
     fixture
 }
 
+pub fn cdylib_workspace() -> Fixture {
+    let fixture = Fixture::new();
+    fixture.file(
+        "Cargo.toml",
+        r#"
+            [workspace]
+            members = ["member_a", "member_b", "not_wasm"]
+        "#,
+    );
+    for member in &["member_a", "member_b", "not_wasm"] {
+        let crate_type = if *member == "not_wasm" {
+            "rlib"
+        } else {
+            "cdylib"
+        };
+        fixture
+            .file(
+                Path::new(member).join("Cargo.toml"),
+                format!(
+                    r#"
+                        [package]
+                        authors = ["The wasm-pack developers"]
+                        description = "so awesome rust+wasm package"
+                        license = "WTFPL"
+                        name = "{}"
+                        repository = "https://github.com/rustwasm/wasm-pack.git"
+                        version = "0.1.0"
+
+                        [lib]
+                        crate-type = ["{}"]
+
+                        [dependencies]
+                        wasm-bindgen = "0.2"
+                    "#,
+                    member, crate_type
+                ),
+            )
+            .file(
+                Path::new(member).join("src").join("lib.rs"),
+                r#"
+                    extern crate wasm_bindgen;
+                    use wasm_bindgen::prelude::*;
+
+                    #[wasm_bindgen]
+                    pub fn hello() -> u32 { 42 }
+                "#,
+            );
+    }
+    fixture
+}
+
 pub fn transitive_dependencies() -> Fixture {
     fn project_main_fixture(fixture: &mut Fixture) {
         fixture.file(PathBuf::from("main/README"), "# Main Fixture\n");
@@ -0,0 +1,94 @@
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+use utils::fixture;
+use wasm_pack::manifest;
+use wasm_pack::watch::{self, Snapshot};
+
+#[test]
+fn watch_paths_include_path_dependencies() {
+    let fixture = fixture::transitive_dependencies();
+    let crate_data = manifest::CrateData::new(&fixture.path.join("main"), None).unwrap();
+    let paths = crate_data.watch_paths();
+
+    for project in &["main", "project_a", "project_b"] {
+        let root = fs::canonicalize(fixture.path.join(project)).unwrap();
+        assert!(
+            paths.contains(&root.join("Cargo.toml")),
+            "{}/Cargo.toml is not watched: {:?}",
+            project,
+            paths
+        );
+        assert!(
+            paths.contains(&root.join("src")),
+            "{}/src is not watched: {:?}",
+            project,
+            paths
+        );
+    }
+}
+
+#[test]
+fn watch_paths_include_the_workspace_manifest_and_lockfile() {
+    let fixture = fixture::Fixture::new();
+    fixture
+        .file(
+            "Cargo.toml",
+            r#"
+                [workspace]
+                members = ["member"]
+            "#,
+        )
+        .file(
+            "member/Cargo.toml",
+            r#"
+                [package]
+                authors = ["The wasm-pack developers"]
+                description = "so awesome rust+wasm package"
+                license = "WTFPL"
+                name = "member"
+                repository = "https://github.com/rustwasm/wasm-pack.git"
+                version = "0.1.0"
+
+                [lib]
+                crate-type = ["cdylib"]
+            "#,
+        )
+        .file("member/src/lib.rs", "");
+    let crate_data = manifest::CrateData::new(&fixture.path.join("member"), None).unwrap();
+    let paths = crate_data.watch_paths();
+
+    let root = fs::canonicalize(&fixture.path).unwrap();
+    assert!(paths.contains(&root.join("Cargo.toml")), "{:?}", paths);
+    assert!(paths.contains(&root.join("Cargo.lock")), "{:?}", paths);
+}
+
+#[test]
+fn snapshot_reports_changed_sources() {
+    let fixture = fixture::js_hello_world();
+    let crate_data = manifest::CrateData::new(&fixture.path, None).unwrap();
+    let paths = crate_data.watch_paths();
+    let before = Snapshot::take(&paths);
+
+    // Make sure the modification time actually moves forward.
+    thread::sleep(Duration::from_millis(1100));
+    fixture.file("src/lib.rs", "// changed\n");
+    fixture.file("src/new.rs", "// added\n");
+
+    let after = Snapshot::take(&paths);
+    let changed: Vec<_> = before
+        .changed_paths(&after)
+        .iter()
+        .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+        .collect();
+    assert_eq!(changed, vec!["lib.rs", "new.rs"]);
+    assert!(after.changed_paths(&after).is_empty());
+}
+
+#[test]
+fn manifest_and_lockfile_changes_need_a_full_build() {
+    assert!(watch::needs_full_build(Path::new("/ws/Cargo.toml")));
+    assert!(watch::needs_full_build(Path::new("/ws/Cargo.lock")));
+    assert!(!watch::needs_full_build(Path::new("/ws/src/lib.rs")));
+}